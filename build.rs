@@ -0,0 +1,95 @@
+//! Compiles the GLSL shaders with shaderc when the `compile-shaders` feature is enabled, so
+//! that changes to them take effect without running `src/vulkan/shaders/compile.sh`.
+//! Without the feature, the SPIR-V binaries checked in next to the sources are used.
+
+fn main() {
+    #[cfg(feature = "compile-shaders")]
+    if let Err(e) = shaders::compile() {
+        panic!("Failed to compile the shaders: {}", e);
+    }
+}
+
+#[cfg(feature = "compile-shaders")]
+mod shaders {
+    use std::error::Error;
+    use std::path::{Path, PathBuf};
+
+    const SOURCE_DIR: &str = "src/vulkan/shaders";
+
+    // The sources, the macros they are compiled with and the names of their binaries, like
+    // in compile.sh.
+    const SHADERS: &[(&str, &[&str], &str)] = &[
+        ("shader.vert", &[], "vert"),
+        ("shader.vert", &["MULTIVIEW"], "vert_multiview"),
+        ("shader.frag", &[], "frag"),
+        ("shader.frag", &["STATS"], "frag_stats"),
+        ("shader.frag", &["COLOR_TRANSFORMS"], "frag_color"),
+        ("shader.frag", &["LUT"], "frag_lut"),
+        (
+            "shader.frag",
+            &["COLOR_TRANSFORMS", "LUT"],
+            "frag_color_lut",
+        ),
+        (
+            "shader.frag",
+            &["STATS", "COLOR_TRANSFORMS"],
+            "frag_stats_color",
+        ),
+        ("shader.frag", &["STATS", "LUT"], "frag_stats_lut"),
+        (
+            "shader.frag",
+            &["STATS", "COLOR_TRANSFORMS", "LUT"],
+            "frag_stats_color_lut",
+        ),
+        ("shader.frag", &["INTEGER_INPUTS"], "frag_integer"),
+        (
+            "shader.frag",
+            &["INTEGER_INPUTS", "STATS"],
+            "frag_stats_integer",
+        ),
+        ("metrics.comp", &[], "metrics"),
+        ("metrics.comp", &["FRAME_INPUTS"], "metrics_frame"),
+        ("strip.frag", &[], "strip"),
+        ("inspect.comp", &[], "inspect"),
+        ("ssim.comp", &[], "ssim"),
+        ("histogram.comp", &[], "histogram"),
+        ("handle.vert", &[], "handle_vert"),
+        ("handle.frag", &[], "handle_frag"),
+        ("grid.frag", &[], "grid"),
+        ("crosshair.frag", &[], "crosshair"),
+        ("labels.vert", &[], "labels_vert"),
+        ("labels.frag", &[], "labels_frag"),
+        ("vectors.vert", &[], "vectors_vert"),
+        ("vectors.frag", &[], "vectors_frag"),
+    ];
+
+    pub(crate) fn compile() -> Result<(), Box<dyn Error>> {
+        let out_dir = PathBuf::from(std::env::var("OUT_DIR")?);
+        let compiler = shaderc::Compiler::new()?;
+        println!("cargo:rerun-if-changed={}", SOURCE_DIR);
+
+        for (source, macros, name) in SHADERS {
+            let path = Path::new(SOURCE_DIR).join(source);
+            let text = std::fs::read_to_string(&path)?;
+            let kind = match path.extension().and_then(|e| e.to_str()) {
+                Some("vert") => shaderc::ShaderKind::Vertex,
+                Some("frag") => shaderc::ShaderKind::Fragment,
+                Some("comp") => shaderc::ShaderKind::Compute,
+                _ => return Err(format!("Unknown shader stage of {}.", source).into()),
+            };
+
+            let mut options = shaderc::CompileOptions::new()?;
+            for name in *macros {
+                options.add_macro_definition(name, None);
+            }
+            let artifact =
+                compiler.compile_into_spirv(&text, kind, source, "main", Some(&options))?;
+            std::fs::write(
+                out_dir.join(format!("{}.spv", name)),
+                artifact.as_binary_u8(),
+            )?;
+        }
+
+        Ok(())
+    }
+}
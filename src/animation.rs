@@ -0,0 +1,162 @@
+//! Smooth divider movement for interactive tools.
+//!
+//! A [`DividerAnimator`] eases the divider from where it is towards a target, e.g. when a
+//! key snaps it to the center or to an edge. The caller ticks it once per frame with the
+//! time that passed, and passes the position it returns as
+//! [`CompareInfo::divider_position`](crate::CompareInfo::divider_position).
+
+use std::time::Duration;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The curve a [`DividerAnimator`] moves along.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Easing {
+    /// At a constant speed.
+    Linear,
+    /// Speeding up from the start and slowing down towards the target.
+    #[default]
+    EaseInOut,
+    /// Like a damped spring, overshooting the target slightly before settling on it.
+    Spring,
+}
+
+impl Easing {
+    /// Returns the progress towards the target at the relative time `t`, in the range
+    /// `[0.0, 1.0]`. Both start at `0.0` and end at `1.0`.
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Self::Linear => t,
+            Self::EaseInOut => t * t * (3.0 - 2.0 * t),
+            // Decayed far enough by the end that snapping onto the target isn't visible.
+            Self::Spring if t >= 1.0 => 1.0,
+            Self::Spring => 1.0 - (-6.0 * t).exp() * (2.5 * std::f32::consts::TAU * t).cos(),
+        }
+    }
+}
+
+/// Moves the divider towards a target over time.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DividerAnimator {
+    start: f32,
+    target: f32,
+    duration: Duration,
+    elapsed: Duration,
+    easing: Easing,
+}
+
+impl DividerAnimator {
+    /// Creates an animator resting at the position.
+    pub fn new(position: f32) -> Self {
+        Self {
+            start: position,
+            target: position,
+            duration: Duration::ZERO,
+            elapsed: Duration::ZERO,
+            easing: Easing::default(),
+        }
+    }
+
+    /// Starts moving from the current position to the target, replacing any animation in
+    /// progress. A zero duration jumps to the target.
+    pub fn animate_to(&mut self, target: f32, duration: Duration, easing: Easing) {
+        self.start = self.position();
+        self.target = target;
+        self.duration = duration;
+        self.elapsed = Duration::ZERO;
+        self.easing = easing;
+    }
+
+    /// Places the divider at the position right away, e.g. while the user drags it.
+    pub fn set_position(&mut self, position: f32) {
+        *self = Self::new(position);
+    }
+
+    /// Advances the animation by the time that passed since the last tick, and returns the
+    /// new position.
+    pub fn tick(&mut self, delta: Duration) -> f32 {
+        self.elapsed = (self.elapsed + delta).min(self.duration);
+        self.position()
+    }
+
+    /// Returns the current position, in the range `[0.0, 1.0]` even while a spring
+    /// overshoots.
+    pub fn position(&self) -> f32 {
+        let progress = match self.duration.is_zero() {
+            true => 1.0,
+            false => {
+                let t = self.elapsed.as_secs_f32() / self.duration.as_secs_f32();
+                self.easing.apply(t)
+            }
+        };
+        (self.start + (self.target - self.start) * progress).clamp(0.0, 1.0)
+    }
+
+    /// Returns the position the divider is moving towards.
+    pub fn target(&self) -> f32 {
+        self.target
+    }
+
+    /// Returns whether the divider hasn't reached its target yet, i.e. whether the caller
+    /// needs to keep redrawing.
+    pub fn is_animating(&self) -> bool {
+        self.elapsed < self.duration
+    }
+}
+
+impl Default for DividerAnimator {
+    fn default() -> Self {
+        Self::new(0.5)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EASINGS: [Easing; 3] = [Easing::Linear, Easing::EaseInOut, Easing::Spring];
+
+    // The relative times from 0.0 to 1.0 in steps of a hundredth.
+    fn steps() -> impl Iterator<Item = f32> {
+        (0..=100).map(|i| i as f32 / 100.0)
+    }
+
+    #[test]
+    fn starts_at_zero_and_ends_at_one() {
+        for easing in EASINGS {
+            assert_eq!(easing.apply(0.0), 0.0, "{:?}", easing);
+            assert_eq!(easing.apply(1.0), 1.0, "{:?}", easing);
+        }
+    }
+
+    #[test]
+    fn clamps_the_time() {
+        for easing in EASINGS {
+            assert_eq!(easing.apply(-1.0), 0.0, "{:?}", easing);
+            assert_eq!(easing.apply(2.0), 1.0, "{:?}", easing);
+        }
+    }
+
+    #[test]
+    fn linear_and_ease_in_out_are_monotonic() {
+        for easing in [Easing::Linear, Easing::EaseInOut] {
+            let values: Vec<f32> = steps().map(|t| easing.apply(t)).collect();
+            assert!(values.is_sorted(), "{:?}", easing);
+            assert!(
+                values.iter().all(|v| (0.0..=1.0).contains(v)),
+                "{:?}",
+                easing
+            );
+        }
+    }
+
+    #[test]
+    fn spring_overshoots_and_settles() {
+        let values: Vec<f32> = steps().map(|t| Easing::Spring.apply(t)).collect();
+        assert!(values.iter().any(|v| *v > 1.0));
+        assert!((values[99] - 1.0).abs() < 0.01);
+    }
+}
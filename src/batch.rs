@@ -0,0 +1,404 @@
+//! Batched comparisons of many image pairs, recorded into a single command buffer.
+//!
+//! Every pair is rendered into its own output image or into a region of a shared output
+//! (an atlas), and its error metrics are computed on the GPU alongside. The pipelines are
+//! bound once per batch, which keeps large regression suites cheap to record.
+
+use anyhow::{Result, anyhow};
+use std::collections::HashMap;
+use std::sync::Arc;
+use vulkanalia::prelude::v1_3::*;
+
+use crate::vulkan::{
+    descriptors::{
+        MAX_INPUTS, create_descriptor_pool, create_descriptor_set, create_input_descriptor_set,
+        input_bindings, update_descriptor_sets, update_metrics_descriptor_set,
+    },
+    memory::create_buffer,
+    push_constants::MetricsPushConstantBuffer,
+    render_pass::create_framebuffer,
+};
+use crate::{
+    Color, CompareInfo, InputBinding, PipelineKind, Recording, RenderTargetComparator,
+    check_mask_input, check_split_positions,
+};
+
+/// The amount of workgroups dispatched per pair. Each produces one partial result.
+const METRICS_WORKGROUPS: (u32, u32) = (8, 8);
+const METRICS_PARTIALS: usize = (METRICS_WORKGROUPS.0 * METRICS_WORKGROUPS.1) as usize;
+
+// Mirrors the `Partial` struct of the metrics compute shader (std430 layout).
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct MetricsPartial {
+    squared_error_sum: [f32; 4],
+    max_difference: [f32; 4],
+    pixel_count: u32,
+    _padding: [u32; 3],
+}
+
+/// A single pair of images to compare as part of a batch.
+#[derive(Clone, Copy, Debug)]
+pub struct BatchItem {
+    /// The two input image views to compare.
+    pub in_image_views: [vk::ImageView; 2],
+    /// The output image view to render into. Items sharing an output view are rendered
+    /// into their regions of it within one render pass.
+    pub out_image_view: vk::ImageView,
+    /// The extent of the output image.
+    pub out_extent: vk::Extent2D,
+    /// The region of the output image to render into. Defaults to the whole output.
+    pub region: Option<vk::Rect2D>,
+}
+
+/// Error metrics of a single compared pair. Values are per RGBA channel, in the units
+/// of the sampled image data (`[0.0, 1.0]` for normalized formats).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PairMetrics {
+    /// The mean squared error.
+    pub mse: [f32; 4],
+    /// The largest absolute difference of any pixel.
+    pub max_difference: [f32; 4],
+}
+
+impl PairMetrics {
+    /// Returns the peak signal-to-noise ratio in decibels, for a peak value of `1.0`.
+    /// Infinite for channels without any error.
+    pub fn psnr(&self) -> [f32; 4] {
+        self.mse.map(|mse| -10.0 * mse.log10())
+    }
+}
+
+/// The resources backing a recorded batch comparison.
+///
+/// Must be kept alive until the command buffer it was recorded into has finished executing.
+/// The metrics can be read back afterwards.
+#[derive(Debug)]
+pub struct CompareBatch {
+    device: Arc<Device>,
+    descriptor_pool: vk::DescriptorPool,
+    framebuffers: Vec<vk::Framebuffer>,
+    metrics_buffer: vk::Buffer,
+    metrics_memory: vk::DeviceMemory,
+    len: usize,
+}
+
+impl Drop for CompareBatch {
+    fn drop(&mut self) {
+        unsafe {
+            for framebuffer in &self.framebuffers {
+                self.device.destroy_framebuffer(*framebuffer, None);
+            }
+            self.device.destroy_buffer(self.metrics_buffer, None);
+            self.device.free_memory(self.metrics_memory, None);
+            self.device
+                .destroy_descriptor_pool(self.descriptor_pool, None);
+        }
+    }
+}
+
+impl CompareBatch {
+    /// Returns the amount of compared pairs.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the batch contains no pairs.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Reads back the metrics of every pair, in the order the items were supplied.
+    ///
+    /// # Safety
+    ///
+    /// The command buffer the batch was recorded into must have finished executing.
+    pub unsafe fn metrics(&self) -> Result<Vec<PairMetrics>> {
+        let count = self.len * METRICS_PARTIALS;
+        let size = (count * std::mem::size_of::<MetricsPartial>()) as vk::DeviceSize;
+
+        let partials = unsafe {
+            let memory = self.device.map_memory(
+                self.metrics_memory,
+                0,
+                size,
+                vk::MemoryMapFlags::empty(),
+            )?;
+            let partials =
+                std::slice::from_raw_parts(memory as *const MetricsPartial, count).to_vec();
+            self.device.unmap_memory(self.metrics_memory);
+            partials
+        };
+
+        Ok(partials
+            .chunks_exact(METRICS_PARTIALS)
+            .map(reduce_partials)
+            .collect())
+    }
+}
+
+pub(crate) fn reduce_partials(partials: &[MetricsPartial]) -> PairMetrics {
+    let mut squared_error_sum = [0.0_f64; 4];
+    let mut max_difference = [0.0_f32; 4];
+    let mut pixel_count = 0_u64;
+
+    for partial in partials {
+        for (sum, value) in squared_error_sum.iter_mut().zip(partial.squared_error_sum) {
+            *sum += value as f64;
+        }
+        for (max, value) in max_difference.iter_mut().zip(partial.max_difference) {
+            *max = max.max(value);
+        }
+        pixel_count += partial.pixel_count as u64;
+    }
+
+    let pixel_count = pixel_count.max(1) as f64;
+    PairMetrics {
+        mse: squared_error_sum.map(|sum| (sum / pixel_count) as f32),
+        max_difference,
+    }
+}
+
+impl RenderTargetComparator {
+    /// Records the comparison of every item into `info.command_buffer` and computes the
+    /// metrics of each pair. The divider settings of `info` apply to all items.
+    ///
+    /// The output images must be compatible with the comparator's format. Descriptor sets
+    /// are allocated from a pool owned by the returned [`CompareBatch`], so the comparator's
+    /// descriptor pool is not used. Requires `memory_properties` to have been provided
+    /// at creation.
+    ///
+    /// # Safety
+    ///
+    /// The input images must be in `SHADER_READ_ONLY_OPTIMAL` layout when the command buffer
+    /// executes, and the returned `CompareBatch` must outlive its execution.
+    pub unsafe fn compare_batch<I>(&self, info: &CompareInfo, items: I) -> Result<CompareBatch>
+    where
+        I: IntoIterator<Item = BatchItem>,
+    {
+        let items = items.into_iter().collect::<Vec<_>>();
+        if items.is_empty() {
+            return Err(anyhow!("A batch comparison needs at least one item."));
+        }
+        check_split_positions(info, 2)?;
+        check_mask_input(info, 2)?;
+        if self.multiview {
+            return Err(anyhow!(
+                "Batch comparisons are not available with multiview."
+            ));
+        }
+        if self.ycbcr_conversion.is_some() {
+            return Err(anyhow!(
+                "Batch comparisons are not available with a YCbCr conversion."
+            ));
+        }
+        self.check_float_reads("A batch comparison")?;
+        self.check_descriptor_sets("A batch comparison")?;
+        if info.input_base != 0 {
+            return Err(anyhow!(
+                "Batch comparisons bind the inputs of their items, so they take no input base."
+            ));
+        }
+        let render_pass = self.render_pass()?;
+
+        let memory_properties = self.memory_properties.as_ref().ok_or_else(|| {
+            anyhow!("Batch comparisons require memory_properties to be set at creation.")
+        })?;
+
+        let device = &self.device;
+        let count = items.len() as u32;
+
+        // Each item needs a graphics and a metrics descriptor set.
+        let image_sampler_count = count * (MAX_INPUTS as u32 + 2);
+        let descriptor_pool =
+            create_descriptor_pool(device, count * 2, image_sampler_count, count, 0)?;
+
+        // From here on, the batch cleans up everything it owns if recording fails.
+        let mut batch = CompareBatch {
+            device: Arc::clone(device),
+            descriptor_pool,
+            framebuffers: Vec::new(),
+            metrics_buffer: vk::Buffer::null(),
+            metrics_memory: vk::DeviceMemory::null(),
+            len: items.len(),
+        };
+
+        let size = (items.len() * METRICS_PARTIALS * std::mem::size_of::<MetricsPartial>())
+            as vk::DeviceSize;
+        (batch.metrics_buffer, batch.metrics_memory) = create_buffer(
+            device,
+            memory_properties,
+            size,
+            vk::BufferUsageFlags::STORAGE_BUFFER,
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+        )?;
+
+        let mut descriptor_sets = Vec::with_capacity(items.len());
+        let mut metrics_descriptor_sets = Vec::with_capacity(items.len());
+        for item in &items {
+            let descriptor_set = create_input_descriptor_set(
+                device,
+                &descriptor_pool,
+                &self.descriptor_set_layout,
+                MAX_INPUTS as u32,
+            )?;
+            update_descriptor_sets(
+                device,
+                &descriptor_set,
+                &self.sampler,
+                &input_bindings(&item.in_image_views),
+            );
+            descriptor_sets.push(descriptor_set);
+
+            let metrics_descriptor_set = create_descriptor_set(
+                device,
+                &descriptor_pool,
+                &self.metrics_descriptor_set_layout,
+            )?;
+            update_metrics_descriptor_set(
+                device,
+                &metrics_descriptor_set,
+                &self.sampler,
+                &item.in_image_views,
+                &batch.metrics_buffer,
+            );
+            metrics_descriptor_sets.push(metrics_descriptor_set);
+        }
+
+        // Group the items by output, keeping the order in which the outputs first appear.
+        let mut targets: Vec<(vk::ImageView, vk::Extent2D, Vec<usize>)> = Vec::new();
+        let mut target_indices = HashMap::new();
+        for (i, item) in items.iter().enumerate() {
+            let target = *target_indices
+                .entry(item.out_image_view)
+                .or_insert_with(|| {
+                    targets.push((item.out_image_view, item.out_extent, Vec::new()));
+                    targets.len() - 1
+                });
+            targets[target].2.push(i);
+        }
+
+        let command_buffer = info.command_buffer;
+
+        // The metrics are computed first, as dispatches are not allowed inside a render pass.
+        unsafe {
+            device.cmd_bind_pipeline(
+                command_buffer,
+                vk::PipelineBindPoint::COMPUTE,
+                self.metrics_pipeline,
+            );
+
+            for (i, descriptor_set) in metrics_descriptor_sets.iter().enumerate() {
+                device.cmd_bind_descriptor_sets(
+                    command_buffer,
+                    vk::PipelineBindPoint::COMPUTE,
+                    self.metrics_pipeline_layout,
+                    0,
+                    &[*descriptor_set],
+                    &[] as &[u32],
+                );
+
+                let push_buffer = MetricsPushConstantBuffer {
+                    first_partial: (i * METRICS_PARTIALS) as u32,
+                    srgb_inputs: self.srgb_inputs,
+                };
+                device.cmd_push_constants(
+                    command_buffer,
+                    self.metrics_pipeline_layout,
+                    vk::ShaderStageFlags::COMPUTE,
+                    0,
+                    bytemuck::bytes_of(&push_buffer),
+                );
+
+                device.cmd_dispatch(
+                    command_buffer,
+                    METRICS_WORKGROUPS.0,
+                    METRICS_WORKGROUPS.1,
+                    1,
+                );
+            }
+
+            // Make the partial results visible to the host once the command buffer completes.
+            let barrier = vk::BufferMemoryBarrier::builder()
+                .src_access_mask(vk::AccessFlags::SHADER_WRITE)
+                .dst_access_mask(vk::AccessFlags::HOST_READ)
+                .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                .buffer(batch.metrics_buffer)
+                .offset(0)
+                .size(vk::WHOLE_SIZE)
+                .build();
+
+            device.cmd_pipeline_barrier(
+                command_buffer,
+                vk::PipelineStageFlags::COMPUTE_SHADER,
+                vk::PipelineStageFlags::HOST,
+                vk::DependencyFlags::empty(),
+                &[] as &[vk::MemoryBarrier],
+                &[barrier],
+                &[] as &[vk::ImageMemoryBarrier],
+            );
+        }
+
+        let clear_color = info.clear_color.unwrap_or(Color(0.0, 0.0, 0.0, 1.0));
+        let color_clear_value = vk::ClearValue {
+            color: vk::ClearColorValue {
+                float32: [clear_color.0, clear_color.1, clear_color.2, clear_color.3],
+            },
+        };
+        let clear_values = &[color_clear_value];
+        let pipeline = self.pipeline(PipelineKind::Compare(info.mode), Recording::RenderPass)?;
+
+        for (out_image_view, out_extent, indices) in targets {
+            let framebuffer = create_framebuffer(device, render_pass, out_image_view, out_extent)?;
+            batch.framebuffers.push(framebuffer);
+
+            let render_area = vk::Rect2D::builder()
+                .offset(vk::Offset2D::default())
+                .extent(out_extent)
+                .build();
+
+            let begin_info = vk::RenderPassBeginInfo::builder()
+                .render_pass(render_pass)
+                .framebuffer(framebuffer)
+                .render_area(render_area)
+                .clear_values(clear_values)
+                .build();
+
+            unsafe {
+                device.cmd_begin_render_pass(
+                    command_buffer,
+                    &begin_info,
+                    vk::SubpassContents::INLINE,
+                );
+
+                device.cmd_bind_pipeline(command_buffer, vk::PipelineBindPoint::GRAPHICS, pipeline);
+
+                for i in indices {
+                    let region = items[i].region.unwrap_or(render_area);
+                    let viewport = vk::Viewport::builder()
+                        .x(region.offset.x as f32)
+                        .y(region.offset.y as f32)
+                        .width(region.extent.width as f32)
+                        .height(region.extent.height as f32)
+                        .min_depth(0.0)
+                        .max_depth(1.0)
+                        .build();
+
+                    self.cmd_draw_comparison(
+                        command_buffer,
+                        InputBinding::Set(descriptor_sets[i]),
+                        items[i].in_image_views.len(),
+                        viewport,
+                        region,
+                        info,
+                    );
+                }
+
+                device.cmd_end_render_pass(command_buffer);
+            }
+        }
+
+        Ok(batch)
+    }
+}
@@ -0,0 +1,144 @@
+//! Per-input color transforms, applied before the inputs are compared.
+//!
+//! A comparator created with `color_transforms` enabled uses a fragment shader variant that
+//! multiplies the linear values of each input with a matrix from a small uniform buffer.
+//! This corrects the white balance or converts the primaries of one side, so that inputs
+//! rendered in different color spaces can be compared. The transforms apply to everything
+//! the comparison pass shows and its statistics, but not to the metrics of batch
+//! comparisons.
+
+use anyhow::{Result, anyhow};
+use std::sync::Arc;
+use vulkanalia::prelude::v1_3::*;
+
+use crate::RenderTargetComparator;
+use crate::vulkan::{
+    descriptors::{
+        MAX_INPUTS, create_color_transform_descriptor_set_layout, create_descriptor_pool,
+        create_descriptor_set, update_uniform_buffer_descriptor,
+    },
+    memory::create_buffer,
+};
+
+/// A 4x4 matrix of columns, as in GLSL. It transforms `(r, g, b, 1.0)`, so the fourth
+/// column offsets the channels.
+pub type Mat4 = [[f32; 4]; 4];
+
+/// The matrix leaving colors unchanged.
+pub const IDENTITY: Mat4 = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+];
+
+// Mirrors the `ColorTransforms` buffer of the fragment shader (std140 layout).
+type ColorTransformBuffer = [Mat4; MAX_INPUTS];
+
+/// The uniform buffer holding the transforms, and its descriptor.
+#[derive(Debug)]
+pub(crate) struct ColorTransformResources {
+    device: Arc<Device>,
+    pub(crate) descriptor_set_layout: vk::DescriptorSetLayout,
+    descriptor_pool: vk::DescriptorPool,
+    pub(crate) descriptor_set: vk::DescriptorSet,
+    buffer: vk::Buffer,
+    memory: vk::DeviceMemory,
+}
+
+impl Drop for ColorTransformResources {
+    fn drop(&mut self) {
+        unsafe {
+            self.device.destroy_buffer(self.buffer, None);
+            self.device.free_memory(self.memory, None);
+            self.device
+                .destroy_descriptor_pool(self.descriptor_pool, None);
+            self.device
+                .destroy_descriptor_set_layout(self.descriptor_set_layout, None);
+        }
+    }
+}
+
+impl ColorTransformResources {
+    pub(crate) fn new(
+        device: &Arc<Device>,
+        memory_properties: &vk::PhysicalDeviceMemoryProperties,
+    ) -> Result<Self> {
+        let descriptor_set_layout = create_color_transform_descriptor_set_layout(device)?;
+        // Only coherent memory types are chosen, so writes need no flush.
+        let (buffer, memory) = create_buffer(
+            device,
+            memory_properties,
+            std::mem::size_of::<ColorTransformBuffer>() as vk::DeviceSize,
+            vk::BufferUsageFlags::UNIFORM_BUFFER,
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+        )?;
+
+        // Owned right away, so that everything created so far is released on error.
+        let mut transforms = Self {
+            device: Arc::clone(device),
+            descriptor_set_layout,
+            descriptor_pool: vk::DescriptorPool::null(),
+            descriptor_set: vk::DescriptorSet::null(),
+            buffer,
+            memory,
+        };
+
+        transforms.descriptor_pool = create_descriptor_pool(device, 1, 0, 0, 1)?;
+        transforms.descriptor_set =
+            create_descriptor_set(device, &transforms.descriptor_pool, &descriptor_set_layout)?;
+        update_uniform_buffer_descriptor(device, &transforms.descriptor_set, 0, &buffer);
+
+        for input in 0..MAX_INPUTS {
+            unsafe { transforms.write(input, &IDENTITY) }?;
+        }
+
+        Ok(transforms)
+    }
+
+    /// Writes the transform of an input to the buffer. The memory is host coherent, so the
+    /// write is visible to the device without flushing it.
+    unsafe fn write(&self, input: usize, transform: &Mat4) -> Result<()> {
+        let size = std::mem::size_of::<Mat4>() as vk::DeviceSize;
+        unsafe {
+            let memory = self.device.map_memory(
+                self.memory,
+                input as vk::DeviceSize * size,
+                size,
+                vk::MemoryMapFlags::empty(),
+            )?;
+            std::ptr::write_unaligned(memory as *mut Mat4, *transform);
+            self.device.unmap_memory(self.memory);
+        }
+        Ok(())
+    }
+}
+
+impl RenderTargetComparator {
+    /// Sets the color transform of the input at `index`, applied to its linear values before
+    /// they are compared. `None` restores the identity. Applies likewise to the inputs of
+    /// registered sequences and batch items at the same index.
+    ///
+    /// # Safety
+    ///
+    /// No command buffer recorded with the comparator may be pending execution, as they
+    /// read the transforms when they execute.
+    pub unsafe fn set_color_transform(
+        &mut self,
+        index: usize,
+        transform: Option<Mat4>,
+    ) -> Result<()> {
+        let transforms = self.color_transforms.as_ref().ok_or_else(|| {
+            anyhow!("The comparator was not created with color transforms enabled.")
+        })?;
+        if index >= MAX_INPUTS {
+            return Err(anyhow!(
+                "Expected an input index below {}, got {}.",
+                MAX_INPUTS,
+                index
+            ));
+        }
+
+        unsafe { transforms.write(index, &transform.unwrap_or(IDENTITY)) }
+    }
+}
@@ -0,0 +1,242 @@
+//! Descriptions of the comparator's Vulkan objects, for integrators building on them.
+//!
+//! Custom passes may bind the comparator's input descriptor set in their own pipelines, which
+//! requires their pipeline layouts to use a compatible descriptor set layout. Rather than
+//! duplicating the bindings, such passes can create the layout from the comparator.
+//!
+//! Likewise, comparisons recorded into the caller's render pass use pipelines created for the
+//! comparator's own render pass, so the two must be compatible. Vulkan can't describe an
+//! existing render pass, so the caller's pass is checked against a description of it.
+//! Output image views can be checked before the comparator creates a framebuffer for them.
+
+use anyhow::{Result, anyhow};
+use vulkanalia::prelude::v1_3::*;
+
+use crate::vulkan::descriptors::{
+    bindless_binding_flags, create_descriptor_set_layout, input_layout_bindings,
+};
+use crate::vulkan::render_pass::color_attachment;
+use crate::{RenderTargetComparator, view_mask};
+
+/// A binding of a descriptor set layout.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DescriptorBindingDescription {
+    /// The binding number.
+    pub binding: u32,
+    /// The type of the descriptors.
+    pub descriptor_type: vk::DescriptorType,
+    /// The amount of descriptors, i.e. the length of the array in the shader.
+    pub descriptor_count: u32,
+    /// The shader stages the descriptors are accessible from.
+    pub stage_flags: vk::ShaderStageFlags,
+    /// The flags the binding is created with, through
+    /// `VkDescriptorSetLayoutBindingFlagsCreateInfo`.
+    pub binding_flags: vk::DescriptorBindingFlags,
+}
+
+impl From<vk::DescriptorSetLayoutBinding> for DescriptorBindingDescription {
+    fn from(binding: vk::DescriptorSetLayoutBinding) -> Self {
+        Self {
+            binding: binding.binding,
+            descriptor_type: binding.descriptor_type,
+            descriptor_count: binding.descriptor_count,
+            stage_flags: binding.stage_flags,
+            binding_flags: vk::DescriptorBindingFlags::empty(),
+        }
+    }
+}
+
+/// The parts of a render pass that matter for recording comparisons into it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct RenderPassDescription {
+    /// The amount of subpasses.
+    pub subpass_count: u32,
+    /// The format of the color attachment the comparison is drawn into.
+    pub format: vk::Format,
+    /// The sample count of the color attachment.
+    pub samples: vk::SampleCountFlags,
+    /// What happens to the color attachment at the start of the render pass.
+    pub load_op: vk::AttachmentLoadOp,
+    /// What happens to the color attachment at the end of the render pass.
+    pub store_op: vk::AttachmentStoreOp,
+    /// The layout of the color attachment before the render pass.
+    pub initial_layout: vk::ImageLayout,
+    /// The layout of the color attachment after the render pass.
+    pub final_layout: vk::ImageLayout,
+    /// The amount of other attachments used by the subpass, such as further color, depth
+    /// or resolve attachments.
+    pub other_attachment_count: u32,
+    /// The view mask of the subpass, `0b11` with multiview and `0` without.
+    pub view_mask: u32,
+}
+
+impl RenderTargetComparator {
+    /// Returns the description of the comparator's own render pass, a single subpass
+    /// drawing into the output.
+    pub fn render_pass_description(&self) -> RenderPassDescription {
+        let attachment =
+            color_attachment(self.format, self.final_layout, vk::AttachmentLoadOp::CLEAR);
+        RenderPassDescription {
+            subpass_count: 1,
+            format: attachment.format,
+            samples: attachment.samples,
+            load_op: attachment.load_op,
+            store_op: attachment.store_op,
+            initial_layout: attachment.initial_layout,
+            final_layout: attachment.final_layout,
+            other_attachment_count: 0,
+            view_mask: view_mask(self.multiview),
+        }
+    }
+
+    /// Returns whether a render pass is compatible with the comparator's own, so that
+    /// [`compare_in_current_pass`](Self::compare_in_current_pass) may record into it.
+    ///
+    /// As with Vulkan's render pass compatibility, only the subpasses, their view masks and
+    /// the attachment formats and sample counts matter. Load and store operations and
+    /// layouts may differ.
+    /// No render pass is compatible with a comparator using dynamic rendering only.
+    pub fn is_render_pass_compatible(&self, description: &RenderPassDescription) -> bool {
+        let own = self.render_pass_description();
+        self.render_pass.is_some()
+            && description.subpass_count == own.subpass_count
+            && description.format == own.format
+            && description.samples == own.samples
+            && description.other_attachment_count == own.other_attachment_count
+            && description.view_mask == own.view_mask
+    }
+
+    /// Returns the bindings of the layout of the comparator's input descriptor set, see
+    /// [`descriptor_set`](Self::descriptor_set). The immutable samplers of comparators with
+    /// a YCbCr conversion aren't described.
+    ///
+    /// All inputs are bound as a single array of combined image samplers, with unused
+    /// elements repeating the first inputs. With bindless inputs, the array holds up to their
+    /// maximum and is partially bound, with a variable descriptor count.
+    pub fn descriptor_set_layout_description(&self) -> Vec<DescriptorBindingDescription> {
+        input_layout_bindings()
+            .into_iter()
+            .map(|binding| {
+                let mut description = DescriptorBindingDescription::from(binding);
+                if let Some(count) = self.bindless_inputs {
+                    description.descriptor_count = count;
+                    description.binding_flags = bindless_binding_flags();
+                }
+                description
+            })
+            .collect()
+    }
+
+    /// Creates a descriptor set layout identical to the one of the comparator's input
+    /// descriptor set, for pipeline layouts of custom passes binding that set. The caller
+    /// owns the layout and must destroy it.
+    pub fn create_compatible_descriptor_set_layout(&self) -> Result<vk::DescriptorSetLayout> {
+        create_descriptor_set_layout(
+            &self.device,
+            self.ycbcr_conversion.as_ref().map(|ycbcr| ycbcr.sampler()),
+            false,
+            self.bindless_inputs,
+        )
+    }
+
+    /// Returns the descriptor set binding the comparator's inputs of a frame in flight, to
+    /// bind in custom passes. It must not be updated. Null with push descriptors, which
+    /// allocate no sets.
+    pub fn descriptor_set(&self, frame_index: usize) -> vk::DescriptorSet {
+        self.frame(frame_index).descriptor_set
+    }
+
+    /// Returns the extent of the output image.
+    pub fn output_extent(&self) -> vk::Extent2D {
+        self.extent
+    }
+
+    /// Returns the format of the output image, which is picked by the comparator with
+    /// difference output.
+    pub fn output_format(&self) -> vk::Format {
+        self.format
+    }
+
+    /// Checks that a view, created from `view_info` of an image of `image_extent`, can be
+    /// rendered into as the comparator's output. Returns an error describing the first
+    /// mismatch otherwise.
+    ///
+    /// The image must also have been created with `COLOR_ATTACHMENT` usage, which the view
+    /// doesn't tell.
+    pub fn check_output_view(
+        &self,
+        view_info: &vk::ImageViewCreateInfo,
+        image_extent: vk::Extent2D,
+    ) -> Result<()> {
+        if view_info.format != self.format {
+            return Err(anyhow!(
+                "The output view has format {:?}, but the comparator renders {:?}. Create the \
+                 view with the comparator's output format.",
+                view_info.format,
+                self.format
+            ));
+        }
+
+        // With multiview, a layer per eye.
+        let (view_type, layer_count) = match self.multiview {
+            false => (vk::ImageViewType::_2D, 1),
+            true => (vk::ImageViewType::_2D_ARRAY, 2),
+        };
+        if view_info.view_type != view_type {
+            return Err(anyhow!(
+                "The output view has type {:?}, but must be a {:?} view.",
+                view_info.view_type,
+                view_type
+            ));
+        }
+
+        let range = view_info.subresource_range;
+        if range.aspect_mask != vk::ImageAspectFlags::COLOR {
+            return Err(anyhow!(
+                "The output view covers the {:?} aspects, but must cover the color aspect only.",
+                range.aspect_mask
+            ));
+        }
+
+        // The remaining levels or layers would do for images with as many, but which the
+        // image has isn't known here.
+        if range.level_count != 1 || range.layer_count != layer_count {
+            return Err(anyhow!(
+                "The output view covers {} mip levels and {} layers, but must cover one mip \
+                 level, and a layer per view ({}).",
+                range.level_count,
+                range.layer_count,
+                layer_count
+            ));
+        }
+
+        let components = view_info.components;
+        if [components.r, components.g, components.b, components.a]
+            .iter()
+            .any(|swizzle| *swizzle != vk::ComponentSwizzle::IDENTITY)
+        {
+            return Err(anyhow!(
+                "The output view swizzles its components, but attachments must use the \
+                 identity swizzle."
+            ));
+        }
+
+        let level_size = |size: u32| size.checked_shr(range.base_mip_level).unwrap_or(0).max(1);
+        let level_extent = vk::Extent2D {
+            width: level_size(image_extent.width),
+            height: level_size(image_extent.height),
+        };
+        if level_extent.width < self.extent.width || level_extent.height < self.extent.height {
+            return Err(anyhow!(
+                "The output view is {}x{}, but the comparator renders {}x{}. Create the \
+                 comparator with the extent of the output image.",
+                level_extent.width,
+                level_extent.height,
+                self.extent.width,
+                self.extent.height
+            ));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,83 @@
+//! Comparison modes supplied as SPIR-V fragment shaders, e.g. a studio specific perceptual
+//! metric, registered with [`RenderTargetComparator::register_custom_mode`].
+//!
+//! A custom mode replaces the built-in comparison shader and shares its interface, so that
+//! it can be written against `src/vulkan/shaders/shader.frag`:
+//!
+//! - The inputs are bound as `layout (binding = 0) uniform sampler2D inputs[N]` in set 0,
+//!   with `N` the specialization constant 23 (eight unless bindless inputs are enabled).
+//!   Fewer bound inputs repeat, so every element is valid. With integer inputs, they are
+//!   `usampler2D`s.
+//! - The push constants are the `ComparatorPC` block of the built-in shader, filled from the
+//!   [`CompareInfo`](crate::CompareInfo) like for its `mode`, e.g. the blend factor is in
+//!   `mode_parameter` with [`CompareMode::Onion`](crate::CompareMode::Onion). The block is
+//!   at most 128 bytes and visible to the fragment stage only.
+//! - The texture coordinates of the output, in `[0.0, 1.0]`, are the input at location 0,
+//!   and the eye being drawn with multiview the flat `uint` at location 1. The color is
+//!   written to the output at location 0, with entry point `main`.
+//! - The specialization constants 1 to 23 hold the input mapping as in the built-in shader.
+//!   The mode constant 0 is always the wipe.
+//! - With statistics, color transforms or a lookup table enabled at creation, their
+//!   descriptor sets follow in that order, and may be ignored.
+//!
+//! The pipelines of custom modes are created when they are first prepared and kept along
+//! with the built-in ones.
+
+use anyhow::{Result, anyhow};
+use std::sync::Arc;
+
+use crate::RenderTargetComparator;
+
+// The first word of every SPIR-V module.
+const SPIRV_MAGIC: u32 = 0x0723_0203;
+
+/// The fragment shader of a custom comparison mode.
+#[derive(Clone, Debug)]
+pub struct CustomMode {
+    spirv: Arc<[u8]>,
+}
+
+impl CustomMode {
+    /// Wraps the SPIR-V bytecode of a fragment shader implementing the interface described
+    /// in the [module](self) documentation. Fails if it isn't SPIR-V at all, while invalid
+    /// shaders fail when their pipelines are created.
+    pub fn from_spirv(bytes: &[u8]) -> Result<Self> {
+        let magic = bytes
+            .get(..4)
+            .map(|word| u32::from_le_bytes(word.try_into().unwrap()));
+        if bytes.len() % 4 != 0 || magic != Some(SPIRV_MAGIC) {
+            return Err(anyhow!(
+                "Expected SPIR-V bytecode, a multiple of four bytes starting with its magic \
+                 number."
+            ));
+        }
+        Ok(Self {
+            spirv: bytes.into(),
+        })
+    }
+
+    /// Returns the SPIR-V bytecode.
+    pub fn spirv(&self) -> &[u8] {
+        &self.spirv
+    }
+}
+
+/// Identifies a custom mode registered with a comparator, see
+/// [`CompareInfo::custom_mode`](crate::CompareInfo::custom_mode).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CustomModeId(u32);
+
+impl RenderTargetComparator {
+    /// Registers a custom mode to compare in, and returns its id. Its pipelines are created
+    /// when it is first [prepared](Self::prepare) with the id.
+    pub fn register_custom_mode(&mut self, mode: CustomMode) -> CustomModeId {
+        self.custom_modes.push(mode);
+        CustomModeId((self.custom_modes.len() - 1) as u32)
+    }
+
+    pub(crate) fn custom_mode(&self, id: CustomModeId) -> Result<&CustomMode> {
+        self.custom_modes
+            .get(id.0 as usize)
+            .ok_or_else(|| anyhow!("The custom mode {} is not registered.", id.0))
+    }
+}
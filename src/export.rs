@@ -0,0 +1,207 @@
+//! Saving the comparison output to image files, e.g. to attach it to bug reports.
+//!
+//! PNG files are written with the `png` feature and hold 8-bit sRGB values, as the comparison
+//! is shown. EXR files are written with the `exr` feature and hold linear 32-bit floats. The
+//! output is read back with [`RenderTargetComparator::read_output`], which handles the layout
+//! transitions, and converted from the output format. Difference output is written as
+//! raw data in both cases.
+
+use anyhow::{Result, anyhow};
+use std::path::Path;
+use vulkanalia::prelude::v1_3::*;
+
+use crate::RenderTargetComparator;
+use crate::vulkan::push_constants::{FLAG_DIFFERENCE_OUTPUT, FLAG_ENCODE_SRGB};
+
+// How the values read back from the output are encoded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum StoredEncoding {
+    Linear,
+    Srgb,
+    // Difference data, written as it is.
+    Raw,
+}
+
+impl RenderTargetComparator {
+    /// Reads back the output image like [`read_output`](Self::read_output) and saves it as
+    /// an 8-bit RGBA PNG file.
+    ///
+    /// # Safety
+    ///
+    /// See [`read_output`](Self::read_output).
+    #[cfg(feature = "png")]
+    pub unsafe fn save_png(&mut self, image: vk::Image, path: impl AsRef<Path>) -> Result<()> {
+        let (texels, encoding) = unsafe { self.read_output_rgba(image) }?;
+        let bytes = texels
+            .iter()
+            .flat_map(|texel| {
+                let [r, g, b, a] = *texel;
+                let rgb = match encoding {
+                    StoredEncoding::Linear => [r, g, b].map(encode_srgb),
+                    StoredEncoding::Srgb | StoredEncoding::Raw => [r, g, b],
+                };
+                [rgb[0], rgb[1], rgb[2], a].map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8)
+            })
+            .collect::<Vec<_>>();
+
+        let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        let mut encoder = png::Encoder::new(file, self.extent.width, self.extent.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        if encoding != StoredEncoding::Raw {
+            encoder.set_srgb(png::SrgbRenderingIntent::Perceptual);
+        }
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&bytes)?;
+        writer.finish()?;
+        Ok(())
+    }
+
+    /// Reads back the output image like [`read_output`](Self::read_output) and saves it as
+    /// a 32-bit float RGBA EXR file.
+    ///
+    /// # Safety
+    ///
+    /// See [`read_output`](Self::read_output).
+    #[cfg(feature = "exr")]
+    pub unsafe fn save_exr(&mut self, image: vk::Image, path: impl AsRef<Path>) -> Result<()> {
+        let (texels, encoding) = unsafe { self.read_output_rgba(image) }?;
+        let width = self.extent.width as usize;
+        exr::prelude::write_rgba_file(path, width, self.extent.height as usize, |x, y| {
+            let [r, g, b, a] = texels[y * width + x];
+            let [r, g, b] = match encoding {
+                StoredEncoding::Srgb => [r, g, b].map(decode_srgb),
+                StoredEncoding::Linear | StoredEncoding::Raw => [r, g, b],
+            };
+            (r, g, b, a)
+        })?;
+        Ok(())
+    }
+
+    /// Reads back the output image as floats per channel in RGBA order, with their encoding.
+    unsafe fn read_output_rgba(
+        &mut self,
+        image: vk::Image,
+    ) -> Result<(Vec<[f32; 4]>, StoredEncoding)> {
+        let bytes = unsafe { self.read_output(image) }?;
+        let texels = texels_to_rgba(self.format, &bytes)?;
+
+        let encoding = if self.flags & FLAG_DIFFERENCE_OUTPUT != 0 {
+            StoredEncoding::Raw
+        } else if self.flags & FLAG_ENCODE_SRGB != 0
+            || matches!(
+                self.format,
+                vk::Format::R8G8B8A8_SRGB | vk::Format::B8G8R8A8_SRGB
+            )
+        {
+            StoredEncoding::Srgb
+        } else {
+            StoredEncoding::Linear
+        };
+
+        Ok((texels, encoding))
+    }
+}
+
+/// Converts tightly packed texels of the format to floats per channel, in RGBA order.
+fn texels_to_rgba(format: vk::Format, bytes: &[u8]) -> Result<Vec<[f32; 4]>> {
+    let unorm = |c: u8| c as f32 / 255.0;
+    let texels = match format {
+        vk::Format::R8G8B8A8_UNORM | vk::Format::R8G8B8A8_SRGB => bytes
+            .chunks_exact(4)
+            .map(|t| [t[0], t[1], t[2], t[3]].map(unorm))
+            .collect(),
+        vk::Format::B8G8R8A8_UNORM | vk::Format::B8G8R8A8_SRGB => bytes
+            .chunks_exact(4)
+            .map(|t| [t[2], t[1], t[0], t[3]].map(unorm))
+            .collect(),
+        vk::Format::R16G16B16A16_SFLOAT => bytes
+            .chunks_exact(8)
+            .map(|t| {
+                std::array::from_fn(|i| half_to_f32(u16::from_ne_bytes([t[2 * i], t[2 * i + 1]])))
+            })
+            .collect(),
+        vk::Format::R32G32B32A32_SFLOAT => bytes
+            .chunks_exact(16)
+            .map(|t| {
+                std::array::from_fn(|i| {
+                    f32::from_ne_bytes([t[4 * i], t[4 * i + 1], t[4 * i + 2], t[4 * i + 3]])
+                })
+            })
+            .collect(),
+        _ => {
+            return Err(anyhow!(
+                "Exporting images of format {:?} is not supported.",
+                format
+            ));
+        }
+    };
+    Ok(texels)
+}
+
+/// Converts the bits of a half float to a float.
+fn half_to_f32(bits: u16) -> f32 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((bits >> 10) & 0x1f) as i32;
+    let mantissa = (bits & 0x03ff) as f32;
+    match exponent {
+        0 => sign * mantissa * 2.0_f32.powi(-24),
+        31 if mantissa == 0.0 => sign * f32::INFINITY,
+        31 => f32::NAN,
+        _ => sign * (1.0 + mantissa / 1024.0) * 2.0_f32.powi(exponent - 15),
+    }
+}
+
+#[cfg(feature = "png")]
+fn encode_srgb(c: f32) -> f32 {
+    let c = c.clamp(0.0, 1.0);
+    if c > 0.0031308 {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    } else {
+        c * 12.92
+    }
+}
+
+#[cfg(feature = "exr")]
+fn decode_srgb(c: f32) -> f32 {
+    if c > 0.04045 {
+        ((c + 0.055) / 1.055).powf(2.4)
+    } else {
+        c / 12.92
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_normal_halves() {
+        assert_eq!(half_to_f32(0x3c00), 1.0);
+        assert_eq!(half_to_f32(0xc000), -2.0);
+        assert_eq!(half_to_f32(0x3555), 0.333_251_95);
+        assert_eq!(half_to_f32(0x7bff), 65504.0);
+        assert_eq!(half_to_f32(0x0400), 2.0_f32.powi(-14));
+    }
+
+    #[test]
+    fn converts_subnormals() {
+        assert_eq!(half_to_f32(0x0001), 2.0_f32.powi(-24));
+        assert_eq!(half_to_f32(0x03ff), 1023.0 * 2.0_f32.powi(-24));
+        assert_eq!(half_to_f32(0x8001), -(2.0_f32.powi(-24)));
+    }
+
+    #[test]
+    fn keeps_the_sign_of_zero() {
+        assert!(half_to_f32(0x0000) == 0.0 && half_to_f32(0x0000).is_sign_positive());
+        assert!(half_to_f32(0x8000) == 0.0 && half_to_f32(0x8000).is_sign_negative());
+    }
+
+    #[test]
+    fn converts_infinities_and_nan() {
+        assert_eq!(half_to_f32(0x7c00), f32::INFINITY);
+        assert_eq!(half_to_f32(0xfc00), f32::NEG_INFINITY);
+        assert!(half_to_f32(0x7e00).is_nan());
+        assert!(half_to_f32(0xfc01).is_nan());
+    }
+}
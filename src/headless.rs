@@ -0,0 +1,278 @@
+//! A self-contained comparator that needs no Vulkan setup from the caller.
+//!
+//! Image diffing tools and test suites often have no renderer of their own. A
+//! [`HeadlessComparator`] loads the Vulkan library, creates its own instance, device, queue and
+//! command pool, and compares tightly packed 8-bit RGBA pixels from host memory into pixels
+//! read back from an offscreen output.
+
+use anyhow::{Result, anyhow};
+use std::collections::HashSet;
+use std::sync::Arc;
+use vulkanalia::loader::{LIBRARY, LibloadingLoader};
+use vulkanalia::prelude::v1_3::*;
+
+use crate::input_image::InputImage;
+use crate::transfer::UploadContext;
+use crate::vulkan::commands::{
+    begin_single_time_commands, create_command_pool, end_single_time_commands,
+};
+use crate::{CompareInfo, CompareMode, RenderTargetComparator};
+
+/// A comparator with its own Vulkan instance and device. Everything is destroyed when it is
+/// dropped.
+#[derive(Debug)]
+pub struct HeadlessComparator {
+    // Created on the first comparison, and recreated when the extent changes.
+    comparator: Option<RenderTargetComparator>,
+    mode: CompareMode,
+    memory_properties: vk::PhysicalDeviceMemoryProperties,
+    queue: vk::Queue,
+    queue_family_index: u32,
+    command_pool: vk::CommandPool,
+    device: Arc<Device>,
+    instance: Instance,
+    // Keeps the Vulkan library loaded.
+    _entry: Entry,
+}
+
+impl HeadlessComparator {
+    /// Loads the Vulkan library and creates an instance and a device on the first physical
+    /// device supporting Vulkan 1.3 and graphics operations, preferring discrete GPUs.
+    /// Compares in [`CompareMode::Difference`] until changed with
+    /// [`set_mode`](Self::set_mode).
+    pub fn new() -> Result<Self> {
+        let loader = unsafe { LibloadingLoader::new(LIBRARY) }
+            .map_err(|e| anyhow!("Failed to load the Vulkan library: {}", e))?;
+        let entry = unsafe { Entry::new(loader) }
+            .map_err(|e| anyhow!("Failed to load the Vulkan entry points: {}", e))?;
+        let instance = unsafe { create_instance(&entry) }?;
+
+        let (device, physical_device, queue_family_index) =
+            match unsafe { create_device(&instance) } {
+                Ok(created) => created,
+                Err(e) => {
+                    unsafe { instance.destroy_instance(None) };
+                    return Err(e);
+                }
+            };
+        let command_pool = match create_command_pool(&device, queue_family_index) {
+            Ok(command_pool) => command_pool,
+            Err(e) => {
+                unsafe {
+                    device.destroy_device(None);
+                    instance.destroy_instance(None);
+                }
+                return Err(e);
+            }
+        };
+
+        let memory_properties =
+            unsafe { instance.get_physical_device_memory_properties(physical_device) };
+        let queue = unsafe { device.get_device_queue(queue_family_index, 0) };
+
+        Ok(Self {
+            comparator: None,
+            mode: CompareMode::Difference,
+            memory_properties,
+            queue,
+            queue_family_index,
+            command_pool,
+            device: Arc::new(device),
+            instance,
+            _entry: entry,
+        })
+    }
+
+    /// Sets how the following comparisons compose the two images.
+    pub fn set_mode(&mut self, mode: CompareMode) {
+        self.mode = mode;
+    }
+
+    /// Compares two images of tightly packed 8-bit RGBA pixels, row by row from the top, and
+    /// waits for it. Returns the output in the same layout and extent.
+    pub fn compare(
+        &mut self,
+        width: u32,
+        height: u32,
+        bytes_a: &[u8],
+        bytes_b: &[u8],
+    ) -> Result<Vec<u8>> {
+        let upload = self.upload_context();
+        let image_a = InputImage::from_rgba8(&self.device, &upload, width, height, bytes_a)?;
+        let image_b = InputImage::from_rgba8(&self.device, &upload, width, height, bytes_b)?;
+        self.compare_images(&image_a, &image_b)
+    }
+
+    /// Loads two image files of the same extent with [`InputImage::load`] and compares them
+    /// like [`compare`](Self::compare).
+    #[cfg(feature = "io")]
+    pub fn compare_files(
+        &mut self,
+        path_a: impl AsRef<std::path::Path>,
+        path_b: impl AsRef<std::path::Path>,
+    ) -> Result<Vec<u8>> {
+        let upload = self.upload_context();
+        let image_a = InputImage::load(&self.device, &upload, path_a)?;
+        let image_b = InputImage::load(&self.device, &upload, path_b)?;
+        self.compare_images(&image_a, &image_b)
+    }
+
+    fn upload_context(&self) -> UploadContext {
+        UploadContext {
+            memory_properties: self.memory_properties,
+            queue: self.queue,
+            command_pool: self.command_pool,
+        }
+    }
+
+    fn compare_images(&mut self, image_a: &InputImage, image_b: &InputImage) -> Result<Vec<u8>> {
+        let extent = image_a.extent();
+        if image_b.extent() != extent {
+            return Err(anyhow!(
+                "Expected images of the same extent, got {}x{} and {}x{}.",
+                extent.width,
+                extent.height,
+                image_b.extent().width,
+                image_b.extent().height
+            ));
+        }
+        let views = [image_a.view(), image_b.view()];
+
+        // All previous work has been waited for, so the inputs can be replaced right away.
+        let comparator = match self.comparator.take() {
+            Some(mut comparator) if comparator.extent == extent => {
+                unsafe { comparator.set_inputs(&views) }?;
+                comparator
+            }
+            _ => RenderTargetComparator::builder()
+                .device(Arc::clone(&self.device))
+                .format(vk::Format::R8G8B8A8_UNORM)
+                .extent(extent)
+                .in_image_views(views.to_vec())
+                .memory_properties(self.memory_properties)
+                .queue(self.queue)
+                .queue_family_index(self.queue_family_index)
+                .modes(vec![self.mode])
+                .create_offscreen()?,
+        };
+        let comparator = self.comparator.insert(comparator);
+
+        let command_buffer =
+            unsafe { begin_single_time_commands(&self.device, self.command_pool) }?;
+        let recorded = CompareInfo::builder()
+            .command_buffer(command_buffer)
+            .mode(self.mode)
+            .build()
+            .map_err(anyhow::Error::from)
+            .and_then(|info| {
+                comparator.prepare(&info)?;
+                unsafe { comparator.compare(&info) }
+            });
+        if let Err(e) = recorded {
+            unsafe {
+                self.device
+                    .free_command_buffers(self.command_pool, &[command_buffer])
+            };
+            return Err(e);
+        }
+        unsafe {
+            end_single_time_commands(&self.device, self.queue, self.command_pool, command_buffer)
+        }?;
+
+        unsafe { comparator.read_offscreen_output() }
+    }
+}
+
+impl Drop for HeadlessComparator {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = self.device.device_wait_idle();
+            self.comparator = None;
+            self.device.destroy_command_pool(self.command_pool, None);
+            self.device.destroy_device(None);
+            self.instance.destroy_instance(None);
+        }
+    }
+}
+
+// Creates a Vulkan 1.3 instance, enumerating portability drivers such as MoltenVK where
+// available.
+unsafe fn create_instance(entry: &Entry) -> Result<Instance> {
+    let application_info = vk::ApplicationInfo::builder()
+        .application_name(b"rtcmp\0")
+        .engine_name(b"rtcmp\0")
+        .api_version(vk::make_version(1, 3, 0))
+        .build();
+
+    let available = unsafe { entry.enumerate_instance_extension_properties(None) }?
+        .iter()
+        .map(|e| e.extension_name)
+        .collect::<HashSet<_>>();
+    let portability = available.contains(&vk::KHR_PORTABILITY_ENUMERATION_EXTENSION.name);
+    let mut extensions = Vec::new();
+    let mut flags = vk::InstanceCreateFlags::empty();
+    if portability {
+        extensions.push(vk::KHR_PORTABILITY_ENUMERATION_EXTENSION.name.as_ptr());
+        flags |= vk::InstanceCreateFlags::ENUMERATE_PORTABILITY_KHR;
+    }
+
+    let info = vk::InstanceCreateInfo::builder()
+        .application_info(&application_info)
+        .enabled_extension_names(&extensions)
+        .flags(flags)
+        .build();
+
+    let instance = unsafe { entry.create_instance(&info, None) }?;
+    Ok(instance)
+}
+
+// Creates a device with a single graphics queue on the most suitable physical device.
+// Returns it along with the physical device and the queue family.
+unsafe fn create_device(instance: &Instance) -> Result<(Device, vk::PhysicalDevice, u32)> {
+    let mut candidates = Vec::new();
+    for physical_device in unsafe { instance.enumerate_physical_devices() }? {
+        let properties = unsafe { instance.get_physical_device_properties(physical_device) };
+        if properties.api_version < vk::make_version(1, 3, 0) {
+            continue;
+        }
+        let families =
+            unsafe { instance.get_physical_device_queue_family_properties(physical_device) };
+        let family = families
+            .iter()
+            .position(|f| f.queue_flags.contains(vk::QueueFlags::GRAPHICS));
+        if let Some(family) = family {
+            let discrete = properties.device_type == vk::PhysicalDeviceType::DISCRETE_GPU;
+            candidates.push((discrete, physical_device, family as u32));
+        }
+    }
+    // Stable, so that the first discrete GPU is picked, or the first device without any.
+    candidates.sort_by_key(|(discrete, _, _)| !discrete);
+    let (_, physical_device, queue_family_index) = candidates
+        .first()
+        .copied()
+        .ok_or_else(|| anyhow!("No physical device supports Vulkan 1.3 and graphics."))?;
+
+    let available =
+        unsafe { instance.enumerate_device_extension_properties(physical_device, None) }?
+            .iter()
+            .map(|e| e.extension_name)
+            .collect::<HashSet<_>>();
+    // Portability drivers require the subset extension to be enabled.
+    let mut extensions = Vec::new();
+    if available.contains(&vk::KHR_PORTABILITY_SUBSET_EXTENSION.name) {
+        extensions.push(vk::KHR_PORTABILITY_SUBSET_EXTENSION.name.as_ptr());
+    }
+
+    let queue_priorities = &[1.0];
+    let queue_infos = &[vk::DeviceQueueCreateInfo::builder()
+        .queue_family_index(queue_family_index)
+        .queue_priorities(queue_priorities)
+        .build()];
+    let info = vk::DeviceCreateInfo::builder()
+        .queue_create_infos(queue_infos)
+        .enabled_extension_names(&extensions)
+        .build();
+
+    let device = unsafe { instance.create_device(physical_device, &info, None) }?;
+    Ok((device, physical_device, queue_family_index))
+}
@@ -0,0 +1,119 @@
+//! Comparing single layers and mip levels of images, such as cubemap faces.
+//!
+//! The comparator samples 2D views. Rather than creating a view per layer by hand,
+//! [`RenderTargetComparator::from_image_layers`] takes the images along with an
+//! [`ImageLayer`] selecting the layer and mip level of each, and creates and owns the views.
+
+use anyhow::{Result, anyhow};
+use vulkanalia::prelude::v1_3::*;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::vulkan::image::create_subresource_view;
+use crate::{RenderTargetComparator, RenderTargetComparatorCreateInfo};
+
+/// A face of a cubemap, in the order of the layers of a cube compatible image.
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum CubeFace {
+    PositiveX = 0,
+    NegativeX = 1,
+    PositiveY = 2,
+    NegativeY = 3,
+    PositiveZ = 4,
+    NegativeZ = 5,
+}
+
+/// A single layer and mip level of an image, compared as a 2D input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ImageLayer {
+    /// A color or depth image in `SHADER_READ_ONLY_OPTIMAL` layout, created with `SAMPLED` usage.
+    pub image: vk::Image,
+    /// The format of the view, usually that of the image.
+    pub format: vk::Format,
+    /// The array layer.
+    pub layer: u32,
+    /// The mip level, which becomes the only level of the view.
+    pub mip_level: u32,
+}
+
+impl ImageLayer {
+    /// The full resolution level of a layer.
+    pub fn new(image: vk::Image, format: vk::Format, layer: u32) -> Self {
+        Self {
+            image,
+            format,
+            layer,
+            mip_level: 0,
+        }
+    }
+
+    /// The full resolution level of a face of a cubemap, or of the cube at `cube` of a
+    /// cubemap array.
+    pub fn cube_face(image: vk::Image, format: vk::Format, cube: u32, face: CubeFace) -> Self {
+        Self::new(image, format, cube * 6 + face as u32)
+    }
+
+    /// The same layer at another mip level.
+    pub fn at_mip_level(self, mip_level: u32) -> Self {
+        Self { mip_level, ..self }
+    }
+}
+
+impl RenderTargetComparator {
+    /// Creates a comparator like [`new`](Self::new), comparing the selected layers of the
+    /// images instead of `info.in_image_views`, which may be left empty. The views of the
+    /// layers are owned by the comparator and destroyed along with it, so the images must
+    /// outlive it.
+    pub fn from_image_layers(
+        info: &RenderTargetComparatorCreateInfo,
+        layers: &[ImageLayer],
+    ) -> Result<Self> {
+        if info.ycbcr_conversion.is_some() {
+            return Err(anyhow!(
+                "Views of multi-planar inputs must be created with YcbcrConversion::create_view."
+            ));
+        }
+        let device = &info.device;
+        let mut views = Vec::with_capacity(layers.len());
+        for layer in layers {
+            match create_subresource_view(
+                device,
+                layer.image,
+                layer.format,
+                layer.mip_level,
+                1,
+                layer.layer,
+            ) {
+                Ok(view) => views.push(view),
+                Err(e) => {
+                    destroy_views(device, &views);
+                    return Err(e);
+                }
+            }
+        }
+
+        let info = RenderTargetComparatorCreateInfo {
+            in_image_views: views.clone(),
+            ..info.clone()
+        };
+        match Self::new(&info) {
+            Ok(mut comparator) => {
+                comparator.owned_input_views = views;
+                Ok(comparator)
+            }
+            Err(e) => {
+                destroy_views(device, &views);
+                Err(e)
+            }
+        }
+    }
+}
+
+fn destroy_views(device: &Device, views: &[vk::ImageView]) {
+    for view in views {
+        unsafe { device.destroy_image_view(*view, None) };
+    }
+}
@@ -0,0 +1,164 @@
+//! Sampled input images created from pixels in host memory.
+//!
+//! Most comparisons start from pixels decoded on the CPU rather than from existing Vulkan
+//! images. An [`InputImage`] uploads such pixels once and owns the resulting image, whose
+//! view can be passed to the comparator like any other input.
+
+use anyhow::{Result, anyhow};
+use std::sync::Arc;
+use vulkanalia::prelude::v1_3::*;
+
+use crate::transfer::{UploadContext, upload_to_image};
+use crate::vulkan::image::{create_image, create_image_view};
+
+/// An image holding uploaded pixels, in `SHADER_READ_ONLY_OPTIMAL` layout and ready to be
+/// sampled. Destroyed when dropped.
+#[derive(Debug)]
+pub struct InputImage {
+    device: Arc<Device>,
+    image: vk::Image,
+    memory: vk::DeviceMemory,
+    view: vk::ImageView,
+    extent: vk::Extent2D,
+    format: vk::Format,
+}
+
+impl Drop for InputImage {
+    fn drop(&mut self) {
+        unsafe {
+            self.device.destroy_image_view(self.view, None);
+            self.device.destroy_image(self.image, None);
+            self.device.free_memory(self.memory, None);
+        }
+    }
+}
+
+impl InputImage {
+    /// Creates an image from tightly packed 8-bit RGBA pixels, row by row from the top.
+    pub fn from_rgba8(
+        device: &Arc<Device>,
+        upload: &UploadContext,
+        width: u32,
+        height: u32,
+        pixels: &[u8],
+    ) -> Result<Self> {
+        Self::from_pixels(
+            device,
+            upload,
+            vk::Extent2D { width, height },
+            vk::Format::R8G8B8A8_UNORM,
+            4,
+            pixels,
+        )
+    }
+
+    /// Creates an image from tightly packed 16-bit float RGBA pixels, given as the bits of
+    /// the half precision values.
+    pub fn from_rgba16f(
+        device: &Arc<Device>,
+        upload: &UploadContext,
+        width: u32,
+        height: u32,
+        pixels: &[u16],
+    ) -> Result<Self> {
+        Self::from_pixels(
+            device,
+            upload,
+            vk::Extent2D { width, height },
+            vk::Format::R16G16B16A16_SFLOAT,
+            8,
+            bytemuck::cast_slice(pixels),
+        )
+    }
+
+    /// Creates an image from tightly packed 32-bit float RGBA pixels.
+    pub fn from_rgba32f(
+        device: &Arc<Device>,
+        upload: &UploadContext,
+        width: u32,
+        height: u32,
+        pixels: &[f32],
+    ) -> Result<Self> {
+        Self::from_pixels(
+            device,
+            upload,
+            vk::Extent2D { width, height },
+            vk::Format::R32G32B32A32_SFLOAT,
+            16,
+            bytemuck::cast_slice(pixels),
+        )
+    }
+
+    /// Returns the view of the image, to pass to the comparator.
+    pub fn view(&self) -> vk::ImageView {
+        self.view
+    }
+
+    /// Returns the image.
+    pub fn image(&self) -> vk::Image {
+        self.image
+    }
+
+    /// Returns the extent of the image.
+    pub fn extent(&self) -> vk::Extent2D {
+        self.extent
+    }
+
+    /// Returns the format of the image.
+    pub fn format(&self) -> vk::Format {
+        self.format
+    }
+
+    fn from_pixels(
+        device: &Arc<Device>,
+        upload: &UploadContext,
+        extent: vk::Extent2D,
+        format: vk::Format,
+        texel_size: usize,
+        data: &[u8],
+    ) -> Result<Self> {
+        let size = extent.width as usize * extent.height as usize * texel_size;
+        if size == 0 || data.len() != size {
+            return Err(anyhow!(
+                "Expected {} bytes of pixels for a {}x{} image, got {}.",
+                size,
+                extent.width,
+                extent.height,
+                data.len()
+            ));
+        }
+
+        let (image, memory) = create_image(
+            device,
+            &upload.memory_properties,
+            extent,
+            format,
+            1,
+            vk::ImageUsageFlags::SAMPLED | vk::ImageUsageFlags::TRANSFER_DST,
+        )?;
+
+        // Owned right away, so that everything created so far is released on error.
+        let mut input = Self {
+            device: Arc::clone(device),
+            image,
+            memory,
+            view: vk::ImageView::null(),
+            extent,
+            format,
+        };
+
+        unsafe {
+            upload_to_image(
+                device,
+                upload,
+                image,
+                extent,
+                data,
+                vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+            )
+        }?;
+
+        input.view = create_image_view(device, image, format, 1)?;
+        Ok(input)
+    }
+}
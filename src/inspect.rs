@@ -0,0 +1,213 @@
+//! Reading the values of the inputs under a point, e.g. to show them next to the cursor.
+//!
+//! The comparator only knows the views of its inputs, so rather than copying from the images,
+//! a single compute invocation fetches the texels under the point through the input
+//! descriptor set into a small host visible buffer. Its resources are created on first use.
+
+use anyhow::Result;
+use std::sync::Arc;
+use vulkanalia::prelude::v1_3::*;
+
+use crate::vulkan::{
+    commands::{begin_single_time_commands, end_single_time_commands},
+    descriptors::{
+        MAX_INPUTS, create_compute_buffer_descriptor_set_layout, create_descriptor_pool,
+        create_descriptor_set, update_storage_buffer_descriptor,
+    },
+    memory::create_buffer,
+    pipeline::create_inspect_pipeline,
+    push_constants::InspectPushConstantBuffer,
+};
+use crate::{Color, RenderTargetComparator};
+
+// Mirrors the `Texels` buffer of the inspector compute shader (std430 layout).
+type TexelBuffer = [Color; MAX_INPUTS];
+
+/// The inspector's pipeline, and the buffer the texels are written to.
+#[derive(Debug)]
+pub(crate) struct InspectorResources {
+    device: Arc<Device>,
+    descriptor_set_layout: vk::DescriptorSetLayout,
+    descriptor_pool: vk::DescriptorPool,
+    descriptor_set: vk::DescriptorSet,
+    pipeline_layout: vk::PipelineLayout,
+    pipeline: vk::Pipeline,
+    buffer: vk::Buffer,
+    memory: vk::DeviceMemory,
+}
+
+impl Drop for InspectorResources {
+    fn drop(&mut self) {
+        unsafe {
+            self.device.destroy_pipeline(self.pipeline, None);
+            self.device
+                .destroy_pipeline_layout(self.pipeline_layout, None);
+            self.device.destroy_buffer(self.buffer, None);
+            self.device.free_memory(self.memory, None);
+            self.device
+                .destroy_descriptor_pool(self.descriptor_pool, None);
+            self.device
+                .destroy_descriptor_set_layout(self.descriptor_set_layout, None);
+        }
+    }
+}
+
+impl InspectorResources {
+    fn new(
+        device: &Arc<Device>,
+        memory_properties: &vk::PhysicalDeviceMemoryProperties,
+        pipeline_cache: vk::PipelineCache,
+        input_layout: vk::DescriptorSetLayout,
+    ) -> Result<Self> {
+        let descriptor_set_layout = create_compute_buffer_descriptor_set_layout(device)?;
+        let (buffer, memory) = create_buffer(
+            device,
+            memory_properties,
+            std::mem::size_of::<TexelBuffer>() as vk::DeviceSize,
+            vk::BufferUsageFlags::STORAGE_BUFFER,
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+        )?;
+
+        // Owned right away, so that everything created so far is released on error.
+        let mut inspector = Self {
+            device: Arc::clone(device),
+            descriptor_set_layout,
+            descriptor_pool: vk::DescriptorPool::null(),
+            descriptor_set: vk::DescriptorSet::null(),
+            pipeline_layout: vk::PipelineLayout::null(),
+            pipeline: vk::Pipeline::null(),
+            buffer,
+            memory,
+        };
+
+        inspector.descriptor_pool = create_descriptor_pool(device, 1, 0, 1, 0)?;
+        inspector.descriptor_set =
+            create_descriptor_set(device, &inspector.descriptor_pool, &descriptor_set_layout)?;
+        update_storage_buffer_descriptor(device, &inspector.descriptor_set, 0, &buffer);
+
+        (inspector.pipeline_layout, inspector.pipeline) = create_inspect_pipeline(
+            device,
+            pipeline_cache,
+            &[input_layout, descriptor_set_layout],
+        )?;
+
+        Ok(inspector)
+    }
+
+    /// Records fetching the texels under `uv` from the inputs bound by `input_set`, and making
+    /// them visible to the host.
+    unsafe fn cmd_inspect(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        input_set: vk::DescriptorSet,
+        uv: [f32; 2],
+    ) {
+        let barrier = vk::BufferMemoryBarrier::builder()
+            .src_access_mask(vk::AccessFlags::SHADER_WRITE)
+            .dst_access_mask(vk::AccessFlags::HOST_READ)
+            .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .buffer(self.buffer)
+            .offset(0)
+            .size(vk::WHOLE_SIZE)
+            .build();
+
+        unsafe {
+            self.device.cmd_bind_pipeline(
+                command_buffer,
+                vk::PipelineBindPoint::COMPUTE,
+                self.pipeline,
+            );
+            self.device.cmd_bind_descriptor_sets(
+                command_buffer,
+                vk::PipelineBindPoint::COMPUTE,
+                self.pipeline_layout,
+                0,
+                &[input_set, self.descriptor_set],
+                &[] as &[u32],
+            );
+            self.device.cmd_push_constants(
+                command_buffer,
+                self.pipeline_layout,
+                vk::ShaderStageFlags::COMPUTE,
+                0,
+                bytemuck::bytes_of(&InspectPushConstantBuffer { uv }),
+            );
+            self.device.cmd_dispatch(command_buffer, 1, 1, 1);
+            self.device.cmd_pipeline_barrier(
+                command_buffer,
+                vk::PipelineStageFlags::COMPUTE_SHADER,
+                vk::PipelineStageFlags::HOST,
+                vk::DependencyFlags::empty(),
+                &[] as &[vk::MemoryBarrier],
+                &[barrier],
+                &[] as &[vk::ImageMemoryBarrier],
+            );
+        }
+    }
+
+    /// Reads back the texels of the last inspection.
+    unsafe fn read(&self) -> Result<TexelBuffer> {
+        unsafe {
+            let memory = self.device.map_memory(
+                self.memory,
+                0,
+                std::mem::size_of::<TexelBuffer>() as vk::DeviceSize,
+                vk::MemoryMapFlags::empty(),
+            )?;
+            let texels = std::ptr::read_unaligned(memory as *const TexelBuffer);
+            self.device.unmap_memory(self.memory);
+            Ok(texels)
+        }
+    }
+}
+
+impl RenderTargetComparator {
+    /// Reads the values of the first two inputs of a frame in flight at a point, and waits
+    /// for them. Requires the queue and memory properties to be set at creation.
+    ///
+    /// The point is in texture coordinates of the inputs, in the range `[0.0, 1.0]`. Without
+    /// fit, extent policy and alignment offsets, the comparison samples them at
+    /// `(position - 0.5) / zoom + 0.5 + pan` for a position relative to the output. The
+    /// values are the texels of the base level under the point, unfiltered and as stored,
+    /// i.e. before any encoding, color transform or lookup table is applied.
+    ///
+    /// # Safety
+    ///
+    /// The input images must be in `SHADER_READ_ONLY_OPTIMAL` layout, and must not be
+    /// written to until the values have been read.
+    pub unsafe fn read_pixels(
+        &mut self,
+        frame_index: usize,
+        point: [f32; 2],
+    ) -> Result<(Color, Color)> {
+        self.check_unfiltered_reads("Reading pixels")?;
+        self.check_float_reads("Reading pixels")?;
+        self.check_descriptor_sets("Reading pixels")?;
+        let context = self.upload_context()?;
+        let input_set = self.frame(frame_index).descriptor_set;
+        let inspector = match &mut self.inspector {
+            Some(inspector) => inspector,
+            inspector => inspector.insert(InspectorResources::new(
+                &self.device,
+                &context.memory_properties,
+                self.pipeline_cache,
+                self.descriptor_set_layout,
+            )?),
+        };
+
+        let texels = unsafe {
+            let command_buffer = begin_single_time_commands(&self.device, context.command_pool)?;
+            inspector.cmd_inspect(command_buffer, input_set, point);
+            end_single_time_commands(
+                &self.device,
+                context.queue,
+                context.command_pool,
+                command_buffer,
+            )?;
+            inspector.read()
+        }?;
+
+        Ok((texels[0], texels[1]))
+    }
+}
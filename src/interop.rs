@@ -0,0 +1,133 @@
+//! Using the comparator on a device created by another Vulkan binding, such as ash.
+//!
+//! The comparator calls Vulkan through vulkanalia's dispatch tables. Rather than loading the
+//! Vulkan library a second time, [`ImportedDevice::new`] fills those tables from the
+//! application's own `vkGetInstanceProcAddr` and raw instance and device handles. Other
+//! handles, such as image views, convert with [`vk::Handle::from_raw`] from the raw values
+//! of the application's binding, e.g. ash's `Handle::as_raw`.
+
+use anyhow::{Result, anyhow};
+use std::error::Error;
+use std::ffi::{CStr, CString, c_char, c_void};
+use std::sync::Arc;
+use vulkanalia::loader::Loader;
+use vulkanalia::prelude::v1_3::*;
+
+/// The raw handles of an instance and device created outside of vulkanalia.
+#[derive(Clone, Copy, Debug)]
+pub struct RawDevice<'a> {
+    /// The address of `vkGetInstanceProcAddr`, e.g. ash's
+    /// `entry.static_fn().get_instance_proc_addr as *const c_void`.
+    pub get_instance_proc_addr: *const c_void,
+    /// The raw `VkInstance`.
+    pub instance: usize,
+    /// The raw `VkPhysicalDevice` the device was created on.
+    pub physical_device: usize,
+    /// The raw `VkDevice`.
+    pub device: usize,
+    /// The API version the instance was created with, e.g. `vk::make_version(1, 3, 0)`.
+    pub api_version: u32,
+    /// The instance extensions the instance was created with.
+    pub instance_extensions: &'a [&'a CStr],
+    /// The device extensions the device was created with.
+    pub device_extensions: &'a [&'a CStr],
+}
+
+/// Vulkanalia dispatch tables for an instance and device owned by the application. Nothing
+/// is destroyed when dropped.
+#[derive(Debug)]
+pub struct ImportedDevice {
+    device: Arc<Device>,
+    instance: Instance,
+    // Keeps the loader alive.
+    _entry: Entry,
+}
+
+impl ImportedDevice {
+    /// Loads the commands of the instance and device through the application's
+    /// `vkGetInstanceProcAddr`.
+    ///
+    /// # Safety
+    ///
+    /// All handles and the address must be valid, and the instance and device must outlive
+    /// the imported device and everything created with it.
+    pub unsafe fn new(raw: &RawDevice) -> Result<Self> {
+        if raw.get_instance_proc_addr.is_null() {
+            return Err(anyhow!("The address of vkGetInstanceProcAddr must be set."));
+        }
+        let get_instance_proc_addr: GetInstanceProcAddr =
+            unsafe { std::mem::transmute(raw.get_instance_proc_addr) };
+        let entry = unsafe { Entry::new(ProcAddrLoader(get_instance_proc_addr)) }
+            .map_err(|e| anyhow!("Failed to load the Vulkan entry points: {}", e))?;
+
+        let application_info = vk::ApplicationInfo::builder()
+            .api_version(raw.api_version)
+            .build();
+        let instance_extensions = extension_names(raw.instance_extensions);
+        let instance_info = vk::InstanceCreateInfo::builder()
+            .application_info(&application_info)
+            .enabled_extension_names(&instance_extensions)
+            .build();
+        let instance = unsafe {
+            Instance::from_created(&entry, &instance_info, vk::Instance::from_raw(raw.instance))
+        }?;
+
+        let device_extensions = extension_names(raw.device_extensions);
+        let device_info = vk::DeviceCreateInfo::builder()
+            .enabled_extension_names(&device_extensions)
+            .build();
+        let device = unsafe {
+            Device::from_created(
+                &instance,
+                vk::PhysicalDevice::from_raw(raw.physical_device),
+                &device_info,
+                vk::Device::from_raw(raw.device),
+            )
+        }?;
+
+        Ok(Self {
+            device: Arc::new(device),
+            instance,
+            _entry: entry,
+        })
+    }
+
+    /// Returns the device, to pass to the comparator and the other helpers of the crate.
+    pub fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+
+    /// Returns the instance, e.g. to query the memory properties of the physical device.
+    pub fn instance(&self) -> &Instance {
+        &self.instance
+    }
+}
+
+type GetInstanceProcAddr =
+    unsafe extern "system" fn(vk::Instance, *const c_char) -> Option<unsafe extern "system" fn()>;
+
+type Command = extern "system" fn();
+
+// Loads the global commands through the application's `vkGetInstanceProcAddr` instead of the
+// Vulkan library.
+struct ProcAddrLoader(GetInstanceProcAddr);
+
+impl Loader for ProcAddrLoader {
+    unsafe fn load(&self, name: &[u8]) -> Result<Command, Box<dyn Error + Send + Sync + 'static>> {
+        let name = CString::new(name.strip_suffix(b"\0").unwrap_or(name))?;
+        if name.as_bytes() == b"vkGetInstanceProcAddr" {
+            let command = unsafe { std::mem::transmute::<GetInstanceProcAddr, Command>(self.0) };
+            return Ok(command);
+        }
+        match unsafe { (self.0)(vk::Instance::null(), name.as_ptr()) } {
+            Some(command) => {
+                Ok(unsafe { std::mem::transmute::<unsafe extern "system" fn(), Command>(command) })
+            }
+            None => Err(format!("Failed to load {}.", name.to_string_lossy()).into()),
+        }
+    }
+}
+
+fn extension_names(extensions: &[&CStr]) -> Vec<*const c_char> {
+    extensions.iter().map(|name| name.as_ptr()).collect()
+}
@@ -0,0 +1,237 @@
+//! Text labels naming the inputs, drawn with a glyph atlas supplied by the caller.
+//!
+//! The comparator doesn't rasterize fonts. Instead, it is created with a
+//! [`GlyphAtlas`]: an image of equally sized glyph cells, laid out row by row in character
+//! order. Each label is a single line placed in the corner of the area showing its input,
+//! e.g. `"v1.2"` on the left of a wipe and `"v1.3-rc"` on the right.
+
+use anyhow::{Result, anyhow};
+use std::sync::Arc;
+use vulkanalia::prelude::v1_3::*;
+
+use crate::vulkan::{
+    descriptors::{
+        create_descriptor_pool, create_descriptor_set, create_image_descriptor_set_layout,
+        update_image_descriptor_set,
+    },
+    pipeline::create_label_pipeline_layout,
+    push_constants::LabelPushConstantBuffer,
+    sampler::create_image_sampler,
+};
+use crate::{
+    CompareInfo, CompareMode, DividerOrientation, RenderTargetComparator, SamplerConfig,
+    grid_layout, split_position, swapped_input,
+};
+
+/// The amount of characters of a label that are drawn. Longer labels are cut off.
+pub const MAX_LABEL_LENGTH: usize = 64;
+
+/// An image of glyphs in a grid of equally sized cells, filled row by row in character
+/// order. The glyphs' coverage is read from the red channel, e.g. white glyphs on black in
+/// an `R8_UNORM` image. The view must be in `SHADER_READ_ONLY_OPTIMAL` layout.
+#[derive(Clone, Copy, Debug)]
+pub struct GlyphAtlas {
+    /// The view of the atlas image, owned by the caller.
+    pub view: vk::ImageView,
+    /// The amount of cells per row.
+    pub columns: u32,
+    /// The amount of rows of cells.
+    pub rows: u32,
+    /// The width of a cell over its height.
+    pub cell_aspect: f32,
+    /// The character of the first cell, e.g. `' '` for printable ASCII. Characters without
+    /// a cell, and those beyond `'\u{ff}'`, are left blank.
+    pub first_char: char,
+}
+
+/// The descriptor of the glyph atlas, and the layout of the pipeline drawing labels.
+#[derive(Debug)]
+pub(crate) struct LabelResources {
+    device: Arc<Device>,
+    descriptor_set_layout: vk::DescriptorSetLayout,
+    descriptor_pool: vk::DescriptorPool,
+    descriptor_set: vk::DescriptorSet,
+    sampler: vk::Sampler,
+    pub(crate) pipeline_layout: vk::PipelineLayout,
+    atlas: GlyphAtlas,
+}
+
+impl Drop for LabelResources {
+    fn drop(&mut self) {
+        unsafe {
+            self.device
+                .destroy_pipeline_layout(self.pipeline_layout, None);
+            self.device.destroy_sampler(self.sampler, None);
+            self.device
+                .destroy_descriptor_pool(self.descriptor_pool, None);
+            self.device
+                .destroy_descriptor_set_layout(self.descriptor_set_layout, None);
+        }
+    }
+}
+
+impl LabelResources {
+    pub(crate) fn new(device: &Arc<Device>, atlas: GlyphAtlas) -> Result<Self> {
+        if atlas.columns == 0 || atlas.rows == 0 {
+            return Err(anyhow!("The glyph atlas must have at least one cell."));
+        }
+
+        let descriptor_set_layout = create_image_descriptor_set_layout(device)?;
+
+        // Owned right away, so that everything created so far is released on error.
+        let mut labels = Self {
+            device: Arc::clone(device),
+            descriptor_set_layout,
+            descriptor_pool: vk::DescriptorPool::null(),
+            descriptor_set: vk::DescriptorSet::null(),
+            sampler: vk::Sampler::null(),
+            pipeline_layout: vk::PipelineLayout::null(),
+            atlas,
+        };
+
+        labels.sampler = create_image_sampler(device, &SamplerConfig::default())?;
+        labels.descriptor_pool = create_descriptor_pool(device, 1, 1, 0, 0)?;
+        labels.descriptor_set =
+            create_descriptor_set(device, &labels.descriptor_pool, &descriptor_set_layout)?;
+        update_image_descriptor_set(device, &labels.descriptor_set, &labels.sampler, atlas.view);
+        labels.pipeline_layout = create_label_pipeline_layout(device, descriptor_set_layout)?;
+
+        Ok(labels)
+    }
+}
+
+impl RenderTargetComparator {
+    /// Records the input labels over a comparison drawn in the current render pass.
+    pub(crate) unsafe fn cmd_draw_labels(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        pipeline: vk::Pipeline,
+        viewport: vk::Viewport,
+        input_count: usize,
+        info: &CompareInfo,
+    ) -> Result<()> {
+        let labels = self
+            .labels
+            .as_ref()
+            .ok_or_else(|| anyhow!("The comparator was not created with a glyph atlas."))?;
+
+        let glyph_size = [
+            info.input_label_size * labels.atlas.cell_aspect,
+            info.input_label_size,
+        ];
+        // Half a line away from the corner.
+        let margin = info.input_label_size / 2.0;
+
+        unsafe {
+            self.device.cmd_bind_pipeline(
+                command_buffer,
+                vk::PipelineBindPoint::GRAPHICS,
+                pipeline,
+            );
+            self.device.cmd_bind_descriptor_sets(
+                command_buffer,
+                vk::PipelineBindPoint::GRAPHICS,
+                labels.pipeline_layout,
+                0,
+                &[labels.descriptor_set],
+                &[] as &[u32],
+            );
+            self.device.cmd_set_viewport(command_buffer, 0, &[viewport]);
+        }
+
+        for (index, label) in info.input_labels.iter().enumerate() {
+            let Some((corner, right_aligned)) =
+                label_corner(info, viewport, input_count, swapped_input(info, index))
+            else {
+                continue;
+            };
+
+            // Four characters per word, the first in the lowest byte.
+            let mut text = [0_u32; MAX_LABEL_LENGTH / 4];
+            let mut length = 0;
+            for (i, c) in label.chars().take(MAX_LABEL_LENGTH).enumerate() {
+                text[i / 4] |= (u8::try_from(c).unwrap_or(0) as u32) << (8 * (i % 4));
+                length += 1;
+            }
+            if length == 0 {
+                continue;
+            }
+
+            let width = length as f32 * glyph_size[0];
+            let origin = [
+                match right_aligned {
+                    true => corner[0] - margin - width,
+                    false => corner[0] + margin,
+                },
+                corner[1] + margin,
+            ];
+
+            let push_buffer = LabelPushConstantBuffer {
+                color: info.input_label_color,
+                origin,
+                viewport_size: [viewport.width, viewport.height],
+                glyph_size,
+                atlas_grid: [labels.atlas.columns, labels.atlas.rows],
+                first_char: labels.atlas.first_char as u32,
+                text,
+            };
+
+            unsafe {
+                self.device.cmd_push_constants(
+                    command_buffer,
+                    labels.pipeline_layout,
+                    vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
+                    0,
+                    bytemuck::bytes_of(&push_buffer),
+                );
+                // An instance per glyph.
+                self.device.cmd_draw(command_buffer, 6, length as u32, 0, 0);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the corner of the area showing an input that its label is placed at, relative
+/// to the viewport, and whether the label is right aligned to it. None for inputs the mode
+/// doesn't show side by side.
+fn label_corner(
+    info: &CompareInfo,
+    viewport: vk::Viewport,
+    input_count: usize,
+    index: usize,
+) -> Option<([f32; 2], bool)> {
+    if index >= input_count {
+        return None;
+    }
+
+    let corner = match info.mode {
+        CompareMode::Quad if index < 4 => [
+            (index % 2) as f32 * info.divider_position * viewport.width,
+            (index / 2) as f32 * info.divider_position_y * viewport.height,
+        ],
+        CompareMode::Split => [
+            split_position(info, input_count as u32, index as u32) * viewport.width,
+            0.0,
+        ],
+        CompareMode::Grid => {
+            let (columns, cell_width, cell_height) = grid_layout(info, viewport, input_count);
+            let gap = info.grid_gap as f32;
+            let index = index as u32;
+            [
+                (index % columns) as f32 * (cell_width + gap),
+                (index / columns) as f32 * (cell_height + gap),
+            ]
+        }
+        // The other modes show the first two inputs over the whole output, or on either
+        // side of the wipe divider.
+        _ if index == 0 => [0.0, 0.0],
+        _ if index == 1 && info.divider_orientation == DividerOrientation::Horizontal => {
+            [0.0, info.divider_position * viewport.height]
+        }
+        _ if index == 1 => return Some(([viewport.width, 0.0], true)),
+        _ => return None,
+    };
+    Some((corner, false))
+}
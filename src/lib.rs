@@ -4,72 +4,1127 @@
 //! Vulkan resources to render a side-by-side comparison of two images into a
 //! target image view.
 
-use anyhow::Result;
+use anyhow::{Result, anyhow};
 use derive_builder::Builder;
-use std::rc::Rc;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::path::Path;
+use std::sync::Arc;
 use vulkanalia::prelude::v1_3::*;
 
+use crate::color_transform::ColorTransformResources;
+use crate::custom_mode::{CustomMode, CustomModeId};
+use crate::inspect::InspectorResources;
+use crate::labels::{GlyphAtlas, LabelResources};
+use crate::lut::LutResources;
+use crate::offscreen::OwnedOutput;
+use crate::quality::{MetricsTimeline, QualityPipelines};
+use crate::scrub::Sequences;
+use crate::stats::StatsResources;
+use crate::timing::{GpuTimer, TimedPass};
+use crate::transfer::UploadContext;
 use crate::vulkan::{
-    descriptors::{create_descriptor_set, create_descriptor_set_layout, update_descriptor_sets},
-    pipeline::create_pipeline,
-    push_constants::PushConstantBuffer,
-    render_pass::create_render_pass,
+    commands::create_command_pool,
+    debug::set_object_name,
+    descriptors::{
+        MAX_INPUTS, cmd_push_input_descriptors, create_descriptor_pool,
+        create_descriptor_set_layout, create_input_descriptor_set,
+        create_metrics_descriptor_set_layout, input_bindings, update_descriptor_sets,
+        update_input_array,
+    },
+    image::texel_size,
+    pipeline::{
+        FragmentVariant, InputMapping, PipelineTarget, create_crosshair_pipeline,
+        create_crosshair_pipeline_layout, create_grid_pipeline, create_grid_pipeline_layout,
+        create_handle_pipeline, create_handle_pipeline_layout, create_label_pipeline,
+        create_metrics_pipeline, create_pipeline, create_pipeline_cache, create_pipeline_layout,
+        create_vector_pipeline, create_vector_pipeline_layout,
+    },
+    push_constants::{
+        BACKGROUND_SHIFT, CELL_SWAP_INPUTS, CHANNEL_SHIFT, CrosshairPushConstantBuffer,
+        EYE_INPUTS_SHIFT, FLAG_ALPHA_CHECKERBOARD, FLAG_DIFFERENCE_OUTPUT, FLAG_ENCODE_SRGB,
+        FLAG_EXTENT_CROP, FLAG_EXTENT_SCALE_TO_FIRST, FLAG_FIT_CONTAIN, FLAG_FIT_COVER,
+        FLAG_FIXED_LOD, FLAG_HIGHLIGHT_NON_FINITE, FLAG_HIGHLIGHTED, FLAG_NEAREST,
+        FLAG_SIGNED_DIFFERENCE, FLAG_TONE_MAP_ACES, FLAG_TONE_MAP_REINHARD, GridPushConstantBuffer,
+        HandlePushConstantBuffer, INPUT_BASE_SHIFT, LUT_INPUTS_SHIFT, PushConstantBuffer,
+        SRGB_INPUTS_SHIFT, VectorPushConstantBuffer, pack_half2,
+    },
+    render_pass::{create_framebuffer, create_render_pass},
     sampler::create_image_sampler,
 };
+use crate::ycbcr::{MAX_YCBCR_DESCRIPTOR_COUNT, YcbcrConversion};
 
+pub mod animation;
+pub mod batch;
+pub mod color_transform;
+pub mod compatibility;
+pub mod custom_mode;
+#[cfg(any(feature = "png", feature = "exr"))]
+pub mod export;
+#[cfg(feature = "headless")]
+pub mod headless;
+pub mod image_layers;
+pub mod input_image;
+pub mod inspect;
+pub mod interop;
+pub mod labels;
+#[cfg(feature = "io")]
+pub mod load;
+pub mod lut;
+pub mod mipmaps;
+pub mod offscreen;
+pub mod quality;
+pub mod scrub;
+pub mod stats;
+pub mod theme;
+pub mod thumbnails;
+pub mod timing;
+pub mod transfer;
+pub mod viewport;
 pub(crate) mod vulkan;
+pub mod ycbcr;
 
 /// A simple RGBA color struct.
 #[repr(C)]
-#[derive(Clone, Copy, Debug, Default, bytemuck::Pod, bytemuck::Zeroable)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Color(pub f32, pub f32, pub f32, pub f32);
 
+impl Color {
+    // The color as passed to the fragment shader, clamped to 8 bits per channel as read by
+    // `unpackUnorm4x8`.
+    pub(crate) fn pack_unorm(self) -> u32 {
+        let channel = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+        u32::from_le_bytes([
+            channel(self.0),
+            channel(self.1),
+            channel(self.2),
+            channel(self.3),
+        ])
+    }
+}
+
+/// How the input images are composed into the output.
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum CompareMode {
+    /// The first two inputs side by side, split by a vertical divider.
+    #[default]
+    Wipe = 0,
+    /// Four inputs in a 2x2 grid, split by a vertical and a horizontal divider.
+    /// The inputs fill the top-left, top-right, bottom-left and bottom-right quadrants.
+    Quad = 1,
+    /// All inputs in a grid of cells, filled row by row, like a contact sheet. Four inputs
+    /// make a 2x2 grid of whole quadrants, optionally apart by [`CompareInfo::grid_gap`].
+    Grid = 2,
+    /// Bit-exact equality of the first two inputs. Any pixel whose texels differ, however
+    /// slightly, is painted in the highlight color, matching pixels show the first input
+    /// dimmed. The mismatches are counted in [`stats::CompareStats::mismatched_pixels`].
+    Exact = 3,
+    /// The first two inputs blended on top of each other by
+    /// [`blend_factor`](CompareInfo::blend_factor), to ghost both frames of an animation.
+    Onion = 4,
+    /// The per-channel difference `abs(A - B)` of the first two inputs, amplified by
+    /// [`difference_gain`](CompareInfo::difference_gain) to make subtle differences visible.
+    Difference = 5,
+    /// The first two inputs alternating in a checkerboard of
+    /// [`checker_size`](CompareInfo::checker_size) pixel tiles, to spot compression artifacts.
+    Checkerboard = 6,
+    /// Either of the first two inputs over the whole output, selected by
+    /// [`flicker_second`](CompareInfo::flicker_second). Toggling it from frame to frame
+    /// flickers between the inputs without switching pipelines.
+    Flicker = 7,
+    /// All inputs side by side in vertical strips, split by a divider between each pair of
+    /// neighbours at [`split_positions`](CompareInfo::split_positions).
+    Split = 8,
+    /// The first input, with every pixel whose difference to the second exceeds
+    /// [`difference_threshold`](CompareInfo::difference_threshold) in any channel painted
+    /// in the highlight color. Matching pixels are shown at
+    /// [`threshold_brightness`](CompareInfo::threshold_brightness), to dim them if desired.
+    Threshold = 9,
+    /// The perceived color difference of the first two inputs, the CIEDE2000 Delta E of
+    /// their CIE L*a*b* values, in grayscale up to
+    /// [`delta_e_range`](CompareInfo::delta_e_range) as white. The inputs are taken as
+    /// linear sRGB and clipped to white. A Delta E around one is just noticeable.
+    DeltaE = 10,
+    /// The first two inputs blended per pixel by the red channel of the third input, the
+    /// mask: `0.0` shows the first, `1.0` the second and values in between mix both, for
+    /// reveals of any shape or comparing a region of interest only. The mask is sampled
+    /// like the other inputs, and shows the first input where it doesn't cover the output.
+    Mask = 11,
+}
+
+/// The direction of the divider line in [`CompareMode::Wipe`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum DividerOrientation {
+    /// A vertical line, the first input on the left and the second on the right.
+    #[default]
+    Vertical,
+    /// A horizontal line, the first input on top and the second below.
+    Horizontal,
+    /// A vertical line rotated clockwise by the angle in radians, e.g. `FRAC_PI_4` for a
+    /// diagonal from the top right to the bottom left. The first input is on the side that
+    /// is left of the unrotated line.
+    Angled(f32),
+}
+
+impl DividerOrientation {
+    // The clockwise angle from vertical.
+    fn angle(self) -> f32 {
+        match self {
+            Self::Vertical => 0.0,
+            Self::Horizontal => std::f32::consts::FRAC_PI_2,
+            Self::Angled(angle) => angle,
+        }
+    }
+}
+
+/// How inputs of another aspect ratio than the output are fit into it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum FitMode {
+    /// Stretched to fill the output.
+    #[default]
+    Stretch,
+    /// Scaled to fit within the output, keeping the aspect ratio. The bars beside the
+    /// input show the [`Background`].
+    Contain,
+    /// Scaled to cover the output, keeping the aspect ratio. The input is cropped.
+    Cover,
+}
+
+/// How inputs of different extents are reconciled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ExtentPolicy {
+    /// Every input is scaled to the output on its own, fit by its own aspect ratio.
+    #[default]
+    ScaleToOutput,
+    /// The other inputs are scaled to the extent of the first, so that all of them cover
+    /// the same area, fit by the first input's aspect ratio.
+    ScaleToFirst,
+    /// The other inputs keep the pixel size of the first, aligned at the top left. Larger
+    /// inputs are cropped, smaller ones are padded with the [`Background`]. Texels of
+    /// inputs of different extents correspond, also in [`CompareMode::Exact`].
+    Crop,
+}
+
+/// What is shown where zoom and pan expose areas outside the inputs, and the letterbox bars
+/// of [`FitMode::Contain`].
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Background {
+    /// The edge pixels of the inputs, smeared outwards.
+    Clamp,
+    /// A solid color.
+    Solid(Color),
+    /// Diagonal stripes of the color and the color at half brightness.
+    Hatched(Color),
+}
+
+impl Default for Background {
+    fn default() -> Self {
+        Self::Hatched(Color(0.3, 0.3, 0.3, 1.0))
+    }
+}
+
+impl Background {
+    // The pattern index and color as passed to the fragment shader.
+    fn shader_params(self) -> (u32, Color) {
+        match self {
+            Self::Clamp => (0, Color::default()),
+            Self::Solid(color) => (1, color),
+            Self::Hatched(color) => (2, color),
+        }
+    }
+}
+
+/// Arrows showing the difference `B - A` of vector data in the first two inputs, such as
+/// motion vectors or normals, whose first two channels are taken as the vector.
+#[derive(Clone, Copy, Debug)]
+pub struct VectorOverlay {
+    /// The distance between neighbouring arrows, in pixels.
+    pub spacing: u32,
+    /// The arrow length in pixels per unit of difference.
+    pub scale: f32,
+    /// The color of the arrows.
+    pub color: Color,
+}
+
+impl Default for VectorOverlay {
+    fn default() -> Self {
+        Self {
+            spacing: 16,
+            scale: 1.0,
+            color: Color(0.0, 1.0, 0.0, 1.0),
+        }
+    }
+}
+
+/// The shape of the [`DividerHandle`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum HandleShape {
+    /// A filled disc.
+    #[default]
+    Circle,
+    /// A chevron on either side of the divider, pointing away from it.
+    Chevrons,
+}
+
+/// A grab handle on the wipe divider, showing GUI users where to drag it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DividerHandle {
+    /// The position of the handle along the divider, in the range `[0.0, 1.0]`. Vertical
+    /// for vertical and angled dividers closer to vertical, horizontal otherwise.
+    pub position: f32,
+    /// The radius of the handle in pixels.
+    pub radius: f32,
+    /// Whether the handle is a disc or a pair of chevrons.
+    pub shape: HandleShape,
+    /// The color of the handle, `None` for the divider color.
+    pub color: Option<Color>,
+}
+
+impl Default for DividerHandle {
+    fn default() -> Self {
+        Self {
+            position: 0.5,
+            radius: 12.0,
+            shape: HandleShape::Circle,
+            color: None,
+        }
+    }
+}
+
+/// How the [`PixelGrid`] is drawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum PixelGridStyle {
+    /// Lines across the whole comparison.
+    #[default]
+    Lines,
+    /// Ticks along the top and left edges only, leaving the inputs unobstructed.
+    Rulers,
+}
+
+/// Pixel-wide lines at a regular spacing over the comparison, to judge the alignment of the
+/// inputs and measure the size of features. The lines start at the top left corner of the
+/// viewport.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PixelGrid {
+    /// The distance between neighbouring lines, in pixels.
+    pub spacing: f32,
+    /// Every this many lines is a major line, drawn at twice the opacity and with long ticks
+    /// on the rulers. `0` for none.
+    pub major_every: u32,
+    /// The color of the lines and ticks.
+    pub color: Color,
+    /// The opacity of the minor lines, multiplied with the alpha of the color.
+    pub opacity: f32,
+    /// Whether lines are drawn across the comparison or ticks along its edges.
+    pub style: PixelGridStyle,
+}
+
+impl Default for PixelGrid {
+    fn default() -> Self {
+        Self {
+            spacing: 10.0,
+            major_every: 10,
+            color: Color(1.0, 1.0, 1.0, 1.0),
+            opacity: 0.25,
+            style: PixelGridStyle::Lines,
+        }
+    }
+}
+
+/// Lines through a point, typically the cursor, across the whole comparison, so that the
+/// same pixel row and column can be compared on all sides of the dividers. In
+/// [`CompareMode::Grid`], drawn at the same point of every cell.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Crosshair {
+    /// The point the lines cross at, relative to the viewport, or to each cell of a grid.
+    pub position: [f32; 2],
+    /// The color of the lines.
+    pub color: Color,
+    /// The width of the lines in pixels.
+    pub width: f32,
+    /// The radius around the point left clear in pixels, so that the pixel itself stays
+    /// visible.
+    pub gap: f32,
+}
+
+impl Default for Crosshair {
+    fn default() -> Self {
+        Self {
+            position: [0.5, 0.5],
+            color: Color(1.0, 1.0, 1.0, 0.8),
+            width: 1.0,
+            gap: 4.0,
+        }
+    }
+}
+
+/// A single channel shown as grayscale, to track down issues confined to it.
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ChannelMask {
+    Red = 1,
+    Green = 2,
+    Blue = 3,
+    /// The alpha channel, shown opaque.
+    Alpha = 4,
+    /// The Rec. 709 luma of the linear color channels.
+    Luma = 5,
+}
+
+/// A checkerboard the shown inputs are composited over by their alpha, so that differences in
+/// transparency are visible.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AlphaCheckerboard {
+    /// The edge length of the tiles in pixels.
+    pub size: u32,
+    /// The colors of the tiles, alternating.
+    pub colors: [Color; 2],
+}
+
+impl Default for AlphaCheckerboard {
+    fn default() -> Self {
+        Self {
+            size: 8,
+            colors: [Color(0.8, 0.8, 0.8, 1.0), Color(0.6, 0.6, 0.6, 1.0)],
+        }
+    }
+}
+
+/// A circular loupe showing the comparison around a point enlarged, drawn over it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Magnifier {
+    /// The point the loupe is centered on and enlarges, relative to the output, in the range
+    /// `[0.0, 1.0]`. Typically the cursor.
+    pub center: [f32; 2],
+    /// The radius of the loupe in pixels.
+    pub radius: f32,
+    /// The magnification within the loupe, on top of [`CompareInfo::zoom`].
+    pub zoom: f32,
+}
+
+impl Default for Magnifier {
+    fn default() -> Self {
+        Self {
+            center: [0.5, 0.5],
+            radius: 128.0,
+            zoom: 4.0,
+        }
+    }
+}
+
+/// How the inputs are sampled. The default filters linearly, also between mip levels, and
+/// clamps to the edge.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SamplerConfig {
+    /// The filter when the inputs are minified, i.e. zoomed out.
+    pub min_filter: vk::Filter,
+    /// The filter when the inputs are magnified, i.e. zoomed in. `NEAREST` keeps the edges
+    /// of pixel art sharp.
+    pub mag_filter: vk::Filter,
+    /// The filter between mip levels.
+    pub mipmap_mode: vk::SamplerMipmapMode,
+    /// How horizontal coordinates outside the inputs are resolved. These only show up
+    /// within the filter footprint at the edges, as the background is shown outside.
+    pub address_mode_u: vk::SamplerAddressMode,
+    /// How vertical coordinates outside the inputs are resolved.
+    pub address_mode_v: vk::SamplerAddressMode,
+    /// The maximum anisotropy, or `None` to disable anisotropic filtering. Requires the
+    /// `samplerAnisotropy` device feature.
+    pub max_anisotropy: Option<f32>,
+    /// The color sampled outside the inputs with `CLAMP_TO_BORDER`.
+    pub border_color: vk::BorderColor,
+    /// The lowest mip level used, e.g. to leave out the full resolution level.
+    pub min_lod: f32,
+    /// The highest mip level used. `vk::LOD_CLAMP_NONE` uses all levels the inputs have,
+    /// `0.0` only the full resolution level.
+    pub max_lod: f32,
+    /// The bias added to the mip level the sampler picks. Positive biases sample coarser
+    /// levels.
+    pub mip_lod_bias: f32,
+}
+
+impl Default for SamplerConfig {
+    fn default() -> Self {
+        Self {
+            min_filter: vk::Filter::LINEAR,
+            mag_filter: vk::Filter::LINEAR,
+            mipmap_mode: vk::SamplerMipmapMode::LINEAR,
+            address_mode_u: vk::SamplerAddressMode::CLAMP_TO_EDGE,
+            address_mode_v: vk::SamplerAddressMode::CLAMP_TO_EDGE,
+            max_anisotropy: None,
+            border_color: vk::BorderColor::INT_OPAQUE_BLACK,
+            min_lod: 0.0,
+            max_lod: vk::LOD_CLAMP_NONE,
+            mip_lod_bias: 0.0,
+        }
+    }
+}
+
+/// Which mip level of the inputs is shown, e.g. to compare mip chains generated by
+/// different downsamplers. Always within the range of the [`SamplerConfig`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum InputLod {
+    /// The level the sampler picks for the footprint of each pixel.
+    #[default]
+    Auto,
+    /// The level the sampler picks, offset by the bias on top of
+    /// [`SamplerConfig::mip_lod_bias`].
+    Bias(f32),
+    /// This level, regardless of the zoom. Fractional levels blend the two nearest ones
+    /// with a linear `mipmap_mode`.
+    Level(f32),
+}
+
+/// The curve bringing HDR values into the displayable range, see [`ToneMapping`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ToneMapOperator {
+    /// Only scales by the exposure, clipping values above one.
+    ExposureOnly,
+    /// `x / (1 + x)`, which compresses highlights smoothly but desaturates them.
+    Reinhard,
+    /// An approximation of the ACES filmic curve, with more contrast than Reinhard.
+    #[default]
+    Aces,
+}
+
+/// How HDR inputs, e.g. of `R16G16B16A16_SFLOAT` views, are shown.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ToneMapping {
+    /// The curve applied after the exposure.
+    pub operator: ToneMapOperator,
+    /// The exposure in stops, scaling the values by `2^exposure` before the curve.
+    pub exposure: f32,
+}
+
+/// How the values sampled from an input are encoded. Inputs are normalized to linear before
+/// they are compared, so that inputs of different encodings can be compared directly.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum InputEncoding {
+    /// Linear values, as sampled from views of linear data, or from `_SRGB` views which the
+    /// hardware decodes.
+    #[default]
+    Linear,
+    /// sRGB encoded values sampled without decoding, such as from a `_UNORM` view of an sRGB
+    /// capture. They are decoded in the shader.
+    Srgb,
+}
+
+/// How stored depth values are spread over the shown range, see [`DepthRange`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DepthCurve {
+    /// Evenly, by the difference to `near`.
+    #[default]
+    Linear,
+    /// By the ratio to `near`, which spreads the values close to it, e.g. to tell apart
+    /// nearby surfaces of a perspective depth buffer. Both ends of the range must be
+    /// positive.
+    Logarithmic,
+}
+
+/// Shows depth inputs, such as shadow maps or depth prepasses, as grayscale. The depth, read
+/// from the red channel, is remapped from `[near, far]` to black to white before it is
+/// compared, so that the range of interest fills the shown values. Values outside of it are
+/// clamped. `near` may be greater than `far`, e.g. for reversed depth.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DepthRange {
+    /// The depth shown black.
+    pub near: f32,
+    /// The depth shown white.
+    pub far: f32,
+    pub curve: DepthCurve,
+}
+
+impl Default for DepthRange {
+    fn default() -> Self {
+        Self {
+            near: 0.0,
+            far: 1.0,
+            curve: DepthCurve::Linear,
+        }
+    }
+}
+
+/// The amount of colors of an [`IntegerMapping::Palette`].
+pub const MAX_PALETTE_COLORS: usize = 16;
+
+/// How the values of integer inputs are shown, see
+/// [`RenderTargetComparatorCreateInfo::integer_inputs`].
+#[derive(Clone, Debug)]
+pub enum IntegerMapping {
+    /// Divides the color channels by the value, e.g. 255 for `R8G8B8A8_UINT` views, or the
+    /// highest ID of an ID buffer. The alpha is opaque. With [`CompareInfo::channel_mask`],
+    /// a single channel is shown as grayscale.
+    Normalize(u32),
+    /// Colors the values of the red channel, such as object or material IDs, from the
+    /// palette, repeating it for values past its end. Holds at most [`MAX_PALETTE_COLORS`].
+    Palette(Vec<Color>),
+}
+
+/// How the values written to the output are encoded. All modes work on linear values.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum OutputEncoding {
+    /// Linear values, for `_SRGB` outputs which the hardware encodes, and outputs of
+    /// linear data.
+    #[default]
+    Linear,
+    /// sRGB encoded values, for `_UNORM` outputs that are displayed as sRGB, such as
+    /// `_UNORM` swapchain images. They are encoded in the shader, clamped to `[0.0, 1.0]`.
+    Srgb,
+}
+
+/// The data written by a comparator created for difference output. Such a comparator
+/// renders the raw per-channel difference of the first two inputs, without divider or any
+/// other decoration, for use as the input of further analysis passes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DifferenceOutput {
+    /// `abs(A - B)` in an 8-bit normalized attachment.
+    Absolute,
+    /// `abs(A - B)` in a 16-bit float attachment, for differences finer than 1/255.
+    AbsoluteFloat,
+    /// `A - B`, keeping the sign, in a 16-bit float attachment.
+    Signed,
+}
+
+impl DifferenceOutput {
+    /// Returns the attachment format used for this kind of output. The output image must be
+    /// created with this format.
+    pub fn format(self) -> vk::Format {
+        match self {
+            Self::Absolute => vk::Format::R8G8B8A8_UNORM,
+            Self::AbsoluteFloat | Self::Signed => vk::Format::R16G16B16A16_SFLOAT,
+        }
+    }
+}
+
 /// Configuration for a single frame comparison operation.
 #[derive(Builder, Clone, Copy, Debug)]
-#[builder(setter(into))]
-pub struct CompareInfo {
+#[builder(setter(into), build_fn(validate = "Self::validate"))]
+pub struct CompareInfo<'a> {
     /// The command buffer to record drawing commands into.
     #[builder(default)]
     pub command_buffer: vk::CommandBuffer,
-    /// The horizontal position of the divider, in the range `[0.0, 1.0]`.
+    /// How the inputs are composed.
+    #[builder(default)]
+    pub mode: CompareMode,
+    /// A custom mode registered with [`RenderTargetComparator::register_custom_mode`] to
+    /// compose the inputs with instead of `mode`, which still selects how the parameters are
+    /// passed, see the [`custom_mode`] module. Not used by batch comparisons.
+    #[builder(default = "None")]
+    pub custom_mode: Option<CustomModeId>,
+    /// The position of the divider, in the range `[0.0, 1.0]`. Horizontal for a vertical
+    /// divider, vertical for a horizontal one. Angled dividers pass through this position
+    /// on both axes.
     #[builder(default = "0.5_f32")]
     pub divider_position: f32,
+    /// The weight of the second input in [`CompareMode::Onion`], in the range `[0.0, 1.0]`.
+    #[builder(default = "0.5_f32")]
+    pub blend_factor: f32,
+    /// The factor differences are multiplied by in [`CompareMode::Difference`].
+    #[builder(default = "1.0_f32")]
+    pub difference_gain: f32,
+    /// The largest difference per channel (RGBA) still considered a match in
+    /// [`CompareMode::Threshold`]. Passed as half floats.
+    #[builder(default = "[0.0; 4]")]
+    pub difference_threshold: [f32; 4],
+    /// The factor the color of matching pixels is multiplied by in
+    /// [`CompareMode::Threshold`], e.g. `0.25` to dim them like [`CompareMode::Exact`].
+    #[builder(default = "1.0_f32")]
+    pub threshold_brightness: f32,
+    /// The Delta E shown as white in [`CompareMode::DeltaE`]. Larger differences clip.
+    #[builder(default = "10.0_f32")]
+    pub delta_e_range: f32,
+    /// The edge length of the tiles in [`CompareMode::Checkerboard`], in pixels.
+    #[builder(default = "32_u32")]
+    pub checker_size: u32,
+    /// Shows the second input instead of the first in [`CompareMode::Flicker`]. For timed
+    /// flicker, derive it from the time, e.g. `(seconds * 2.0) as u64 % 2 == 1`.
+    #[builder(default = "false")]
+    pub flicker_second: bool,
+    /// The horizontal positions of the dividers in [`CompareMode::Split`], ascending in the
+    /// range `[0.0, 1.0]`, one less than there are inputs. Empty splits evenly.
+    #[builder(default)]
+    pub split_positions: &'a [f32],
+    /// The frame in flight whose inputs are compared, see
+    /// [`RenderTargetComparatorCreateInfo::frames_in_flight`]. Taken modulo their amount.
+    #[builder(default = "0_usize")]
+    pub frame_index: usize,
+    /// Renders into this output image view instead of the comparator's, e.g. the current
+    /// swapchain image. It must be of the comparator's output format and extent, and have
+    /// been passed to [`RenderTargetComparator::prepare`]. Ignored when recording into the
+    /// caller's render pass.
+    #[builder(default = "None")]
+    pub out_image_view: Option<vk::ImageView>,
+    /// Overrides the comparator's viewport for this comparison, e.g. to draw into a part of
+    /// the output, or into outputs of other sizes within the caller's render pass. Ignored
+    /// by batch comparisons, which lay out their own viewports.
+    #[builder(default = "None")]
+    pub viewport: Option<vk::Viewport>,
+    /// Renders only into this region of the output, e.g. a docked panel of an editor,
+    /// leaving the rest of the output as it was. The comparison fills the region unless a
+    /// `viewport` is set, and nothing is drawn outside of it. With a render pass, the output
+    /// is then expected in `final_layout`, as left by a previous comparison. Ignored by batch
+    /// comparisons.
+    #[builder(default = "None")]
+    pub render_area: Option<vk::Rect2D>,
+    /// The direction of the divider in [`CompareMode::Wipe`].
+    #[builder(default)]
+    pub divider_orientation: DividerOrientation,
+    /// The vertical position of the horizontal divider in [`CompareMode::Quad`],
+    /// in the range `[0.0, 1.0]`.
+    #[builder(default = "0.5_f32")]
+    pub divider_position_y: f32,
     /// The width of the divider line in pixels.
     #[builder(default = "4_u8")]
     pub divider_width: u8,
     /// The color of the divider line.
     #[builder(default)]
     pub divider_color: Color,
+    /// The amount of columns in [`CompareMode::Grid`]. `0` picks a square-ish layout.
+    #[builder(default = "0_u32")]
+    pub grid_columns: u32,
+    /// The space between the cells in [`CompareMode::Grid`], in pixels. It's left as
+    /// cleared, or as drawn before with no clear color or within the caller's render pass.
+    #[builder(default = "0_u32")]
+    pub grid_gap: u32,
+    /// The index of the grid cell to highlight in [`CompareMode::Grid`].
+    #[builder(default = "None")]
+    pub highlighted_cell: Option<u32>,
+    /// The border color of the highlighted grid cell, and the color of differing pixels
+    /// in [`CompareMode::Exact`] and [`CompareMode::Threshold`].
+    #[builder(default = "Color(1.0, 0.8, 0.0, 1.0)")]
+    pub highlight_color: Color,
+    /// The magnification of the inputs around the center of the output (or of each cell).
+    #[builder(default = "1.0_f32")]
+    pub zoom: f32,
+    /// The offset of the inputs' sampled area, in texture coordinates.
+    #[builder(default = "[0.0, 0.0]")]
+    pub pan: [f32; 2],
+    /// The largest channel difference of a pixel above which it counts towards
+    /// [`CompareStats::pixels_above_threshold`](stats::CompareStats::pixels_above_threshold).
+    #[builder(default = "0.0_f32")]
+    pub stats_threshold: f32,
+    /// Paints NaN and infinite texels of the shown inputs in `non_finite_color`, to spot
+    /// them in HDR buffers at a glance. Has no effect on difference output.
+    #[builder(default = "false")]
+    pub highlight_non_finite: bool,
+    /// Shows the inputs' texels unfiltered, regardless of the sampler, so that single pixel
+    /// differences stay visible when zoomed in. Reads the full resolution level even when
+    /// zoomed out.
+    #[builder(default = "false")]
+    pub nearest: bool,
+    /// The mip level the inputs are shown at. With `nearest`, [`InputLod::Level`] shows the
+    /// texels of the nearest level instead of the full resolution one, and biases are
+    /// ignored.
+    #[builder(default)]
+    pub lod: InputLod,
+    /// Tone maps the shown inputs, so that HDR inputs don't clip to white. Differences are
+    /// computed from the untouched values.
+    #[builder(default = "None")]
+    pub tone_mapping: Option<ToneMapping>,
+    /// The offset each input is shifted by before it is compared, in its pixels, by index.
+    /// Aligns captures that are slightly shifted against each other. Inputs without an
+    /// entry stay in place. Applies to everything the comparison shows and its statistics,
+    /// but not to the metrics of batch comparisons. Offsets are passed as half floats,
+    /// precise to a sixteenth of a pixel up to 64 pixels.
+    #[builder(default)]
+    pub input_offsets: &'a [[f32; 2]],
+    /// The indices of the inputs the comparator's lookup table is applied to, see
+    /// [`RenderTargetComparatorCreateInfo::lut`]. Ignored without one.
+    #[builder(default)]
+    pub lut_inputs: &'a [usize],
+    /// The color of highlighted NaN and infinite texels.
+    #[builder(default = "Color(1.0, 0.0, 1.0, 1.0)")]
+    pub non_finite_color: Color,
+    /// How inputs of another aspect ratio than the output (or cell) are fit into it.
+    #[builder(default)]
+    pub fit: FitMode,
+    /// How inputs of different extents are reconciled. By default each covers the output,
+    /// which only lines up inputs that are scaled versions of each other.
+    #[builder(default)]
+    pub extent_policy: ExtentPolicy,
+    /// What is shown outside the inputs when zoomed out, panned past their edges, or beside
+    /// inputs fit with [`FitMode::Contain`]. The letterbox color is a [`Background::Solid`].
+    #[builder(default)]
+    pub background: Background,
+    /// A label wrapping the recorded commands, to tell comparisons apart in GPU captures.
+    /// Only recorded by comparators created with `debug_utils`, which label comparisons
+    /// without one `rtcmp::compare`.
+    #[builder(default = "None")]
+    pub label: Option<&'a str>,
+    /// The color of the label in GPU captures. All zeros leave it to the tool.
+    #[builder(default)]
+    pub label_color: Color,
+    /// Draws arrows of the vector difference of the first two inputs over the comparison.
+    /// Not drawn by batch comparisons.
+    #[builder(default = "None")]
+    pub vector_overlay: Option<VectorOverlay>,
+    /// Draws a grab handle on the divider in [`CompareMode::Wipe`]. Not drawn by batch
+    /// comparisons, nor with difference output.
+    #[builder(default = "None")]
+    pub divider_handle: Option<DividerHandle>,
+    /// Draws a pixel grid or rulers over the comparison. Not drawn by batch comparisons, nor
+    /// with difference output.
+    #[builder(default = "None")]
+    pub pixel_grid: Option<PixelGrid>,
+    /// Draws a crosshair over the comparison. Not drawn by batch comparisons, nor with
+    /// difference output.
+    #[builder(default = "None")]
+    pub crosshair: Option<Crosshair>,
+    /// Draws a loupe enlarging the comparison around a point over it. Within the loupe, the
+    /// inputs stay on their side of the divider. Not drawn in [`CompareMode::Grid`] and
+    /// [`CompareMode::Split`], nor with difference output.
+    #[builder(default = "None")]
+    pub magnifier: Option<Magnifier>,
+    /// Composites the shown inputs over a checkerboard by their alpha instead of writing
+    /// it out, so that differences in transparency are visible. The tiles are in screen
+    /// space, and line up across dividers.
+    #[builder(default = "None")]
+    pub alpha_checkerboard: Option<AlphaCheckerboard>,
+    /// Shows only this channel of the inputs as grayscale, on all sides of the comparison.
+    /// In [`CompareMode::Difference`], shows the channel of the difference.
+    #[builder(default = "None")]
+    pub channel_mask: Option<ChannelMask>,
+    /// Names of the inputs, drawn in the corner of the area showing each, with the glyph
+    /// atlas of [`RenderTargetComparatorCreateInfo::glyph_atlas`]. Labels past the shown
+    /// inputs are ignored. Not drawn by batch comparisons, nor with difference output.
+    #[builder(default)]
+    pub input_labels: &'a [&'a str],
+    /// The color of the input labels.
+    #[builder(default = "Color(1.0, 1.0, 1.0, 1.0)")]
+    pub input_label_color: Color,
+    /// The height of the input labels' glyphs in pixels.
+    #[builder(default = "16.0_f32")]
+    pub input_label_size: f32,
+    /// Shows the first two inputs in each other's place, e.g. to flip sides with a hotkey or
+    /// for blind A/B tests, without updating the descriptor sets. Everything set per input,
+    /// such as its encoding, alignment offset and label, moves along with it, and the
+    /// vectors of the vector overlay are reversed.
+    #[builder(default = "false")]
+    pub swap_inputs: bool,
+    /// The element of the bindless input array the first input is read from, the others
+    /// following it, e.g. to pick one of hundreds of candidate frames per draw, see
+    /// [`RenderTargetComparatorCreateInfo::bindless_inputs`]. Everything set per input, such
+    /// as its encoding and alignment offset, still applies by its index. Must be zero without
+    /// bindless inputs, and for batch comparisons and scrubbing.
+    #[builder(default = "0")]
+    pub input_base: u32,
+    /// The color the output is cleared to before the comparison is drawn. `None` keeps its
+    /// content instead, e.g. to draw into the viewport of an editor without wiping the rest
+    /// of it. The output is then expected in `final_layout` with a render pass, as left by a
+    /// previous comparison. Unused when recording into rendering begun by the caller, and
+    /// batch comparisons always clear, to black without a color.
+    #[builder(default = "Some(Color(0.0, 0.0, 0.0, 1.0))")]
+    pub clear_color: Option<Color>,
+    /// Blends the comparison over the output's content with this opacity in `[0.0, 1.0]`,
+    /// e.g. to fade a debug overlay in and out over the caller's scene. The content is kept
+    /// with no `clear_color`, or when recording into the caller's render pass or rendering.
+    /// Overlays such as labels are drawn over it unfaded. Ignored with difference output and
+    /// by batch comparisons.
+    #[builder(default = "None")]
+    pub opacity: Option<f32>,
 }
 
-impl CompareInfo {
-    pub fn builder() -> CompareInfoBuilder {
+impl<'a> CompareInfo<'a> {
+    pub fn builder() -> CompareInfoBuilder<'a> {
         CompareInfoBuilder::default()
     }
 }
 
+impl CompareInfoBuilder<'_> {
+    // Values outside of the output would silently draw a divider off screen.
+    fn validate(&self) -> std::result::Result<(), String> {
+        let fractions = [
+            ("divider position", self.divider_position),
+            ("vertical divider position", self.divider_position_y),
+            ("blend factor", self.blend_factor),
+        ];
+        for (name, value) in fractions {
+            if let Some(value) = value.filter(|value| !(0.0..=1.0).contains(value)) {
+                return Err(format!(
+                    "The {} must be in the range [0.0, 1.0], got {}.",
+                    name, value
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+// The input descriptor set of a frame in flight, and the amount of inputs it binds.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct FrameInputs {
+    // Null with push descriptors, which push the views when recording instead.
+    pub(crate) descriptor_set: vk::DescriptorSet,
+    pub(crate) image_views: [vk::ImageView; MAX_INPUTS],
+    pub(crate) input_count: usize,
+}
+
+// How the inputs of a comparison are bound when recording.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum InputBinding {
+    // A descriptor set written beforehand.
+    Set(vk::DescriptorSet),
+    // Views pushed into the command buffer, with push descriptors.
+    Pushed([vk::ImageView; MAX_INPUTS]),
+}
+
+// The kinds of pipelines the comparator creates when they are prepared.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum PipelineKind {
+    Compare(CompareMode),
+    // The comparison blended over the output by an opacity.
+    Composite(CompareMode),
+    // A registered custom mode, and whether it is blended like `Composite`.
+    Custom(CustomModeId, bool),
+    Vectors,
+    Handle,
+    PixelGrid,
+    Crosshair,
+    Labels,
+}
+
+// How the pipelines are recorded: within the comparator's render pass (or one compatible with
+// it), the caller's subpass, or dynamic rendering.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Recording {
+    RenderPass,
+    Subpass,
+    DynamicRendering,
+}
+
+// What a comparison renders into.
+#[derive(Clone, Copy, Debug)]
+enum Output {
+    // A framebuffer of the comparator's render pass.
+    Framebuffer(vk::RenderPass, vk::Framebuffer),
+    // An image view, with dynamic rendering.
+    View(vk::ImageView),
+}
+
+impl Output {
+    // How the pipelines are recorded when rendering into the output.
+    fn recording(self) -> Recording {
+        match self {
+            Output::Framebuffer(..) => Recording::RenderPass,
+            Output::View(_) => Recording::DynamicRendering,
+        }
+    }
+}
+
+/// A subpass of a render pass owned by the caller.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Subpass {
+    /// The render pass.
+    pub render_pass: vk::RenderPass,
+    /// The index of the subpass within the render pass.
+    pub index: u32,
+}
+
 /// Configuration for the comparator.
 #[derive(Builder, Clone, Debug)]
 #[builder(setter(into), build_fn(name = "build"))]
 pub struct RenderTargetComparatorCreateInfo {
     /// The Vulkan logical device.
-    pub device: Rc<Device>,
-    /// The descriptor pool to allocate from.
-    pub descriptor_pool: vk::DescriptorPool,
-    /// The format of the output image.
+    pub device: Arc<Device>,
+    /// The descriptor pool to allocate the input descriptor sets from, one per frame in flight
+    /// with [`RenderTargetComparator::image_sampler_count`] image samplers each. Without one,
+    /// the comparator creates and owns a pool of that size. Unused with `push_descriptors`.
+    #[builder(default = "None")]
+    pub descriptor_pool: Option<vk::DescriptorPool>,
+    /// Pushes the inputs into the command buffer when recording, instead of allocating a
+    /// descriptor set per frame in flight, so no descriptor pool is needed and the inputs
+    /// may be replaced while comparisons are pending. Requires the device to be created with
+    /// the `VK_KHR_push_descriptor` extension, leave it unset to fall back to descriptor sets
+    /// where it isn't available. Batch comparisons, scrubbing, the quality metrics and pixel
+    /// reads bind descriptor sets, so they are not available.
+    #[builder(default = "false")]
+    pub push_descriptors: bool,
+    /// Binds an array of this many input views at once with descriptor indexing, written
+    /// with [`RenderTargetComparator::write_input_array`], so that hundreds of candidate
+    /// frames can be bound once and selected per comparison by [`CompareInfo::input_base`].
+    /// The first eight elements hold the inputs of the frame in flight, which the vector
+    /// overlay, the quality metrics and pixel reads keep reading regardless of the base.
+    /// Must be between 8 and 65536. Requires the `descriptorBindingPartiallyBound` and
+    /// `descriptorBindingVariableDescriptorCount` device features, and a descriptor pool
+    /// of this many image samplers per frame in flight. Not available with push
+    /// descriptors or a YCbCr conversion.
+    #[builder(default = "None")]
+    pub bindless_inputs: Option<u32>,
+    /// The format of the output image. Not needed with `difference_output`, which picks
+    /// the format itself.
+    #[builder(default)]
     pub format: vk::Format,
+    /// The properties of the output format, as returned by
+    /// `get_physical_device_format_properties`. When set, the format is checked to support
+    /// color attachments with optimal tiling at creation.
+    #[builder(default = "None")]
+    pub format_properties: Option<vk::FormatProperties>,
     /// The extent (width and height) of the output image.
     pub extent: vk::Extent2D,
-    /// The two input image views to compare.
-    pub in_image_views: [vk::ImageView; 2],
-    /// The output image view to render into.
+    /// The input image views to compare. Two for the wipe, four for the quad view and
+    /// up to eight for the grid. Quadrants without an input of their own repeat the first inputs.
+    pub in_image_views: Vec<vk::ImageView>,
+    /// The encoding of each input, by index. Inputs without an entry are linear. Applies
+    /// likewise to the inputs of registered sequences and batch items.
+    #[builder(default)]
+    pub input_encodings: Vec<InputEncoding>,
+    /// The encoding of the values written to the output. Without the right one, inputs
+    /// and output of different encodings come out brighter or darker than they are.
+    /// Ignored with `difference_output`, which writes raw data.
+    #[builder(default)]
+    pub output_encoding: OutputEncoding,
+    /// The output image view to render into. Not needed by
+    /// [`RenderTargetComparator::offscreen`], which creates its own output.
+    #[builder(default)]
     pub out_image_view: vk::ImageView,
     /// The final layout of the output image after rendering.
     #[builder(default = "vk::ImageLayout::PRESENT_SRC_KHR")]
     pub final_layout: vk::ImageLayout,
+    /// How the inputs are sampled, also by the metrics of batch comparisons.
+    #[builder(default)]
+    pub sampler: SamplerConfig,
     // The viewport
     #[builder(default = "None")]
     pub viewport: Option<vk::Viewport>,
+    /// The memory properties of the physical device, as returned by
+    /// `get_physical_device_memory_properties`. Required by features that allocate
+    /// memory, such as batch comparisons with metrics.
+    #[builder(default = "None")]
+    pub memory_properties: Option<vk::PhysicalDeviceMemoryProperties>,
+    /// Gives quality metrics queries a timeline semaphore that submissions signal to values
+    /// of the caller's choosing, see [`quality::QueryCompletion`], so that engines with their
+    /// own submission schedulers can poll for the results instead of waiting for the queue to
+    /// be idle. Requires the `timelineSemaphore` device feature.
+    #[builder(default = "false")]
+    pub metrics_timeline: bool,
+    /// Renders pure difference data instead of the comparison, see [`DifferenceOutput`].
+    #[builder(default = "None")]
+    pub difference_output: Option<DifferenceOutput>,
+    /// Accumulates difference statistics of the first two inputs during every comparison,
+    /// see [`RenderTargetComparator::stats`]. Requires `memory_properties` and the
+    /// `fragmentStoresAndAtomics` device feature.
+    #[builder(default = "false")]
+    pub stats: bool,
+    /// The `timestampPeriod` limit of the physical device, in nanoseconds per tick. Times
+    /// comparisons and metric passes on the GPU, see
+    /// [`RenderTargetComparator::last_gpu_time`]. The queue family the comparisons are
+    /// submitted to must support timestamps.
+    #[builder(default = "None")]
+    pub timestamp_period: Option<f32>,
+    /// Applies a color transform to each input before comparing, see
+    /// [`RenderTargetComparator::set_color_transform`]. Requires `memory_properties`.
+    #[builder(default = "false")]
+    pub color_transforms: bool,
+    /// A 3D lookup table applied to the inputs in [`CompareInfo::lut_inputs`], see the
+    /// [`lut`] module. The view must be of a 3D image in `SHADER_READ_ONLY_OPTIMAL` layout.
+    #[builder(default = "None")]
+    pub lut: Option<vk::ImageView>,
+    /// The glyphs drawing [`CompareInfo::input_labels`], see the [`labels`] module.
+    #[builder(default = "None")]
+    pub glyph_atlas: Option<GlyphAtlas>,
+    /// The pipeline cache all pipelines are created with. Without one, the comparator
+    /// creates and owns a cache, see [`RenderTargetComparator::save_pipeline_cache`].
+    #[builder(default = "None")]
+    pub pipeline_cache: Option<vk::PipelineCache>,
+    /// The initial contents of the cache the comparator creates, as written by
+    /// [`RenderTargetComparator::save_pipeline_cache`]. Data of another driver or device
+    /// is ignored. Unused with `pipeline_cache`.
+    #[builder(default)]
+    pub pipeline_cache_data: Vec<u8>,
+    /// A queue for the comparator's own one-shot work, such as uploads and mip generation,
+    /// see [`RenderTargetComparator::upload_context`]. Must support graphics operations.
+    #[builder(default = "None")]
+    pub queue: Option<vk::Queue>,
+    /// The family of `queue`. The comparator creates and owns a command pool for it.
+    #[builder(default = "None")]
+    pub queue_family_index: Option<u32>,
+    /// Allows comparing within dynamic rendering, see
+    /// [`RenderTargetComparator::compare_in_current_rendering`]. Requires the
+    /// `dynamicRendering` device feature.
+    #[builder(default = "false")]
+    pub dynamic_rendering: bool,
+    /// Uses dynamic rendering for all comparisons and creates no render pass or framebuffers,
+    /// implying `dynamic_rendering`. [`RenderTargetComparator::compare`] then begins rendering
+    /// itself, and expects the output in `COLOR_ATTACHMENT_OPTIMAL` layout, leaving it there
+    /// instead of transitioning it to `final_layout`. Batch comparisons and recording into
+    /// the caller's render pass are not available.
+    #[builder(default = "false")]
+    pub dynamic_rendering_only: bool,
+    /// A subpass of the caller's render pass to record into with
+    /// [`RenderTargetComparator::compare_in_current_pass`], instead of subpasses compatible
+    /// with the comparator's own render pass. It must have a single color attachment, without
+    /// multisampling, and remain valid for the lifetime of the comparator.
+    #[builder(default = "None")]
+    pub subpass: Option<Subpass>,
+    /// Whether the instance was created with the `VK_EXT_debug_utils` extension, which
+    /// enables [`CompareInfo::label`] and names the objects the comparator creates, e.g.
+    /// `rtcmp::render_pass`.
+    #[builder(default = "false")]
+    pub debug_utils: bool,
+    /// The amount of frames whose comparisons may be pending execution at once. Each has its
+    /// own input descriptor set, selected by [`CompareInfo::frame_index`], so that the inputs
+    /// of one frame can be replaced while others are in flight.
+    #[builder(default = "1_usize")]
+    pub frames_in_flight: usize,
+    /// The modes whose pipelines are created upfront. Others must be prepared with
+    /// [`RenderTargetComparator::prepare`] before they are compared in.
+    #[builder(default = "vec![CompareMode::Wipe]")]
+    pub modes: Vec<CompareMode>,
+    /// The SPIR-V of a fragment shader replacing the built-in comparison shader in all modes,
+    /// e.g. a custom comparison. Its entry point must be `main`, and it receives the same
+    /// push constants, descriptor bindings and specialization constants as the built-in
+    /// shader in `src/vulkan/shaders/shader.frag`, including the descriptor sets of the
+    /// enabled features such as `stats`.
+    #[builder(default = "None")]
+    pub fragment_shader_spirv: Option<Vec<u8>>,
+    /// Renders to both layers of a stereo output in a single pass with multiview, e.g. to
+    /// compare VR eye buffers. The output views must be 2D array views of two layers.
+    /// The inputs are split in half, the first for the left eye (layer 0) and the second for
+    /// the right, e.g. `[a_left, b_left, a_right, b_right]`, so at least four inputs are
+    /// bound. Overlays are drawn the same for both eyes. Rendering begun by the caller, and
+    /// its subpass, must have the view mask `0b11`. Requires the `multiview` device feature.
+    /// Batch comparisons are not available.
+    #[builder(default = "false")]
+    pub multiview: bool,
+    /// Samples multi-planar inputs, such as NV12 or P010 video frames, through the
+    /// conversion, see the [`ycbcr`] module. All inputs, also of registered sequences, must
+    /// then be views created with [`YcbcrConversion::create_view`]. Batch comparisons and
+    /// features reading unfiltered texels are not available.
+    #[builder(default = "None")]
+    pub ycbcr_conversion: Option<Arc<YcbcrConversion>>,
+    /// Compares depth inputs as grayscale, see [`DepthRange`]. Applies to all inputs, which
+    /// are views of the `DEPTH` aspect of depth images, such as of `D32_SFLOAT`. The range is
+    /// baked into the comparison pipelines. Formats without linear filtering support, such
+    /// as `D24_UNORM_S8_UINT` on some devices, must be sampled with `NEAREST` filters.
+    #[builder(default = "None")]
+    pub depth_range: Option<DepthRange>,
+    /// Samples the inputs as unsigned integers, such as of `R32_UINT` ID buffers or
+    /// `R8G8B8A8_UINT` masks, mapped to colors before they are compared. All inputs must then
+    /// be views of unsigned integer formats. They are read without filtering, regardless of
+    /// `sampler`. Not available with color transforms or a lookup table, and neither are
+    /// vector overlays, batch comparisons, the quality metrics and pixel reads.
+    #[builder(default = "None")]
+    pub integer_inputs: Option<IntegerMapping>,
 }
 
 impl RenderTargetComparatorCreateInfo {
@@ -78,160 +1133,2384 @@ impl RenderTargetComparatorCreateInfo {
     }
 }
 
+impl RenderTargetComparatorCreateInfoBuilder {
+    /// Builds the configuration and creates the comparator from it. Fails with the name of
+    /// the first required field that wasn't set, or like [`RenderTargetComparator::new`].
+    pub fn create(&self) -> Result<RenderTargetComparator> {
+        RenderTargetComparator::new(&self.build()?)
+    }
+
+    /// Builds the configuration and creates an offscreen comparator from it, see
+    /// [`RenderTargetComparator::offscreen`].
+    pub fn create_offscreen(&self) -> Result<RenderTargetComparator> {
+        RenderTargetComparator::offscreen(&self.build()?)
+    }
+}
+
 /// A reusable Vulkan utility for rendering a side-by-side image comparison.
+///
+/// Everything that changes the comparator, such as preparing pipelines or registering
+/// sequences, takes `&mut self`. Recording only takes `&self` and changes nothing, so a
+/// shared comparator can record into command buffers of several threads at once. Such
+/// recordings share the statistics buffer and must not execute concurrently with
+/// statistics enabled.
 #[derive(Debug)]
 pub struct RenderTargetComparator {
-    render_pass: vk::RenderPass,
-    device: Rc<Device>,
+    // None when only dynamic rendering is used.
+    render_pass: Option<vk::RenderPass>,
+    // Like the render pass, but keeping the output's content instead of clearing it.
+    load_render_pass: Option<vk::RenderPass>,
+    subpass: Option<Subpass>,
+    device: Arc<Device>,
     descriptor_set_layout: vk::DescriptorSetLayout,
-    descriptor_set: vk::DescriptorSet,
+    // The layout of the pushed inputs, which replaces the input descriptor set layout in the
+    // graphics pipeline layouts. None without push descriptors.
+    push_descriptor_set_layout: Option<vk::DescriptorSetLayout>,
+    // The size of the input array with bindless inputs.
+    bindless_inputs: Option<u32>,
+    // The pool the input descriptor sets are allocated from, if the comparator created it.
+    owned_descriptor_pool: Option<vk::DescriptorPool>,
+    // The inputs of each frame in flight.
+    frames: Vec<FrameInputs>,
+    // The input views created by `from_image_layers`.
+    owned_input_views: Vec<vk::ImageView>,
+    pipeline_cache: vk::PipelineCache,
+    // The pipeline cache, if the comparator created it.
+    owned_pipeline_cache: Option<vk::PipelineCache>,
     pipeline_layout: vk::PipelineLayout,
-    pipeline: vk::Pipeline,
+    vector_pipeline_layout: vk::PipelineLayout,
+    handle_pipeline_layout: vk::PipelineLayout,
+    grid_pipeline_layout: vk::PipelineLayout,
+    crosshair_pipeline_layout: vk::PipelineLayout,
+    // The custom comparison shader replacing the built-in variants.
+    fragment_shader: Option<Vec<u8>>,
+    // The registered custom modes, by id.
+    custom_modes: Vec<CustomMode>,
+    // The prepared pipelines, for render pass (false) or dynamic rendering (true) use.
+    pipelines: HashMap<(PipelineKind, Recording), vk::Pipeline>,
+    format: vk::Format,
+    final_layout: vk::ImageLayout,
+    dynamic_rendering: bool,
+    multiview: bool,
+    // Kept alive, as its sampler is immutable in the input descriptor set layout.
+    ycbcr_conversion: Option<Arc<YcbcrConversion>>,
+    depth_range: Option<DepthRange>,
+    integer_inputs: Option<IntegerMapping>,
+    metrics_descriptor_set_layout: vk::DescriptorSetLayout,
+    metrics_pipeline_layout: vk::PipelineLayout,
+    metrics_pipeline: vk::Pipeline,
     extent: vk::Extent2D,
+    viewport: vk::Viewport,
+    memory_properties: Option<vk::PhysicalDeviceMemoryProperties>,
     sampler: vk::Sampler,
-    framebuffer: vk::Framebuffer,
+    // The output view rendered into by default, and the framebuffers of all prepared output
+    // views including it.
+    out_image_view: vk::ImageView,
+    framebuffers: HashMap<vk::ImageView, vk::Framebuffer>,
+    flags: u32,
+    // A bit per input index whose values are decoded from sRGB in the shaders.
+    srgb_inputs: u32,
+    sequences: Option<Sequences>,
+    stats: Option<StatsResources>,
+    // The timestamp queries, with a timestamp period.
+    timer: Option<GpuTimer>,
+    color_transforms: Option<ColorTransformResources>,
+    lut: Option<LutResources>,
+    labels: Option<LabelResources>,
+    quality: QualityPipelines,
+    // Shared with the queries, which may outlive the comparator.
+    metrics_timeline: Option<Arc<MetricsTimeline>>,
+    // Created on the first read of pixel values.
+    inspector: Option<InspectorResources>,
+    // The queue for internal one-shot work, and the pool owned for it.
+    queue: Option<(vk::Queue, vk::CommandPool)>,
+    debug_utils: bool,
+    // The output image of an offscreen comparator.
+    owned_output: Option<OwnedOutput>,
 }
 
 impl Drop for RenderTargetComparator {
     fn drop(&mut self) {
         unsafe {
             self.device.destroy_sampler(self.sampler, None);
-            self.device.destroy_framebuffer(self.framebuffer, None);
-            self.device.destroy_pipeline(self.pipeline, None);
+            if let Some(descriptor_pool) = self.owned_descriptor_pool {
+                self.device.destroy_descriptor_pool(descriptor_pool, None);
+            }
+            for view in &self.owned_input_views {
+                self.device.destroy_image_view(*view, None);
+            }
+            for framebuffer in self.framebuffers.values() {
+                self.device.destroy_framebuffer(*framebuffer, None);
+            }
+            for pipeline in self.pipelines.values() {
+                self.device.destroy_pipeline(*pipeline, None);
+            }
+            if let Some(pipeline_cache) = self.owned_pipeline_cache {
+                self.device.destroy_pipeline_cache(pipeline_cache, None);
+            }
             self.device
                 .destroy_pipeline_layout(self.pipeline_layout, None);
+            self.device
+                .destroy_pipeline_layout(self.vector_pipeline_layout, None);
+            self.device
+                .destroy_pipeline_layout(self.handle_pipeline_layout, None);
+            self.device
+                .destroy_pipeline_layout(self.grid_pipeline_layout, None);
+            self.device
+                .destroy_pipeline_layout(self.crosshair_pipeline_layout, None);
             self.device
                 .destroy_descriptor_set_layout(self.descriptor_set_layout, None);
-            self.device.destroy_render_pass(self.render_pass, None);
+            if let Some(layout) = self.push_descriptor_set_layout {
+                self.device.destroy_descriptor_set_layout(layout, None);
+            }
+            self.device.destroy_pipeline(self.metrics_pipeline, None);
+            self.device
+                .destroy_pipeline_layout(self.metrics_pipeline_layout, None);
+            self.device
+                .destroy_descriptor_set_layout(self.metrics_descriptor_set_layout, None);
+            if let Some(render_pass) = self.render_pass {
+                self.device.destroy_render_pass(render_pass, None);
+            }
+            if let Some(render_pass) = self.load_render_pass {
+                self.device.destroy_render_pass(render_pass, None);
+            }
+            if let Some((_, command_pool)) = self.queue {
+                self.device.destroy_command_pool(command_pool, None);
+            }
         }
     }
 }
 
+// Sharing a comparator between recording threads relies on this, and moving it to a worker
+// thread along with the resources created through it.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<RenderTargetComparator>();
+    assert_send_sync::<input_image::InputImage>();
+    assert_send_sync::<thumbnails::ThumbnailStrip>();
+    assert_send_sync::<batch::CompareBatch>();
+    assert_send_sync::<quality::SsimQuery>();
+    assert_send_sync::<quality::MetricsQuery>();
+    assert_send_sync::<quality::HistogramQuery>();
+    assert_send_sync::<quality::QueryCompletion>();
+    assert_send_sync::<transfer::StagingBuffer>();
+    assert_send_sync::<transfer::PendingTransfer>();
+    assert_send_sync::<transfer::PendingDownload>();
+};
+
 impl RenderTargetComparator {
-    /// Returns the amount of image samplers that will be allocated by the frame comparator per compare() invocation.
-    /// This needs to be taken into account when creating the descriptor pool.
+    /// Returns the amount of image samplers that will be allocated by the frame comparator per frame in flight.
+    /// This needs to be taken into account when creating the descriptor pool. With a YCbCr
+    /// conversion, each takes the `combinedImageSamplerDescriptorCount` of the input format,
+    /// see [`ycbcr::MAX_YCBCR_DESCRIPTOR_COUNT`].
     pub fn image_sampler_count() -> u32 {
-        2
+        MAX_INPUTS as u32
+    }
+
+    /// Returns a builder of the comparator's configuration, see
+    /// [`RenderTargetComparatorCreateInfoBuilder::create`].
+    pub fn builder() -> RenderTargetComparatorCreateInfoBuilder {
+        RenderTargetComparatorCreateInfo::builder()
     }
 
     /// Creates a new `FrameComparator`. Allocates resources upfront, destroys them when dropped.
     pub fn new(info: &RenderTargetComparatorCreateInfo) -> Result<Self> {
+        check_input_count(info.in_image_views.len())?;
+        if info.frames_in_flight == 0 {
+            return Err(anyhow!("Expected at least one frame in flight."));
+        }
+
+        if info.input_encodings.len() > MAX_INPUTS {
+            return Err(anyhow!(
+                "Expected at most {} input encodings, got {}.",
+                MAX_INPUTS,
+                info.input_encodings.len()
+            ));
+        }
+
+        if let Some(count) = info.bindless_inputs {
+            if !(MAX_INPUTS as u32..=1 << 16).contains(&count) {
+                return Err(anyhow!(
+                    "Expected between {} and {} bindless inputs, got {}.",
+                    MAX_INPUTS,
+                    1 << 16,
+                    count
+                ));
+            }
+            if info.push_descriptors || info.ycbcr_conversion.is_some() {
+                return Err(anyhow!(
+                    "Bindless inputs are not available with push descriptors or a YCbCr \
+                     conversion."
+                ));
+            }
+        }
+
+        if let Some(range) = info.depth_range {
+            if range.near == range.far {
+                return Err(anyhow!("The depth range must not be empty."));
+            }
+            if range.curve == DepthCurve::Logarithmic && (range.near <= 0.0 || range.far <= 0.0) {
+                return Err(anyhow!(
+                    "A logarithmic depth range must be positive, got [{}, {}].",
+                    range.near,
+                    range.far
+                ));
+            }
+        }
+
+        match &info.integer_inputs {
+            Some(_) if info.color_transforms || info.lut.is_some() => {
+                return Err(anyhow!(
+                    "Integer inputs are not available with color transforms or a lookup table."
+                ));
+            }
+            Some(IntegerMapping::Normalize(0)) => {
+                return Err(anyhow!(
+                    "Integer inputs must be normalized by a positive value."
+                ));
+            }
+            Some(IntegerMapping::Palette(colors))
+                if colors.is_empty() || colors.len() > MAX_PALETTE_COLORS =>
+            {
+                return Err(anyhow!(
+                    "Expected between 1 and {} palette colors, got {}.",
+                    MAX_PALETTE_COLORS,
+                    colors.len()
+                ));
+            }
+            _ => {}
+        }
+
+        let format = match info.difference_output {
+            Some(output) => output.format(),
+            None if info.format == vk::Format::UNDEFINED => {
+                return Err(anyhow!("The output format must be set."));
+            }
+            None => info.format,
+        };
+        if let Some(properties) = info.format_properties {
+            let features = properties.optimal_tiling_features;
+            if !features.contains(vk::FormatFeatureFlags::COLOR_ATTACHMENT) {
+                return Err(anyhow!(
+                    "The output format {:?} doesn't support color attachments.",
+                    format
+                ));
+            }
+        }
+        if info.extent.width == 0 || info.extent.height == 0 {
+            return Err(anyhow!(
+                "The output extent must not be empty, got {}x{}.",
+                info.extent.width,
+                info.extent.height
+            ));
+        }
+        if info.out_image_view == vk::ImageView::null() {
+            return Err(anyhow!("The output image view must be set."));
+        }
+
+        let flags = match (info.difference_output, info.output_encoding) {
+            (Some(DifferenceOutput::Signed), _) => FLAG_DIFFERENCE_OUTPUT | FLAG_SIGNED_DIFFERENCE,
+            (Some(_), _) => FLAG_DIFFERENCE_OUTPUT,
+            (None, OutputEncoding::Srgb) => FLAG_ENCODE_SRGB,
+            (None, OutputEncoding::Linear) => 0,
+        };
+
+        if info.queue.is_some() != info.queue_family_index.is_some() {
+            return Err(anyhow!(
+                "The queue and its family index must be set together."
+            ));
+        }
+
+        if info.stats && info.memory_properties.is_none() {
+            return Err(anyhow!(
+                "Statistics require the memory properties to be set."
+            ));
+        }
+        if info.color_transforms && info.memory_properties.is_none() {
+            return Err(anyhow!(
+                "Color transforms require the memory properties to be set."
+            ));
+        }
+        if let Some(period) = info.timestamp_period {
+            if period.is_nan() || period <= 0.0 {
+                return Err(anyhow!(
+                    "The timestamp period must be positive, got {}.",
+                    period
+                ));
+            }
+        }
+
         let device = &info.device;
-        let render_pass = create_render_pass(device, info.format, info.final_layout)?;
-        let descriptor_set_layout = create_descriptor_set_layout(device)?;
+        let render_pass = match info.dynamic_rendering_only {
+            true => None,
+            false => Some(create_render_pass(
+                device,
+                format,
+                info.final_layout,
+                vk::AttachmentLoadOp::CLEAR,
+                view_mask(info.multiview),
+            )?),
+        };
+        let load_render_pass = match info.dynamic_rendering_only {
+            true => None,
+            false => Some(create_render_pass(
+                device,
+                format,
+                info.final_layout,
+                vk::AttachmentLoadOp::LOAD,
+                view_mask(info.multiview),
+            )?),
+        };
+        let immutable_sampler = info.ycbcr_conversion.as_ref().map(|ycbcr| ycbcr.sampler());
+        let descriptor_set_layout =
+            create_descriptor_set_layout(device, immutable_sampler, false, info.bindless_inputs)?;
+        // The compute pipelines keep binding descriptor sets, so both layouts are created.
+        let push_descriptor_set_layout = match info.push_descriptors {
+            true => Some(create_descriptor_set_layout(
+                device,
+                immutable_sampler,
+                true,
+                None,
+            )?),
+            false => None,
+        };
+        let input_set_layout = push_descriptor_set_layout.unwrap_or(descriptor_set_layout);
+
+        let stats = match (info.stats, &info.memory_properties) {
+            (true, Some(memory_properties)) => {
+                Some(StatsResources::new(device, memory_properties)?)
+            }
+            _ => None,
+        };
+
+        let timer = info
+            .timestamp_period
+            .map(|period| GpuTimer::new(device, period, info.frames_in_flight))
+            .transpose()?;
+
+        let color_transforms = match (info.color_transforms, &info.memory_properties) {
+            (true, Some(memory_properties)) => {
+                Some(ColorTransformResources::new(device, memory_properties)?)
+            }
+            _ => None,
+        };
+
+        // The statistics buffer is bound as the second set, followed by the color transforms.
+        let mut descriptor_set_layouts = vec![input_set_layout];
+        descriptor_set_layouts.extend(stats.as_ref().map(|s| s.descriptor_set_layout));
+        descriptor_set_layouts.extend(color_transforms.as_ref().map(|c| c.descriptor_set_layout));
+
+        let lut = info
+            .lut
+            .map(|view| LutResources::new(device, view))
+            .transpose()?;
+        descriptor_set_layouts.extend(lut.as_ref().map(|l| l.descriptor_set_layout));
+
+        let labels = info
+            .glyph_atlas
+            .map(|atlas| LabelResources::new(device, atlas))
+            .transpose()?;
+
+        // The pipelines themselves are created when their mode is prepared.
+        let pipeline_layout = create_pipeline_layout(device, &descriptor_set_layouts)?;
+        let vector_pipeline_layout = create_vector_pipeline_layout(device, input_set_layout)?;
+        let handle_pipeline_layout = create_handle_pipeline_layout(device)?;
+        let grid_pipeline_layout = create_grid_pipeline_layout(device)?;
+        let crosshair_pipeline_layout = create_crosshair_pipeline_layout(device)?;
+
+        let (pipeline_cache, owned_pipeline_cache) = match info.pipeline_cache {
+            Some(pipeline_cache) => (pipeline_cache, None),
+            None => {
+                let pipeline_cache = create_pipeline_cache(device, &info.pipeline_cache_data)?;
+                (pipeline_cache, Some(pipeline_cache))
+            }
+        };
 
-        let (pipeline_layout, pipeline) = create_pipeline(
-            device,
-            &info.extent,
-            &render_pass,
-            &[descriptor_set_layout],
-            info.viewport,
-        )?;
+        let metrics_descriptor_set_layout = create_metrics_descriptor_set_layout(device)?;
+        let (metrics_pipeline_layout, metrics_pipeline) =
+            create_metrics_pipeline(device, pipeline_cache, &metrics_descriptor_set_layout)?;
 
-        let sampler = create_image_sampler(device)?;
+        let quality = QualityPipelines::new(device, pipeline_cache, descriptor_set_layout)?;
+        let metrics_timeline = match info.metrics_timeline {
+            true => Some(Arc::new(MetricsTimeline::new(device)?)),
+            false => None,
+        };
+
+        // Integer formats can't be filtered.
+        let sampler_config = match info.integer_inputs {
+            Some(_) => SamplerConfig {
+                min_filter: vk::Filter::NEAREST,
+                mag_filter: vk::Filter::NEAREST,
+                mipmap_mode: vk::SamplerMipmapMode::NEAREST,
+                max_anisotropy: None,
+                ..info.sampler
+            },
+            None => info.sampler,
+        };
+        let sampler = create_image_sampler(device, &sampler_config)?;
 
         // Create framebuffer
-        let attachments = &[info.out_image_view];
-        let framebuffer_info = vk::FramebufferCreateInfo::builder()
-            .render_pass(render_pass)
-            .attachments(attachments)
-            .width(info.extent.width)
-            .height(info.extent.height)
-            .layers(1);
-
-        // This is inside an unsafe function, and the caller guarantees the
-        // validity of the image view.
-        let framebuffer = unsafe { device.create_framebuffer(&framebuffer_info, None)? };
+        let framebuffers = match render_pass {
+            Some(render_pass) => {
+                let framebuffer =
+                    create_framebuffer(device, render_pass, info.out_image_view, info.extent)?;
+                HashMap::from([(info.out_image_view, framebuffer)])
+            }
+            None => HashMap::new(),
+        };
+
+        // Area of the framebuffer to render to. By default the whole area.
+        let viewport = info.viewport.unwrap_or_else(|| full_viewport(info.extent));
 
         // Handle descriptors
-        let descriptor_set =
-            create_descriptor_set(device, &info.descriptor_pool, &descriptor_set_layout)?;
+        let array_size = info.bindless_inputs.unwrap_or(Self::image_sampler_count());
+        let (descriptor_pool, owned_descriptor_pool) = match info.descriptor_pool {
+            _ if info.push_descriptors => (vk::DescriptorPool::null(), None),
+            Some(descriptor_pool) => (descriptor_pool, None),
+            None => {
+                let set_count = info.frames_in_flight as u32;
+                let descriptor_pool = create_descriptor_pool(
+                    device,
+                    set_count,
+                    set_count
+                        * array_size
+                        * descriptors_per_sampler(info.ycbcr_conversion.as_deref()),
+                    0,
+                    0,
+                )?;
+                (descriptor_pool, Some(descriptor_pool))
+            }
+        };
+
+        let image_views = input_bindings(&info.in_image_views);
+        let frames = (0..info.frames_in_flight)
+            .map(|_| {
+                if info.push_descriptors {
+                    return Ok(FrameInputs {
+                        descriptor_set: vk::DescriptorSet::null(),
+                        image_views,
+                        input_count: info.in_image_views.len(),
+                    });
+                }
 
-        update_descriptor_sets(device, &descriptor_set, &sampler, &info.in_image_views);
+                let descriptor_set = create_input_descriptor_set(
+                    device,
+                    &descriptor_pool,
+                    &descriptor_set_layout,
+                    array_size,
+                )
+                .map_err(|e| match owned_descriptor_pool {
+                    // Pools can't be queried for their capacity, so exhaustion shows here.
+                    None => anyhow!(
+                        "Failed to allocate the input descriptor sets from the given pool, \
+                         which needs {} sets of {} image samplers: {}",
+                        info.frames_in_flight,
+                        array_size,
+                        e
+                    ),
+                    Some(_) => e,
+                })?;
+                update_descriptor_sets(device, &descriptor_set, &sampler, &image_views);
+                Ok(FrameInputs {
+                    descriptor_set,
+                    image_views,
+                    input_count: info.in_image_views.len(),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
 
-        Ok(Self {
+        let queue = match (info.queue, info.queue_family_index) {
+            (Some(queue), Some(queue_family_index)) => {
+                Some((queue, create_command_pool(device, queue_family_index)?))
+            }
+            _ => None,
+        };
+
+        let mut comparator = Self {
             render_pass,
-            device: Rc::clone(device),
+            load_render_pass,
+            subpass: info.subpass,
+            device: Arc::clone(device),
             descriptor_set_layout,
-            descriptor_set,
+            push_descriptor_set_layout,
+            bindless_inputs: info.bindless_inputs,
+            owned_descriptor_pool,
+            frames,
+            owned_input_views: Vec::new(),
+            pipeline_cache,
+            owned_pipeline_cache,
             pipeline_layout,
-            pipeline,
+            vector_pipeline_layout,
+            handle_pipeline_layout,
+            grid_pipeline_layout,
+            crosshair_pipeline_layout,
+            fragment_shader: info.fragment_shader_spirv.clone(),
+            custom_modes: Vec::new(),
+            pipelines: HashMap::new(),
+            format,
+            final_layout: info.final_layout,
+            dynamic_rendering: info.dynamic_rendering || info.dynamic_rendering_only,
+            multiview: info.multiview,
+            ycbcr_conversion: info.ycbcr_conversion.clone(),
+            depth_range: info.depth_range,
+            integer_inputs: info.integer_inputs.clone(),
+            metrics_descriptor_set_layout,
+            metrics_pipeline_layout,
+            metrics_pipeline,
             extent: info.extent,
+            viewport,
+            memory_properties: info.memory_properties,
             sampler,
-            framebuffer,
+            out_image_view: info.out_image_view,
+            framebuffers,
+            flags,
+            srgb_inputs: srgb_input_mask(&info.input_encodings),
+            sequences: None,
+            stats,
+            timer,
+            color_transforms,
+            lut,
+            labels,
+            quality,
+            metrics_timeline,
+            inspector: None,
+            queue,
+            debug_utils: info.debug_utils,
+            owned_output: None,
+        };
+
+        comparator.name_objects();
+        for mode in &info.modes {
+            comparator.prepare_pipelines(PipelineKind::Compare(*mode))?;
+        }
+
+        Ok(comparator)
+    }
+
+    // Names an object created by the comparator, if the instance has debug utils.
+    fn name_object<H: vk::Handle<Repr = u64>>(&self, handle: H, name: &str) {
+        if self.debug_utils && !handle.is_null() {
+            set_object_name(&self.device, handle, &format!("rtcmp::{}", name));
+        }
+    }
+
+    // Names the objects created upfront. Pipelines and framebuffers are named when prepared.
+    fn name_objects(&self) {
+        if let Some(render_pass) = self.render_pass {
+            self.name_object(render_pass, "render_pass");
+        }
+        if let Some(load_render_pass) = self.load_render_pass {
+            self.name_object(load_render_pass, "load_render_pass");
+        }
+        self.name_object(self.descriptor_set_layout, "descriptor_set_layout");
+        if let Some(layout) = self.push_descriptor_set_layout {
+            self.name_object(layout, "push_descriptor_set_layout");
+        }
+        if let Some(descriptor_pool) = self.owned_descriptor_pool {
+            self.name_object(descriptor_pool, "descriptor_pool");
+        }
+        for (index, frame) in self.frames.iter().enumerate() {
+            self.name_object(frame.descriptor_set, &format!("descriptor_set::{}", index));
+        }
+        if let Some(pipeline_cache) = self.owned_pipeline_cache {
+            self.name_object(pipeline_cache, "pipeline_cache");
+        }
+        self.name_object(self.pipeline_layout, "pipeline_layout");
+        self.name_object(self.vector_pipeline_layout, "vector_pipeline_layout");
+        self.name_object(self.handle_pipeline_layout, "handle_pipeline_layout");
+        self.name_object(self.grid_pipeline_layout, "grid_pipeline_layout");
+        self.name_object(self.crosshair_pipeline_layout, "crosshair_pipeline_layout");
+        self.name_object(
+            self.metrics_descriptor_set_layout,
+            "metrics_descriptor_set_layout",
+        );
+        self.name_object(self.metrics_pipeline_layout, "metrics_pipeline_layout");
+        self.name_object(self.metrics_pipeline, "metrics_pipeline");
+        self.name_object(self.sampler, "sampler");
+        for framebuffer in self.framebuffers.values() {
+            self.name_object(*framebuffer, "framebuffer");
+        }
+        if let Some((_, command_pool)) = self.queue {
+            self.name_object(command_pool, "command_pool");
+        }
+    }
+
+    /// Returns the context for uploads and downloads on the comparator's own queue and
+    /// command pool, e.g. for [`InputImage`](input_image::InputImage) or the helpers of
+    /// [`transfer`]. Requires the queue and memory properties to be set at creation.
+    ///
+    /// Transfers using the context must have finished before the comparator is dropped.
+    /// The command pool of the context is used without synchronization, so the context must
+    /// not be used from several threads at once, nor alongside
+    /// [`generate_mipmaps`](Self::generate_mipmaps).
+    pub fn upload_context(&mut self) -> Result<UploadContext> {
+        let (queue, command_pool) = self
+            .queue
+            .ok_or_else(|| anyhow!("The comparator was not created with a queue."))?;
+        let memory_properties = self
+            .memory_properties
+            .ok_or_else(|| anyhow!("The comparator was not created with the memory properties."))?;
+
+        Ok(UploadContext {
+            memory_properties,
+            queue,
+            command_pool,
         })
     }
 
-    /// Records the drawing commands for comparing two images into the provided command buffer.
+    /// Generates the mip chain of an input image on the comparator's own queue, see
+    /// [`mipmaps::generate_mipmaps`].
     ///
     /// # Safety
     ///
-    /// The caller must ensure that the `descriptor_pool` provided during `FrameComparator`
-    /// creation has enough capacity to allocate a new descriptor set for each call to `compare`.
-    /// The allocated descriptor set is valid only for the lifetime of the provided command buffer.
+    /// See [`mipmaps::cmd_generate_mipmaps`].
+    pub unsafe fn generate_mipmaps(
+        &mut self,
+        image: vk::Image,
+        extent: vk::Extent2D,
+        mip_levels: u32,
+    ) -> Result<()> {
+        let (queue, command_pool) = self
+            .queue
+            .ok_or_else(|| anyhow!("The comparator was not created with a queue."))?;
+        unsafe {
+            mipmaps::generate_mipmaps(&self.device, queue, command_pool, image, extent, mip_levels)
+        }
+    }
+
+    /// Reads back the output image on the comparator's own queue, e.g. to snapshot the
+    /// comparison in headless tests, and waits for it. Returns the tightly packed texels of
+    /// the output format, row by row from the top. Requires the queue and memory properties
+    /// to be set at creation.
+    ///
+    /// The image is expected in the layout the comparator leaves it in, `final_layout`, or
+    /// `COLOR_ATTACHMENT_OPTIMAL` with `dynamic_rendering_only`, and is returned to it.
+    ///
+    /// # Safety
+    ///
+    /// The image must be the one of the output view, created with `TRANSFER_SRC` usage and a
+    /// single mip level. The comparison must have finished rendering into it.
+    pub unsafe fn read_output(&mut self, image: vk::Image) -> Result<Vec<u8>> {
+        let texel_size = texel_size(self.format)?;
+        let context = self.upload_context()?;
+        let layout = match self.render_pass {
+            Some(_) => self.final_layout,
+            None => vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+        };
+        unsafe {
+            transfer::download_from_image(
+                &self.device,
+                &context,
+                image,
+                self.extent,
+                texel_size,
+                layout,
+            )
+        }
+    }
+
+    /// Replaces the input image views of all frames in flight, e.g. after the images were
+    /// recreated on a resize. The amount of views may differ from the one at creation,
+    /// within the same limits.
+    ///
+    /// # Safety
+    ///
+    /// No command buffer recorded with [`compare`](Self::compare) or the other recording
+    /// functions may be pending execution, as their descriptor sets are rewritten. Wait for
+    /// their fences, or for the queue to be idle, before calling this. With push
+    /// descriptors, the recorded command buffers keep the views they were recorded with, so
+    /// they may be pending.
+    pub unsafe fn set_inputs(&mut self, in_image_views: &[vk::ImageView]) -> Result<()> {
+        for frame_index in 0..self.frames.len() {
+            unsafe { self.set_frame_inputs(frame_index, in_image_views) }?;
+        }
+        Ok(())
+    }
+
+    /// Replaces the input image views of a single frame in flight, like
+    /// [`set_inputs`](Self::set_inputs).
+    ///
+    /// # Safety
+    ///
+    /// No command buffer recorded for this frame index may be pending execution, unless the
+    /// comparator uses push descriptors. Those of other frames may.
+    pub unsafe fn set_frame_inputs(
+        &mut self,
+        frame_index: usize,
+        in_image_views: &[vk::ImageView],
+    ) -> Result<()> {
+        check_input_count(in_image_views.len())?;
+
+        let frame_count = self.frames.len();
+        let frame = &mut self.frames[frame_index % frame_count];
+        frame.image_views = input_bindings(in_image_views);
+        if self.push_descriptor_set_layout.is_none() {
+            update_descriptor_sets(
+                &self.device,
+                &frame.descriptor_set,
+                &self.sampler,
+                &frame.image_views,
+            );
+        }
+        frame.input_count = in_image_views.len();
+
+        Ok(())
+    }
+
+    /// Writes input views into the bindless input array of all frames in flight, from the
+    /// element `first` on, see [`RenderTargetComparatorCreateInfo::bindless_inputs`]. The
+    /// first eight elements are those replaced by [`set_inputs`](Self::set_inputs).
+    ///
+    /// # Safety
+    ///
+    /// No command buffer recorded with [`compare`](Self::compare) or the other recording
+    /// functions may be pending execution, as their descriptor sets are rewritten. The views
+    /// must be valid for as long as comparisons read them.
+    pub unsafe fn write_input_array(
+        &mut self,
+        first: u32,
+        in_image_views: &[vk::ImageView],
+    ) -> Result<()> {
+        let size = self
+            .bindless_inputs
+            .ok_or_else(|| anyhow!("The comparator was not created with bindless inputs."))?;
+        if first as u64 + in_image_views.len() as u64 > size as u64 {
+            return Err(anyhow!(
+                "Writing {} views from element {} on exceeds the {} bindless inputs.",
+                in_image_views.len(),
+                first,
+                size
+            ));
+        }
+
+        for frame in &self.frames {
+            update_input_array(
+                &self.device,
+                &frame.descriptor_set,
+                &self.sampler,
+                first,
+                in_image_views,
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Renders into a new output image view of the given extent from now on, e.g. after a
+    /// window resize. The viewport is reset to the whole output. Pipelines set their viewport
+    /// when recording, so they are kept.
+    ///
+    /// # Safety
+    ///
+    /// No command buffer recorded with [`compare`](Self::compare) may be pending execution,
+    /// as the framebuffers of all output views are destroyed. Other output views have to be
+    /// prepared again. The view must be of the comparator's output format. Fails for
+    /// comparators created with [`offscreen`](Self::offscreen), whose output image keeps its
+    /// extent.
+    pub unsafe fn resize(
+        &mut self,
+        extent: vk::Extent2D,
+        out_image_view: vk::ImageView,
+    ) -> Result<()> {
+        if self.owned_output.is_some() {
+            return Err(anyhow!(
+                "Offscreen comparators can't be resized, create a new one instead."
+            ));
+        }
+        if extent.width == 0 || extent.height == 0 {
+            return Err(anyhow!(
+                "The output extent must not be empty, got {}x{}.",
+                extent.width,
+                extent.height
+            ));
+        }
+
+        let framebuffer = self
+            .render_pass
+            .map(|render_pass| {
+                create_framebuffer(&self.device, render_pass, out_image_view, extent)
+            })
+            .transpose()?;
+        for (_, old) in self.framebuffers.drain() {
+            unsafe { self.device.destroy_framebuffer(old, None) };
+        }
+
+        self.out_image_view = out_image_view;
+        self.framebuffers
+            .extend(framebuffer.map(|f| (out_image_view, f)));
+        self.extent = extent;
+        self.viewport = full_viewport(extent);
+
+        Ok(())
+    }
+
+    /// Writes the contents of the pipeline cache to a file, to be passed as
+    /// [`RenderTargetComparatorCreateInfo::pipeline_cache_data`] or to
+    /// [`load_pipeline_cache`](Self::load_pipeline_cache) in later runs, so that pipelines
+    /// prepared once don't stutter on their first use again.
+    pub fn save_pipeline_cache(&self, path: impl AsRef<Path>) -> Result<()> {
+        let data = unsafe { self.device.get_pipeline_cache_data(self.pipeline_cache) }?;
+        std::fs::write(path, data)?;
+        Ok(())
+    }
+
+    /// Merges the contents of a file written by
+    /// [`save_pipeline_cache`](Self::save_pipeline_cache) into the pipeline cache, speeding
+    /// up the pipelines prepared afterwards. Those of the modes in
+    /// [`RenderTargetComparatorCreateInfo::modes`] are created before, so prefer
+    /// [`RenderTargetComparatorCreateInfo::pipeline_cache_data`] where possible. Data of
+    /// another driver or device is ignored.
+    pub fn load_pipeline_cache(&mut self, path: impl AsRef<Path>) -> Result<()> {
+        let data = std::fs::read(path)?;
+        let loaded = create_pipeline_cache(&self.device, &data)?;
+        let merged = unsafe {
+            self.device
+                .merge_pipeline_caches(self.pipeline_cache, &[loaded])
+        };
+        unsafe { self.device.destroy_pipeline_cache(loaded, None) };
+        merged?;
+        Ok(())
+    }
+
+    /// Creates the pipelines a comparison needs, unless they exist already: those of its mode
+    /// and, if it has them, of its overlays: the vector arrows, divider handle, pixel grid,
+    /// crosshair and input labels. Likewise creates the framebuffer of its output view
+    /// override. The modes in [`RenderTargetComparatorCreateInfo::modes`] are prepared at
+    /// creation.
+    ///
+    /// Recording a comparison that hasn't been prepared fails, so that recording never
+    /// changes the comparator.
+    pub fn prepare(&mut self, info: &CompareInfo) -> Result<()> {
+        let mut kinds = vec![self.comparison_kind(info)];
+        kinds.extend(info.vector_overlay.map(|_| PipelineKind::Vectors));
+        kinds.extend(self.divider_handle(info).map(|_| PipelineKind::Handle));
+        kinds.extend(self.pixel_grid(info).map(|_| PipelineKind::PixelGrid));
+        kinds.extend(self.crosshair(info).map(|_| PipelineKind::Crosshair));
+        if self.labels_shown(info) {
+            kinds.push(PipelineKind::Labels);
+        }
+
+        for kind in kinds {
+            self.prepare_pipelines(kind)?;
+        }
+
+        // Dynamic rendering needs no framebuffers.
+        let unprepared = info
+            .out_image_view
+            .filter(|out_image_view| !self.framebuffers.contains_key(out_image_view));
+        if let (Some(out_image_view), Some(render_pass)) = (unprepared, self.render_pass) {
+            let framebuffer =
+                create_framebuffer(&self.device, render_pass, out_image_view, self.extent)?;
+            self.name_object(framebuffer, "framebuffer");
+            self.framebuffers.insert(out_image_view, framebuffer);
+        }
+
+        Ok(())
+    }
+
+    /// Destroys the framebuffer of a prepared output view, before the view itself is
+    /// destroyed, e.g. when the swapchain is recreated. The comparator's own output view is
+    /// kept, replace it with [`resize`](Self::resize).
+    ///
+    /// # Safety
+    ///
+    /// No command buffer rendering into the view may be pending execution.
+    pub unsafe fn release_output(&mut self, out_image_view: vk::ImageView) {
+        if out_image_view == self.out_image_view {
+            return;
+        }
+
+        if let Some(framebuffer) = self.framebuffers.remove(&out_image_view) {
+            unsafe { self.device.destroy_framebuffer(framebuffer, None) };
+        }
+    }
+
+    /// Records the drawing commands for comparing two images into the provided command buffer.
+    ///
+    /// # Safety
+    ///
+    /// The input images must be in `SHADER_READ_ONLY_OPTIMAL` layout when the command buffer
+    /// executes. A descriptor pool provided at creation must outlive the comparator, as the
+    /// input descriptor sets are allocated from it, unless it uses push descriptors.
     pub unsafe fn compare(&self, info: &CompareInfo) -> Result<()> {
-        let render_area = vk::Rect2D::builder()
-            .offset(vk::Offset2D::default())
-            .extent(self.extent)
+        let frame = self.frame(info.frame_index);
+        unsafe { self.cmd_compare(info, self.input_binding(frame), frame.input_count) }
+    }
+
+    /// Records the comparison into a render pass the caller has already begun on the command
+    /// buffer, without beginning or ending one. Only binds, pushes and draws, into the
+    /// comparator's viewport.
+    ///
+    /// Statistics are accumulated but neither reset nor made available to the host, as that
+    /// can't be recorded within a render pass.
+    ///
+    /// # Safety
+    ///
+    /// The current subpass must be the one in [`RenderTargetComparatorCreateInfo::subpass`].
+    /// Without one, it must be compatible with the comparator's render pass, i.e. have a
+    /// single color attachment of the output format without multisampling, see
+    /// [`is_render_pass_compatible`](Self::is_render_pass_compatible). The same requirements
+    /// as for [`compare`](Self::compare) apply.
+    pub unsafe fn compare_in_current_pass(&self, info: &CompareInfo) -> Result<()> {
+        let recording = match self.subpass {
+            Some(_) => Recording::Subpass,
+            None => {
+                self.render_pass()?;
+                Recording::RenderPass
+            }
+        };
+
+        unsafe { self.cmd_compare_in_pass(info, recording) }
+    }
+
+    /// Records the comparison within dynamic rendering the caller has already begun on the
+    /// command buffer, like [`compare_in_current_pass`](Self::compare_in_current_pass).
+    /// Requires the comparator to be created with `dynamic_rendering`.
+    ///
+    /// # Safety
+    ///
+    /// The rendering must have a single color attachment of the output format, without
+    /// multisampling. The same requirements as for [`compare`](Self::compare) apply.
+    pub unsafe fn compare_in_current_rendering(&self, info: &CompareInfo) -> Result<()> {
+        if !self.dynamic_rendering {
+            return Err(anyhow!(
+                "The comparator was not created with dynamic rendering enabled."
+            ));
+        }
+
+        unsafe { self.cmd_compare_in_pass(info, Recording::DynamicRendering) }
+    }
+
+    /// Begins recording a secondary command buffer that continues the comparator's rendering
+    /// into the output view, `None` for the comparator's own. Comparisons can then be recorded
+    /// into it on other threads with [`compare_in_current_pass`](Self::compare_in_current_pass),
+    /// or [`compare_in_current_rendering`](Self::compare_in_current_rendering) with dynamic
+    /// rendering only, before ending it and executing it with
+    /// [`execute_comparisons`](Self::execute_comparisons).
+    ///
+    /// With a subpass of the caller given at creation, it continues that subpass instead, and
+    /// is executed within the caller's render pass.
+    ///
+    /// # Safety
+    ///
+    /// The command buffer must be a secondary command buffer in the initial state.
+    pub unsafe fn begin_secondary(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        out_image_view: Option<vk::ImageView>,
+    ) -> Result<()> {
+        let color_attachment_formats = [self.format];
+        let mut rendering_info = vk::CommandBufferInheritanceRenderingInfo::builder()
+            .color_attachment_formats(&color_attachment_formats)
+            .rasterization_samples(vk::SampleCountFlags::_1)
+            .view_mask(view_mask(self.multiview))
             .build();
 
-        let color_clear_value = vk::ClearValue {
-            color: vk::ClearColorValue {
-                float32: [0.0, 0.0, 0.0, 1.0],
+        let mut inheritance_info = vk::CommandBufferInheritanceInfo::builder();
+        inheritance_info = match self.subpass {
+            Some(subpass) => inheritance_info
+                .render_pass(subpass.render_pass)
+                .subpass(subpass.index),
+            None => match self.output(out_image_view)? {
+                // The framebuffer is optional, but may let the implementation optimize.
+                Output::Framebuffer(render_pass, framebuffer) => inheritance_info
+                    .render_pass(render_pass)
+                    .subpass(0)
+                    .framebuffer(framebuffer),
+                Output::View(_) => inheritance_info.push_next(&mut rendering_info),
             },
         };
+        let inheritance_info = inheritance_info.build();
 
-        let clear_values = &[color_clear_value];
-        let begin_info = vk::RenderPassBeginInfo::builder()
-            .render_pass(self.render_pass)
-            .framebuffer(self.framebuffer)
-            .render_area(render_area)
-            .clear_values(clear_values)
+        let begin_info = vk::CommandBufferBeginInfo::builder()
+            .flags(vk::CommandBufferUsageFlags::RENDER_PASS_CONTINUE)
+            .inheritance_info(&inheritance_info)
             .build();
 
+        unsafe {
+            self.device
+                .begin_command_buffer(command_buffer, &begin_info)
+        }?;
+        Ok(())
+    }
+
+    /// Records the execution of secondary command buffers begun with
+    /// [`begin_secondary`](Self::begin_secondary) into a primary command buffer, within a
+    /// render pass or rendering into the output view that clears it first. Statistics are
+    /// reset and made available like in [`compare`](Self::compare).
+    ///
+    /// # Safety
+    ///
+    /// The secondary command buffers must have been begun for the same output view and
+    /// ended. The same requirements as for [`compare`](Self::compare) apply to all
+    /// comparisons recorded into them.
+    pub unsafe fn execute_comparisons(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        out_image_view: Option<vk::ImageView>,
+        secondaries: &[vk::CommandBuffer],
+    ) -> Result<()> {
+        if self.subpass.is_some() {
+            return Err(anyhow!(
+                "The comparator records into the caller's subpass, so its secondary command \
+                 buffers are executed within the caller's render pass."
+            ));
+        }
+
+        let output = self.output(out_image_view)?;
+
+        unsafe {
+            if let Some(stats) = &self.stats {
+                stats.cmd_reset(command_buffer);
+            }
+
+            self.cmd_begin_output(
+                command_buffer,
+                output,
+                vk::SubpassContents::SECONDARY_COMMAND_BUFFERS,
+                full_rect(self.extent),
+                Some(Color(0.0, 0.0, 0.0, 1.0)),
+            );
+            self.device
+                .cmd_execute_commands(command_buffer, secondaries);
+            self.cmd_end_output(command_buffer, output);
+
+            if let Some(stats) = &self.stats {
+                stats.cmd_finish(command_buffer);
+            }
+        }
+
+        Ok(())
+    }
+
+    unsafe fn cmd_compare_in_pass(&self, info: &CompareInfo, recording: Recording) -> Result<()> {
+        let frame = self.frame(info.frame_index);
+        self.check_input_base(info, frame.input_count)?;
+        let input_count = self.eye_input_count(frame.input_count)?;
+        check_split_positions(info, input_count)?;
+        check_mask_input(info, input_count)?;
+        self.check_input_reads(info)?;
+
+        let pipeline = self.pipeline(self.comparison_kind(info), recording)?;
+        let vector_pipeline = info
+            .vector_overlay
+            .map(|_| self.pipeline(PipelineKind::Vectors, recording))
+            .transpose()?;
+        let handle_pipeline = self
+            .divider_handle(info)
+            .map(|_| self.pipeline(PipelineKind::Handle, recording))
+            .transpose()?;
+        let grid_pipeline = self
+            .pixel_grid(info)
+            .map(|_| self.pipeline(PipelineKind::PixelGrid, recording))
+            .transpose()?;
+        let crosshair_pipeline = self
+            .crosshair(info)
+            .map(|_| self.pipeline(PipelineKind::Crosshair, recording))
+            .transpose()?;
+        let label_pipeline = match self.labels_shown(info) {
+            true => Some(self.pipeline(PipelineKind::Labels, recording)?),
+            false => None,
+        };
+
+        // The caller's attachment may be larger, so only the comparator's viewport is touched.
+        let viewport = self.comparison_viewport(info);
+        let scissor = info.render_area.unwrap_or_else(|| viewport_rect(viewport));
+
+        unsafe {
+            let labelled = self.cmd_begin_label(info)?;
+
+            self.device.cmd_bind_pipeline(
+                info.command_buffer,
+                vk::PipelineBindPoint::GRAPHICS,
+                pipeline,
+            );
+
+            let inputs = self.input_binding(frame);
+            self.cmd_draw_comparison(
+                info.command_buffer,
+                inputs,
+                input_count,
+                viewport,
+                scissor,
+                info,
+            );
+
+            if let (Some(pipeline), Some(overlay)) = (vector_pipeline, &info.vector_overlay) {
+                self.cmd_draw_vectors(
+                    info.command_buffer,
+                    pipeline,
+                    inputs,
+                    viewport,
+                    overlay,
+                    info,
+                );
+            }
+
+            if let (Some(pipeline), Some(grid)) = (grid_pipeline, self.pixel_grid(info)) {
+                self.cmd_draw_pixel_grid(info.command_buffer, pipeline, viewport, &grid);
+            }
+
+            if let (Some(pipeline), Some(crosshair)) = (crosshair_pipeline, self.crosshair(info)) {
+                self.cmd_draw_crosshair(
+                    info.command_buffer,
+                    pipeline,
+                    viewport,
+                    input_count,
+                    &crosshair,
+                    info,
+                );
+            }
+
+            if let (Some(pipeline), Some(handle)) = (handle_pipeline, self.divider_handle(info)) {
+                self.cmd_draw_handle(info.command_buffer, pipeline, viewport, &handle, info);
+            }
+
+            if let Some(pipeline) = label_pipeline {
+                self.cmd_draw_labels(info.command_buffer, pipeline, viewport, input_count, info)?;
+            }
+
+            if labelled {
+                self.cmd_end_label(info.command_buffer);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Records a whole comparison render pass, sampling the `input_count` inputs bound by
+    /// `inputs`.
+    unsafe fn cmd_compare(
+        &self,
+        info: &CompareInfo,
+        inputs: InputBinding,
+        input_count: usize,
+    ) -> Result<()> {
+        self.check_input_base(info, input_count)?;
+        let input_count = self.eye_input_count(input_count)?;
+        check_split_positions(info, input_count)?;
+        check_mask_input(info, input_count)?;
+        self.check_input_reads(info)?;
+        let viewport = self.comparison_viewport(info);
+        let output = self.output(info.out_image_view)?;
+        let render_area = match info.render_area {
+            Some(area) => {
+                check_render_area(area, self.extent)?;
+                area
+            }
+            None => full_rect(self.extent),
+        };
+
         let command_buffer = info.command_buffer;
+        let recording = output.recording();
+        let pipeline = self.pipeline(self.comparison_kind(info), recording)?;
+        let vector_pipeline = info
+            .vector_overlay
+            .map(|_| self.pipeline(PipelineKind::Vectors, recording))
+            .transpose()?;
+        let handle_pipeline = self
+            .divider_handle(info)
+            .map(|_| self.pipeline(PipelineKind::Handle, recording))
+            .transpose()?;
+        let grid_pipeline = self
+            .pixel_grid(info)
+            .map(|_| self.pipeline(PipelineKind::PixelGrid, recording))
+            .transpose()?;
+        let crosshair_pipeline = self
+            .crosshair(info)
+            .map(|_| self.pipeline(PipelineKind::Crosshair, recording))
+            .transpose()?;
+        let label_pipeline = match self.labels_shown(info) {
+            true => Some(self.pipeline(PipelineKind::Labels, recording)?),
+            false => None,
+        };
 
         unsafe {
-            self.device.cmd_begin_render_pass(
+            let labelled = self.cmd_begin_label(info)?;
+
+            if let Some(stats) = &self.stats {
+                stats.cmd_reset(command_buffer);
+            }
+            self.cmd_begin_timing(command_buffer, TimedPass::Comparison, info.frame_index);
+
+            self.cmd_begin_output(
                 command_buffer,
-                &begin_info,
+                output,
                 vk::SubpassContents::INLINE,
+                render_area,
+                info.clear_color,
             );
 
             self.device.cmd_bind_pipeline(
                 command_buffer,
                 vk::PipelineBindPoint::GRAPHICS,
-                self.pipeline,
+                pipeline,
             );
 
-            self.device.cmd_bind_descriptor_sets(
+            self.cmd_draw_comparison(
                 command_buffer,
-                vk::PipelineBindPoint::GRAPHICS,
-                self.pipeline_layout,
-                0,
-                &[self.descriptor_set],
-                &[] as &[u32],
+                inputs,
+                input_count,
+                viewport,
+                render_area,
+                info,
             );
 
+            if let (Some(pipeline), Some(overlay)) = (vector_pipeline, &info.vector_overlay) {
+                self.cmd_draw_vectors(command_buffer, pipeline, inputs, viewport, overlay, info);
+            }
+
+            if let (Some(pipeline), Some(grid)) = (grid_pipeline, self.pixel_grid(info)) {
+                self.cmd_draw_pixel_grid(command_buffer, pipeline, viewport, &grid);
+            }
+
+            if let (Some(pipeline), Some(crosshair)) = (crosshair_pipeline, self.crosshair(info)) {
+                self.cmd_draw_crosshair(
+                    command_buffer,
+                    pipeline,
+                    viewport,
+                    input_count,
+                    &crosshair,
+                    info,
+                );
+            }
+
+            if let (Some(pipeline), Some(handle)) = (handle_pipeline, self.divider_handle(info)) {
+                self.cmd_draw_handle(command_buffer, pipeline, viewport, &handle, info);
+            }
+
+            if let Some(pipeline) = label_pipeline {
+                self.cmd_draw_labels(command_buffer, pipeline, viewport, input_count, info)?;
+            }
+
+            self.cmd_end_output(command_buffer, output);
+            self.cmd_end_timing(command_buffer, TimedPass::Comparison, info.frame_index);
+
+            if let Some(stats) = &self.stats {
+                stats.cmd_finish(command_buffer);
+            }
+
+            if labelled {
+                self.cmd_end_label(command_buffer);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns what a comparison renders into: the framebuffer of the output view with a
+    /// render pass, or the view itself with dynamic rendering only.
+    fn output(&self, out_image_view: Option<vk::ImageView>) -> Result<Output> {
+        let out_image_view = out_image_view.unwrap_or(self.out_image_view);
+        match self.render_pass {
+            Some(render_pass) => Ok(Output::Framebuffer(
+                render_pass,
+                self.framebuffer(out_image_view)?,
+            )),
+            None => Ok(Output::View(out_image_view)),
+        }
+    }
+
+    /// Begins the render pass or rendering into the render area of the output, clearing it
+    /// to `clear_color`, or keeping its content without one.
+    unsafe fn cmd_begin_output(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        output: Output,
+        contents: vk::SubpassContents,
+        render_area: vk::Rect2D,
+        clear_color: Option<Color>,
+    ) {
+        let color = clear_color.unwrap_or_default();
+        let color_clear_value = vk::ClearValue {
+            color: vk::ClearColorValue {
+                float32: [color.0, color.1, color.2, color.3],
+            },
+        };
+        let load_op = match clear_color {
+            Some(_) => vk::AttachmentLoadOp::CLEAR,
+            None => vk::AttachmentLoadOp::LOAD,
+        };
+
+        match output {
+            Output::Framebuffer(render_pass, framebuffer) => {
+                // The clearing pass discards the output outside of the render area, so partial
+                // areas are loaded and cleared by hand. The passes differ only in their load
+                // operation, so they share framebuffers and pipelines. Both are created
+                // together.
+                let partial = render_area != full_rect(self.extent);
+                let render_pass = match (clear_color, partial) {
+                    (Some(_), false) => render_pass,
+                    _ => self.load_render_pass.unwrap_or(render_pass),
+                };
+                let clear_values = &[color_clear_value];
+                let begin_info = vk::RenderPassBeginInfo::builder()
+                    .render_pass(render_pass)
+                    .framebuffer(framebuffer)
+                    .render_area(render_area)
+                    .clear_values(clear_values)
+                    .build();
+
+                unsafe {
+                    self.device
+                        .cmd_begin_render_pass(command_buffer, &begin_info, contents)
+                };
+
+                if clear_color.is_some() && partial {
+                    let attachment = vk::ClearAttachment::builder()
+                        .aspect_mask(vk::ImageAspectFlags::COLOR)
+                        .color_attachment(0)
+                        .clear_value(color_clear_value)
+                        .build();
+                    // With multiview, the single layer stands for all views.
+                    let rect = vk::ClearRect::builder()
+                        .rect(render_area)
+                        .base_array_layer(0)
+                        .layer_count(1)
+                        .build();
+                    unsafe {
+                        self.device
+                            .cmd_clear_attachments(command_buffer, &[attachment], &[rect])
+                    };
+                }
+            }
+            Output::View(out_image_view) => {
+                // Without a render pass, the output is rendered into directly. Its layout is
+                // left to the caller.
+                let color_attachment = vk::RenderingAttachmentInfo::builder()
+                    .image_view(out_image_view)
+                    .image_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+                    .load_op(load_op)
+                    .store_op(vk::AttachmentStoreOp::STORE)
+                    .clear_value(color_clear_value)
+                    .build();
+
+                let flags = match contents {
+                    vk::SubpassContents::SECONDARY_COMMAND_BUFFERS => {
+                        vk::RenderingFlags::CONTENTS_SECONDARY_COMMAND_BUFFERS
+                    }
+                    _ => vk::RenderingFlags::empty(),
+                };
+                let rendering_info = vk::RenderingInfo::builder()
+                    .flags(flags)
+                    .render_area(render_area)
+                    .layer_count(1)
+                    .view_mask(view_mask(self.multiview))
+                    .color_attachments(std::slice::from_ref(&color_attachment))
+                    .build();
+
+                unsafe {
+                    self.device
+                        .cmd_begin_rendering(command_buffer, &rendering_info)
+                };
+            }
+        }
+    }
+
+    /// Ends the render pass or rendering begun by [`cmd_begin_output`](Self::cmd_begin_output).
+    unsafe fn cmd_end_output(&self, command_buffer: vk::CommandBuffer, output: Output) {
+        match output {
+            Output::Framebuffer(..) => unsafe { self.device.cmd_end_render_pass(command_buffer) },
+            Output::View(_) => unsafe { self.device.cmd_end_rendering(command_buffer) },
+        }
+    }
+
+    /// Records the state and the draw(s) of a single comparison. The caller must have begun the
+    /// render pass and bound the graphics pipeline.
+    unsafe fn cmd_draw_comparison(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        inputs: InputBinding,
+        input_count: usize,
+        viewport: vk::Viewport,
+        scissor: vk::Rect2D,
+        info: &CompareInfo,
+    ) {
+        unsafe {
+            self.device.cmd_set_scissor(command_buffer, 0, &[scissor]);
+            if let Some(opacity) = self.opacity(info) {
+                self.device.cmd_set_blend_constants(
+                    command_buffer,
+                    [0.0, 0.0, 0.0, opacity.clamp(0.0, 1.0)],
+                );
+            }
+
+            self.cmd_bind_inputs(command_buffer, self.pipeline_layout, inputs);
+
+            // The optional sets follow each other without gaps.
+            let optional_sets = [
+                self.stats.as_ref().map(|s| s.descriptor_set),
+                self.color_transforms.as_ref().map(|c| c.descriptor_set),
+                self.lut.as_ref().map(|l| l.descriptor_set),
+            ]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
+            if !optional_sets.is_empty() {
+                self.device.cmd_bind_descriptor_sets(
+                    command_buffer,
+                    vk::PipelineBindPoint::GRAPHICS,
+                    self.pipeline_layout,
+                    1,
+                    &optional_sets,
+                    &[] as &[u32],
+                );
+            }
+
+            let mut flags = self.flags;
+            if info.nearest {
+                flags |= FLAG_NEAREST;
+            }
+            if info.highlight_non_finite {
+                flags |= FLAG_HIGHLIGHT_NON_FINITE;
+            }
+            if self.lut.is_some() {
+                let inputs = info
+                    .lut_inputs
+                    .iter()
+                    .filter(|&&i| i < MAX_INPUTS)
+                    .fold(0, |mask, i| mask | (1 << i));
+                flags |= inputs << LUT_INPUTS_SHIFT;
+            }
+            flags |= match info.tone_mapping.map(|t| t.operator) {
+                Some(ToneMapOperator::Reinhard) => FLAG_TONE_MAP_REINHARD,
+                Some(ToneMapOperator::Aces) => FLAG_TONE_MAP_ACES,
+                Some(ToneMapOperator::ExposureOnly) | None => 0,
+            };
+            flags |= match info.fit {
+                FitMode::Stretch => 0,
+                FitMode::Contain => FLAG_FIT_CONTAIN,
+                FitMode::Cover => FLAG_FIT_COVER,
+            };
+            flags |= match info.extent_policy {
+                ExtentPolicy::ScaleToOutput => 0,
+                ExtentPolicy::ScaleToFirst => FLAG_EXTENT_SCALE_TO_FIRST,
+                ExtentPolicy::Crop => FLAG_EXTENT_CROP,
+            };
+
+            let (background, background_color) = info.background.shader_params();
+            let alpha_checkerboard = info.alpha_checkerboard.unwrap_or_default();
+            if info.alpha_checkerboard.is_some() {
+                flags |= FLAG_ALPHA_CHECKERBOARD;
+            }
+            flags |= (self.srgb_inputs << SRGB_INPUTS_SHIFT) | (background << BACKGROUND_SHIFT);
+
+            // With multiview, the right eye's inputs follow the `input_count` of the left.
+            let eye_inputs = match self.multiview {
+                true => input_count as u32,
+                false => 0,
+            };
+
+            let lod = match info.lod {
+                InputLod::Auto => 0.0,
+                InputLod::Bias(bias) => bias,
+                InputLod::Level(level) => {
+                    flags |= FLAG_FIXED_LOD;
+                    level
+                }
+            };
+
+            let mut input_offsets = [0; MAX_INPUTS];
+            for (packed, offset) in input_offsets.iter_mut().zip(info.input_offsets) {
+                *packed = pack_half2(*offset);
+            }
+
+            // In framebuffer pixels, as the loupe is circular regardless of the aspect ratio.
+            let magnifier = info
+                .magnifier
+                .filter(|_| !matches!(info.mode, CompareMode::Grid | CompareMode::Split))
+                .unwrap_or(Magnifier {
+                    radius: 0.0,
+                    zoom: 1.0,
+                    ..Default::default()
+                });
+            let magnifier_center = [
+                viewport.x + magnifier.center[0] * viewport.width,
+                viewport.y + magnifier.center[1] * viewport.height,
+            ];
+
             let push_buffer = PushConstantBuffer {
+                color: info.divider_color.pack_unorm(),
+                highlight_color: info.highlight_color.pack_unorm(),
+                non_finite_color: info.non_finite_color.pack_unorm(),
+                background_color: background_color.pack_unorm(),
+                alpha_checker_colors: alpha_checkerboard.colors.map(Color::pack_unorm),
+                pan: info.pan,
+                magnifier_center,
+                difference_threshold: [
+                    pack_half2([info.difference_threshold[0], info.difference_threshold[1]]),
+                    pack_half2([info.difference_threshold[2], info.difference_threshold[3]]),
+                ],
                 divider_pos: info.divider_position,
-                divider_width: info.divider_width as f32 / self.extent.width as f32,
-                color: info.divider_color,
+                divider_width: info.divider_width as f32 / viewport.width,
+                divider_angle: info.divider_orientation.angle(),
+                divider_pos_y: info.divider_position_y,
+                zoom: info.zoom,
+                cell: (info.channel_mask.map_or(0, |channel| channel as u32) << CHANNEL_SHIFT)
+                    | (eye_inputs << EYE_INPUTS_SHIFT)
+                    | (CELL_SWAP_INPUTS * info.swap_inputs as u32)
+                    | (info.input_base << INPUT_BASE_SHIFT),
+                exposure: info.tone_mapping.map_or(1.0, |t| t.exposure.exp2()),
+                flags,
+                stats_threshold: info.stats_threshold,
+                mode_parameter: match info.mode {
+                    CompareMode::Onion => info.blend_factor,
+                    CompareMode::Difference => info.difference_gain,
+                    CompareMode::Checkerboard => info.checker_size as f32,
+                    CompareMode::Flicker => info.flicker_second as u32 as f32,
+                    CompareMode::Threshold => info.threshold_brightness,
+                    CompareMode::DeltaE => info.delta_e_range,
+                    _ => 0.0,
+                },
+                magnifier: pack_half2([magnifier.radius, magnifier.zoom.max(f32::EPSILON)]),
+                lod_checker_size: pack_half2([lod, alpha_checkerboard.size as f32]),
+                input_offsets,
             };
 
-            let bytes: &[u8] = bytemuck::bytes_of(&push_buffer);
+            if info.mode == CompareMode::Split && self.flags & FLAG_DIFFERENCE_OUTPUT == 0 {
+                self.device.cmd_set_viewport(command_buffer, 0, &[viewport]);
+
+                // Every strip is a draw of its own, scissored to its part of the viewport,
+                // which keeps the input index uniform within each draw.
+                let count = input_count as u32;
+                let scissor_end = scissor.offset.x + scissor.extent.width as i32;
+                for strip in 0..count {
+                    let start = split_position(info, count, strip);
+                    let end = split_position(info, count, strip + 1);
+                    let left = ((viewport.x + start * viewport.width).round() as i32)
+                        .clamp(scissor.offset.x, scissor_end);
+                    let right = ((viewport.x + end * viewport.width).round() as i32)
+                        .clamp(left, scissor_end);
+
+                    let strip_scissor = vk::Rect2D::builder()
+                        .offset(vk::Offset2D {
+                            x: left,
+                            y: scissor.offset.y,
+                        })
+                        .extent(vk::Extent2D {
+                            width: (right - left) as u32,
+                            height: scissor.extent.height,
+                        })
+                        .build();
+
+                    // The strip bounds are passed in place of the divider positions.
+                    let push_buffer = PushConstantBuffer {
+                        divider_pos: start,
+                        divider_pos_y: end,
+                        cell: push_buffer.cell | strip,
+                        ..push_buffer
+                    };
+
+                    self.device
+                        .cmd_set_scissor(command_buffer, 0, &[strip_scissor]);
+                    self.cmd_push_constants(command_buffer, &push_buffer);
+                    self.device.cmd_draw(command_buffer, 3, 1, 0, 0);
+                }
+                return;
+            }
+
+            // Difference output ignores the mode, so it is always a single draw.
+            if info.mode != CompareMode::Grid || self.flags & FLAG_DIFFERENCE_OUTPUT != 0 {
+                self.device.cmd_set_viewport(command_buffer, 0, &[viewport]);
+                self.cmd_push_constants(command_buffer, &push_buffer);
+                self.device.cmd_draw(command_buffer, 3, 1, 0, 0);
+                return;
+            }
+
+            // Every cell is a draw of its own into a part of the viewport, which keeps the
+            // input index uniform within each draw.
+            for cell in 0..input_count as u32 {
+                let cell_viewport = grid_cell_viewport(info, viewport, input_count, cell);
+
+                // Neighbouring cells each draw half of the divider between them.
+                let push_buffer = PushConstantBuffer {
+                    divider_width: info.divider_width as f32 / 2.0 / cell_viewport.width,
+                    cell: push_buffer.cell | cell,
+                    flags: match info.highlighted_cell == Some(cell) {
+                        true => push_buffer.flags | FLAG_HIGHLIGHTED,
+                        false => push_buffer.flags,
+                    },
+                    ..push_buffer
+                };
+
+                self.device
+                    .cmd_set_viewport(command_buffer, 0, &[cell_viewport]);
+                self.cmd_push_constants(command_buffer, &push_buffer);
+                self.device.cmd_draw(command_buffer, 3, 1, 0, 0);
+            }
+        }
+    }
+
+    /// Records the vector difference arrows over a comparison drawn in the current render
+    /// pass.
+    unsafe fn cmd_draw_vectors(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        pipeline: vk::Pipeline,
+        inputs: InputBinding,
+        viewport: vk::Viewport,
+        overlay: &VectorOverlay,
+        info: &CompareInfo,
+    ) {
+        let spacing = overlay.spacing.max(1) as f32;
+        let columns = ((viewport.width / spacing) as u32).max(1);
+        let rows = ((viewport.height / spacing) as u32).max(1);
+
+        let push_buffer = VectorPushConstantBuffer {
+            color: overlay.color,
+            pan: info.pan,
+            viewport_size: [viewport.width, viewport.height],
+            zoom: info.zoom,
+            scale: match info.swap_inputs {
+                true => -overlay.scale,
+                false => overlay.scale,
+            },
+            columns,
+            rows,
+        };
+
+        unsafe {
+            self.device.cmd_bind_pipeline(
+                command_buffer,
+                vk::PipelineBindPoint::GRAPHICS,
+                pipeline,
+            );
+
+            // The layouts differ in their push constants, so the inputs are bound again.
+            self.cmd_bind_inputs(command_buffer, self.vector_pipeline_layout, inputs);
+
+            self.device.cmd_set_viewport(command_buffer, 0, &[viewport]);
             self.device.cmd_push_constants(
                 command_buffer,
-                self.pipeline_layout,
-                vk::ShaderStageFlags::FRAGMENT,
+                self.vector_pipeline_layout,
+                vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
                 0,
-                bytes,
+                bytemuck::bytes_of(&push_buffer),
             );
 
+            // An instance per arrow, each of three lines.
+            self.device
+                .cmd_draw(command_buffer, 6, columns * rows, 0, 0);
+        }
+    }
+
+    /// Returns the pixel grid of the comparison, unless it renders difference data.
+    fn pixel_grid(&self, info: &CompareInfo) -> Option<PixelGrid> {
+        info.pixel_grid
+            .filter(|_| self.flags & FLAG_DIFFERENCE_OUTPUT == 0)
+    }
+
+    /// Records the pixel grid over a comparison drawn in the current render pass.
+    unsafe fn cmd_draw_pixel_grid(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        pipeline: vk::Pipeline,
+        viewport: vk::Viewport,
+        grid: &PixelGrid,
+    ) {
+        let push_buffer = GridPushConstantBuffer {
+            color: grid.color,
+            viewport_size: [viewport.width, viewport.height],
+            // Lines closer than two pixels would cover everything.
+            spacing: grid.spacing.max(2.0),
+            major_every: grid.major_every,
+            opacity: grid.opacity.clamp(0.0, 1.0),
+            style: grid.style as u32,
+        };
+
+        unsafe {
+            self.device.cmd_bind_pipeline(
+                command_buffer,
+                vk::PipelineBindPoint::GRAPHICS,
+                pipeline,
+            );
+            self.device.cmd_set_viewport(command_buffer, 0, &[viewport]);
+            self.device.cmd_push_constants(
+                command_buffer,
+                self.grid_pipeline_layout,
+                vk::ShaderStageFlags::FRAGMENT,
+                0,
+                bytemuck::bytes_of(&push_buffer),
+            );
             self.device.cmd_draw(command_buffer, 3, 1, 0, 0);
-            self.device.cmd_end_render_pass(command_buffer);
         }
+    }
+
+    /// Returns the viewport of a comparison: the one set, else its render area, else the
+    /// comparator's.
+    fn comparison_viewport(&self, info: &CompareInfo) -> vk::Viewport {
+        match (info.viewport, info.render_area) {
+            (Some(viewport), _) => viewport,
+            (None, Some(area)) => vk::Viewport::builder()
+                .x(area.offset.x as f32)
+                .y(area.offset.y as f32)
+                .width(area.extent.width as f32)
+                .height(area.extent.height as f32)
+                .min_depth(0.0)
+                .max_depth(1.0)
+                .build(),
+            (None, None) => self.viewport,
+        }
+    }
+
+    /// Returns the opacity the comparison is blended with, unless it renders difference data.
+    fn opacity(&self, info: &CompareInfo) -> Option<f32> {
+        info.opacity
+            .filter(|_| self.flags & FLAG_DIFFERENCE_OUTPUT == 0)
+    }
+
+    /// Returns the kind of the pipeline drawing the comparison itself.
+    fn comparison_kind(&self, info: &CompareInfo) -> PipelineKind {
+        let composite = self.opacity(info).is_some();
+        match info.custom_mode {
+            Some(id) => PipelineKind::Custom(id, composite),
+            None if composite => PipelineKind::Composite(info.mode),
+            None => PipelineKind::Compare(info.mode),
+        }
+    }
+
+    /// Returns the crosshair of the comparison, unless it renders difference data.
+    fn crosshair(&self, info: &CompareInfo) -> Option<Crosshair> {
+        info.crosshair
+            .filter(|_| self.flags & FLAG_DIFFERENCE_OUTPUT == 0)
+    }
 
+    /// Records the crosshair over a comparison drawn in the current render pass, once per
+    /// cell in [`CompareMode::Grid`].
+    unsafe fn cmd_draw_crosshair(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        pipeline: vk::Pipeline,
+        viewport: vk::Viewport,
+        input_count: usize,
+        crosshair: &Crosshair,
+        info: &CompareInfo,
+    ) {
+        let viewports: Vec<vk::Viewport> = match info.mode {
+            CompareMode::Grid => (0..input_count as u32)
+                .map(|cell| grid_cell_viewport(info, viewport, input_count, cell))
+                .collect(),
+            _ => vec![viewport],
+        };
+
+        unsafe {
+            self.device.cmd_bind_pipeline(
+                command_buffer,
+                vk::PipelineBindPoint::GRAPHICS,
+                pipeline,
+            );
+        }
+
+        for viewport in viewports {
+            let push_buffer = CrosshairPushConstantBuffer {
+                color: crosshair.color,
+                viewport_size: [viewport.width, viewport.height],
+                position: crosshair.position,
+                width: crosshair.width.max(1.0),
+                gap: crosshair.gap,
+            };
+
+            unsafe {
+                self.device.cmd_set_viewport(command_buffer, 0, &[viewport]);
+                self.device.cmd_push_constants(
+                    command_buffer,
+                    self.crosshair_pipeline_layout,
+                    vk::ShaderStageFlags::FRAGMENT,
+                    0,
+                    bytemuck::bytes_of(&push_buffer),
+                );
+                self.device.cmd_draw(command_buffer, 3, 1, 0, 0);
+            }
+        }
+    }
+
+    /// Returns the amount of inputs each eye is shown, which is all of them without
+    /// multiview. Each eye needs two inputs of its own, as a single one would be compared
+    /// against the other eye's where the input array wraps.
+    fn eye_input_count(&self, input_count: usize) -> Result<usize> {
+        match self.multiview {
+            false => Ok(input_count),
+            true if input_count % 2 == 0 && input_count >= 4 => Ok(input_count / 2),
+            true => Err(anyhow!(
+                "With multiview, the inputs are split evenly between the eyes, at least two \
+                 each, got {}.",
+                input_count
+            )),
+        }
+    }
+
+    /// Fails if a feature reads unfiltered texels of the inputs, which YCbCr conversions
+    /// don't allow.
+    fn check_unfiltered_reads(&self, feature: &str) -> Result<()> {
+        match self.ycbcr_conversion {
+            Some(_) => Err(anyhow!(
+                "{} is not available with a YCbCr conversion, as it reads unfiltered texels.",
+                feature
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Fails if the inputs of a comparison, `input_count` from its input base on, lie outside
+    /// of the bindless input array.
+    fn check_input_base(&self, info: &CompareInfo, input_count: usize) -> Result<()> {
+        match self.bindless_inputs {
+            None if info.input_base != 0 => Err(anyhow!(
+                "An input base requires the comparator to be created with bindless inputs."
+            )),
+            Some(size) if info.input_base as u64 + input_count as u64 > size as u64 => {
+                Err(anyhow!(
+                    "The {} inputs from element {} on exceed the {} bindless inputs.",
+                    input_count,
+                    info.input_base,
+                    size
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Fails if a feature binds the input descriptor sets, which push descriptors don't
+    /// allocate.
+    fn check_descriptor_sets(&self, feature: &str) -> Result<()> {
+        match self.push_descriptor_set_layout {
+            Some(_) => Err(anyhow!(
+                "{} is not available with push descriptors.",
+                feature
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Fails if a feature reads the inputs as floats, which integer inputs can't be read as.
+    fn check_float_reads(&self, feature: &str) -> Result<()> {
+        match self.integer_inputs {
+            Some(_) => Err(anyhow!("{} is not available with integer inputs.", feature)),
+            None => Ok(()),
+        }
+    }
+
+    /// Fails if the comparison reads the inputs in a way their kind doesn't allow.
+    fn check_input_reads(&self, info: &CompareInfo) -> Result<()> {
+        if info.mode == CompareMode::Exact {
+            self.check_unfiltered_reads("The exact mode")?;
+        }
+        if info.nearest {
+            self.check_unfiltered_reads("Nearest sampling")?;
+        }
+        if info.vector_overlay.is_some() {
+            self.check_float_reads("The vector overlay")?;
+        }
         Ok(())
     }
+
+    /// Returns whether the comparison draws input labels.
+    fn labels_shown(&self, info: &CompareInfo) -> bool {
+        !info.input_labels.is_empty() && self.flags & FLAG_DIFFERENCE_OUTPUT == 0
+    }
+
+    /// Returns the divider handle of a comparison, unless it isn't drawn.
+    fn divider_handle(&self, info: &CompareInfo) -> Option<DividerHandle> {
+        info.divider_handle
+            .filter(|_| info.mode == CompareMode::Wipe && self.flags & FLAG_DIFFERENCE_OUTPUT == 0)
+    }
+
+    /// Records the divider handle over a comparison drawn in the current render pass.
+    unsafe fn cmd_draw_handle(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        pipeline: vk::Pipeline,
+        viewport: vk::Viewport,
+        handle: &DividerHandle,
+        info: &CompareInfo,
+    ) {
+        // In pixels, where the normal of the divider is unscaled by the aspect ratio. The
+        // handle is where the divider crosses the line through its position, across the
+        // axis the divider is closer to.
+        let angle = info.divider_orientation.angle();
+        let normal = [angle.cos(), angle.sin()];
+        let direction = [-normal[1], normal[0]];
+        let through = [
+            info.divider_position * viewport.width,
+            info.divider_position * viewport.height,
+        ];
+        let along = match direction[1].abs() >= direction[0].abs() {
+            true => (handle.position * viewport.height - through[1]) / direction[1],
+            false => (handle.position * viewport.width - through[0]) / direction[0],
+        };
+
+        let push_buffer = HandlePushConstantBuffer {
+            color: handle.color.unwrap_or(info.divider_color),
+            center: [
+                through[0] + along * direction[0],
+                through[1] + along * direction[1],
+            ],
+            viewport_size: [viewport.width, viewport.height],
+            normal,
+            radius: handle.radius,
+            shape: handle.shape as u32,
+        };
+
+        unsafe {
+            self.device.cmd_bind_pipeline(
+                command_buffer,
+                vk::PipelineBindPoint::GRAPHICS,
+                pipeline,
+            );
+            self.device.cmd_set_viewport(command_buffer, 0, &[viewport]);
+            self.device.cmd_push_constants(
+                command_buffer,
+                self.handle_pipeline_layout,
+                vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
+                0,
+                bytemuck::bytes_of(&push_buffer),
+            );
+            self.device.cmd_draw(command_buffer, 6, 1, 0, 0);
+        }
+    }
+
+    /// Returns the inputs of a frame in flight, by an index taken modulo their amount.
+    pub(crate) fn frame(&self, frame_index: usize) -> FrameInputs {
+        self.frames[frame_index % self.frames.len()]
+    }
+
+    /// Returns how the inputs of a frame in flight are bound.
+    fn input_binding(&self, frame: FrameInputs) -> InputBinding {
+        match self.push_descriptor_set_layout {
+            Some(_) => InputBinding::Pushed(frame.image_views),
+            None => InputBinding::Set(frame.descriptor_set),
+        }
+    }
+
+    /// Binds the inputs as set 0 of a graphics pipeline layout.
+    unsafe fn cmd_bind_inputs(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        layout: vk::PipelineLayout,
+        inputs: InputBinding,
+    ) {
+        match inputs {
+            InputBinding::Set(descriptor_set) => unsafe {
+                self.device.cmd_bind_descriptor_sets(
+                    command_buffer,
+                    vk::PipelineBindPoint::GRAPHICS,
+                    layout,
+                    0,
+                    &[descriptor_set],
+                    &[] as &[u32],
+                )
+            },
+            InputBinding::Pushed(image_views) => unsafe {
+                cmd_push_input_descriptors(
+                    &self.device,
+                    command_buffer,
+                    layout,
+                    &self.sampler,
+                    &image_views,
+                )
+            },
+        }
+    }
+
+    /// Returns the render pass, unless the comparator only uses dynamic rendering.
+    pub(crate) fn render_pass(&self) -> Result<vk::RenderPass> {
+        self.render_pass.ok_or_else(|| {
+            anyhow!(
+                "The comparator was created with dynamic_rendering_only and has no render pass."
+            )
+        })
+    }
+
+    /// Returns the framebuffer of a prepared output view.
+    fn framebuffer(&self, out_image_view: vk::ImageView) -> Result<vk::Framebuffer> {
+        self.framebuffers
+            .get(&out_image_view)
+            .copied()
+            .ok_or_else(|| {
+                anyhow!(
+                    "The output image view {:?} has not been prepared, see `prepare`.",
+                    out_image_view
+                )
+            })
+    }
+
+    /// Returns the prepared pipeline of the kind, for render pass or dynamic rendering use.
+    fn pipeline(&self, kind: PipelineKind, recording: Recording) -> Result<vk::Pipeline> {
+        let pipeline = self.pipelines.get(&(kind, recording));
+        pipeline.copied().ok_or_else(|| match kind {
+            PipelineKind::Compare(mode) => {
+                anyhow!("The {:?} mode has not been prepared, see `prepare`.", mode)
+            }
+            PipelineKind::Composite(mode) => anyhow!(
+                "The {:?} mode has not been prepared with an opacity, see `prepare`.",
+                mode
+            ),
+            PipelineKind::Vectors => {
+                anyhow!("The vector overlay has not been prepared, see `prepare`.")
+            }
+            PipelineKind::Handle => {
+                anyhow!("The divider handle has not been prepared, see `prepare`.")
+            }
+            PipelineKind::PixelGrid => {
+                anyhow!("The pixel grid has not been prepared, see `prepare`.")
+            }
+            PipelineKind::Crosshair => {
+                anyhow!("The crosshair has not been prepared, see `prepare`.")
+            }
+            PipelineKind::Labels => {
+                anyhow!("The input labels have not been prepared, see `prepare`.")
+            }
+            PipelineKind::Custom(id, _) => anyhow!(
+                "The custom mode {:?} has not been prepared, see `prepare`.",
+                id
+            ),
+        })
+    }
+
+    /// Creates the pipelines of the kind for every way the comparator may record them,
+    /// unless they exist already.
+    fn prepare_pipelines(&mut self, kind: PipelineKind) -> Result<()> {
+        let recordings = [
+            (self.render_pass.is_some(), Recording::RenderPass),
+            (self.subpass.is_some(), Recording::Subpass),
+            (self.dynamic_rendering, Recording::DynamicRendering),
+        ];
+
+        for (enabled, recording) in recordings {
+            if enabled {
+                self.prepare_pipeline(kind, recording)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn prepare_pipeline(&mut self, kind: PipelineKind, recording: Recording) -> Result<()> {
+        let key = (kind, recording);
+        if self.pipelines.contains_key(&key) {
+            return Ok(());
+        }
+
+        let target = match recording {
+            Recording::RenderPass => PipelineTarget::Subpass(self.render_pass()?, 0),
+            Recording::Subpass => {
+                let subpass = self.subpass.ok_or_else(|| {
+                    anyhow!("The comparator was created without a subpass of the caller.")
+                })?;
+                PipelineTarget::Subpass(subpass.render_pass, subpass.index)
+            }
+            Recording::DynamicRendering => {
+                PipelineTarget::Rendering(self.format, view_mask(self.multiview))
+            }
+        };
+
+        let variant = FragmentVariant {
+            stats: self.stats.is_some(),
+            color_transforms: self.color_transforms.is_some(),
+            lut: self.lut.is_some(),
+            integer: self.integer_inputs.is_some(),
+        };
+        let mapping = InputMapping {
+            depth_range: self.depth_range,
+            integer: self.integer_inputs.as_ref(),
+            array_size: self.bindless_inputs.unwrap_or(Self::image_sampler_count()),
+        };
+        let pipeline = match kind {
+            PipelineKind::Compare(mode) | PipelineKind::Composite(mode) => create_pipeline(
+                &self.device,
+                self.pipeline_cache,
+                target,
+                self.pipeline_layout,
+                variant,
+                mode,
+                self.multiview,
+                matches!(kind, PipelineKind::Composite(_)),
+                &mapping,
+                self.fragment_shader.as_deref(),
+            )?,
+            // Custom shaders have no modes to compile out, they see the first.
+            PipelineKind::Custom(id, composite) => create_pipeline(
+                &self.device,
+                self.pipeline_cache,
+                target,
+                self.pipeline_layout,
+                variant,
+                CompareMode::Wipe,
+                self.multiview,
+                composite,
+                &mapping,
+                Some(self.custom_mode(id)?.spirv()),
+            )?,
+            PipelineKind::Vectors => create_vector_pipeline(
+                &self.device,
+                self.pipeline_cache,
+                target,
+                self.vector_pipeline_layout,
+            )?,
+            PipelineKind::Handle => create_handle_pipeline(
+                &self.device,
+                self.pipeline_cache,
+                target,
+                self.handle_pipeline_layout,
+            )?,
+            PipelineKind::PixelGrid => create_grid_pipeline(
+                &self.device,
+                self.pipeline_cache,
+                target,
+                self.grid_pipeline_layout,
+            )?,
+            PipelineKind::Crosshair => create_crosshair_pipeline(
+                &self.device,
+                self.pipeline_cache,
+                target,
+                self.crosshair_pipeline_layout,
+            )?,
+            PipelineKind::Labels => {
+                let labels = self
+                    .labels
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("The comparator was not created with a glyph atlas."))?;
+                create_label_pipeline(
+                    &self.device,
+                    self.pipeline_cache,
+                    target,
+                    labels.pipeline_layout,
+                )?
+            }
+        };
+
+        self.name_object(pipeline, &format!("pipeline::{:?}", kind));
+        self.pipelines.insert(key, pipeline);
+        Ok(())
+    }
+
+    /// Begins the debug label of the comparison, if labels can be recorded. Returns whether a
+    /// label was begun.
+    unsafe fn cmd_begin_label(&self, info: &CompareInfo) -> Result<bool> {
+        if !self.debug_utils {
+            return Ok(false);
+        }
+        let label = info.label.unwrap_or("rtcmp::compare");
+
+        let label = CString::new(label)?;
+        let label = vk::DebugUtilsLabelEXT::builder()
+            .label_name(label.as_bytes_with_nul())
+            .color(bytemuck::cast(info.label_color))
+            .build();
+
+        unsafe {
+            (self.device.commands().cmd_begin_debug_utils_label_ext)(info.command_buffer, &label)
+        };
+        Ok(true)
+    }
+
+    unsafe fn cmd_end_label(&self, command_buffer: vk::CommandBuffer) {
+        unsafe { (self.device.commands().cmd_end_debug_utils_label_ext)(command_buffer) };
+    }
+
+    unsafe fn cmd_push_constants(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        push_buffer: &PushConstantBuffer,
+    ) {
+        let bytes: &[u8] = bytemuck::bytes_of(push_buffer);
+        unsafe {
+            self.device.cmd_push_constants(
+                command_buffer,
+                self.pipeline_layout,
+                vk::ShaderStageFlags::FRAGMENT,
+                0,
+                bytes,
+            );
+        }
+    }
+}
+
+/// Returns the view mask of the render pass and rendering, covering both eyes with
+/// multiview.
+fn view_mask(multiview: bool) -> u32 {
+    match multiview {
+        true => 0b11,
+        false => 0,
+    }
+}
+
+/// Returns the amount of columns and rows of a grid holding `count` cells.
+fn grid_dimensions(count: usize, columns: u32) -> (u32, u32) {
+    let count = count.max(1) as u32;
+    let columns = match columns {
+        0 => (count as f32).sqrt().ceil() as u32,
+        columns => columns.min(count),
+    };
+    (columns, count.div_ceil(columns))
+}
+
+/// Returns the amount of columns of the grid in [`CompareMode::Grid`], and the width and
+/// height of its cells in pixels. The gap is only between cells, not around the grid.
+fn grid_layout(info: &CompareInfo, viewport: vk::Viewport, input_count: usize) -> (u32, f32, f32) {
+    let (columns, rows) = grid_dimensions(input_count, info.grid_columns);
+    let gap = info.grid_gap as f32;
+    let cell_width = ((viewport.width - gap * (columns - 1) as f32) / columns as f32).max(1.0);
+    let cell_height = ((viewport.height - gap * (rows - 1) as f32) / rows as f32).max(1.0);
+    (columns, cell_width, cell_height)
+}
+
+/// Returns the part of the viewport showing a cell in [`CompareMode::Grid`].
+fn grid_cell_viewport(
+    info: &CompareInfo,
+    viewport: vk::Viewport,
+    input_count: usize,
+    cell: u32,
+) -> vk::Viewport {
+    let (columns, cell_width, cell_height) = grid_layout(info, viewport, input_count);
+    let gap = info.grid_gap as f32;
+    vk::Viewport::builder()
+        .x(viewport.x + (cell % columns) as f32 * (cell_width + gap))
+        .y(viewport.y + (cell / columns) as f32 * (cell_height + gap))
+        .width(cell_width)
+        .height(cell_height)
+        .min_depth(viewport.min_depth)
+        .max_depth(viewport.max_depth)
+        .build()
+}
+
+/// Returns the descriptors each input sampler takes from a pool. Samplers with a YCbCr
+/// conversion may take a descriptor per plane.
+fn descriptors_per_sampler(ycbcr_conversion: Option<&YcbcrConversion>) -> u32 {
+    match ycbcr_conversion {
+        Some(_) => MAX_YCBCR_DESCRIPTOR_COUNT,
+        None => 1,
+    }
+}
+
+/// Returns the mask of the inputs to decode from sRGB, a bit per input index.
+fn srgb_input_mask(encodings: &[InputEncoding]) -> u32 {
+    encodings
+        .iter()
+        .enumerate()
+        .filter(|(_, encoding)| **encoding == InputEncoding::Srgb)
+        .fold(0, |mask, (i, _)| mask | (1 << i))
+}
+
+/// Checks that a comparison in [`CompareMode::Mask`] has its mask bound as the third input.
+fn check_mask_input(info: &CompareInfo, input_count: usize) -> Result<()> {
+    if info.mode == CompareMode::Mask && input_count < 3 {
+        return Err(anyhow!(
+            "Expected the mask as the third input, got {} inputs.",
+            input_count
+        ));
+    }
+    Ok(())
+}
+
+/// Checks that the split positions of a comparison fit its inputs, see
+/// [`CompareInfo::split_positions`].
+fn check_split_positions(info: &CompareInfo, input_count: usize) -> Result<()> {
+    let positions = info.split_positions;
+    if info.mode != CompareMode::Split || positions.is_empty() {
+        return Ok(());
+    }
+
+    if positions.len() != input_count - 1 {
+        return Err(anyhow!(
+            "Expected {} split positions for {} inputs, got {}.",
+            input_count - 1,
+            input_count,
+            positions.len()
+        ));
+    }
+    if positions.iter().any(|p| !(0.0..=1.0).contains(p)) || !positions.is_sorted() {
+        return Err(anyhow!(
+            "The split positions must be ascending in the range [0.0, 1.0]."
+        ));
+    }
+
+    Ok(())
+}
+
+/// Returns the input shown in the place of the input at `index`, see
+/// [`CompareInfo::swap_inputs`].
+fn swapped_input(info: &CompareInfo, index: usize) -> usize {
+    match (info.swap_inputs, index) {
+        (true, 0) => 1,
+        (true, 1) => 0,
+        _ => index,
+    }
+}
+
+/// Returns the position of the edge before the strip `index` of `count` strips, in the range
+/// `[0.0, 1.0]`.
+fn split_position(info: &CompareInfo, count: u32, index: u32) -> f32 {
+    match index {
+        0 => 0.0,
+        index if index >= count => 1.0,
+        index => info
+            .split_positions
+            .get(index as usize - 1)
+            .copied()
+            .unwrap_or(index as f32 / count as f32),
+    }
+}
+
+/// Returns the viewport covering the whole of an output of the given extent.
+fn full_viewport(extent: vk::Extent2D) -> vk::Viewport {
+    vk::Viewport::builder()
+        .x(0.0)
+        .y(0.0)
+        .width(extent.width as f32)
+        .height(extent.height as f32)
+        .min_depth(0.0)
+        .max_depth(1.0)
+        .build()
+}
+
+/// Returns the rectangle a viewport covers, also when it is flipped by a negative height.
+fn viewport_rect(viewport: vk::Viewport) -> vk::Rect2D {
+    let top = viewport.y.min(viewport.y + viewport.height);
+    vk::Rect2D::builder()
+        .offset(vk::Offset2D {
+            x: viewport.x.max(0.0) as i32,
+            y: top.max(0.0) as i32,
+        })
+        .extent(vk::Extent2D {
+            width: viewport.width as u32,
+            height: viewport.height.abs() as u32,
+        })
+        .build()
+}
+
+/// The rectangle covering the whole extent.
+fn full_rect(extent: vk::Extent2D) -> vk::Rect2D {
+    vk::Rect2D::builder()
+        .offset(vk::Offset2D::default())
+        .extent(extent)
+        .build()
+}
+
+/// Checks that a render area lies within the output, see [`CompareInfo::render_area`].
+fn check_render_area(area: vk::Rect2D, extent: vk::Extent2D) -> Result<()> {
+    let within = |offset: i32, size: u32, limit: u32| {
+        offset >= 0 && offset as u64 + size as u64 <= limit as u64
+    };
+    if !within(area.offset.x, area.extent.width, extent.width)
+        || !within(area.offset.y, area.extent.height, extent.height)
+    {
+        return Err(anyhow!(
+            "The render area {:?} exceeds the output extent {:?}.",
+            area,
+            extent
+        ));
+    }
+    Ok(())
+}
+
+/// Checks that an amount of input image views is supported.
+fn check_input_count(count: usize) -> Result<()> {
+    if !(2..=MAX_INPUTS).contains(&count) {
+        return Err(anyhow!(
+            "Expected between 2 and {} input image views, got {}.",
+            MAX_INPUTS,
+            count
+        ));
+    }
+    Ok(())
 }
@@ -0,0 +1,73 @@
+//! Loading input images from image files, e.g. to compare reference renders on disk.
+//!
+//! With the `io` feature, PNG and JPEG files are decoded with the `image` crate and EXR files
+//! with the `exr` crate, then uploaded like any other [`InputImage`]. Values are kept as
+//! stored: 8-bit files are uploaded as `R8G8B8A8_UNORM` and deeper ones as 32-bit floats,
+//! both still sRGB encoded, see [`InputEncoding::Srgb`](crate::InputEncoding::Srgb). EXR
+//! files hold linear values and are uploaded as 32-bit floats.
+
+use anyhow::Result;
+use std::path::Path;
+use std::sync::Arc;
+use vulkanalia::prelude::v1_3::*;
+
+use crate::input_image::InputImage;
+use crate::transfer::UploadContext;
+
+impl InputImage {
+    /// Decodes an image file and uploads it. The format is chosen by the `exr` extension, or
+    /// guessed by the `image` crate otherwise.
+    pub fn load(
+        device: &Arc<Device>,
+        upload: &UploadContext,
+        path: impl AsRef<Path>,
+    ) -> Result<Self> {
+        let path = path.as_ref();
+        let is_exr = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("exr"));
+        if is_exr {
+            return Self::load_exr(device, upload, path);
+        }
+
+        let decoded = image::open(path)?;
+        let color = decoded.color();
+        if color.bytes_per_pixel() > color.channel_count() {
+            let pixels = decoded.to_rgba32f();
+            Self::from_rgba32f(device, upload, pixels.width(), pixels.height(), &pixels)
+        } else {
+            let pixels = decoded.to_rgba8();
+            Self::from_rgba8(device, upload, pixels.width(), pixels.height(), &pixels)
+        }
+    }
+
+    /// Decodes the first RGBA layer of an EXR file and uploads it as 32-bit floats. A
+    /// missing alpha channel is opaque.
+    pub fn load_exr(
+        device: &Arc<Device>,
+        upload: &UploadContext,
+        path: impl AsRef<Path>,
+    ) -> Result<Self> {
+        let image = exr::prelude::read_first_rgba_layer_from_file(
+            path,
+            |resolution, _| {
+                let (width, height) = (resolution.width(), resolution.height());
+                (width, vec![0.0_f32; width * height * 4])
+            },
+            |(width, pixels), position, (r, g, b, a): (f32, f32, f32, f32)| {
+                let index = (position.y() * *width + position.x()) * 4;
+                pixels[index..index + 4].copy_from_slice(&[r, g, b, a]);
+            },
+        )?;
+
+        let size = image.layer_data.size;
+        let (_, pixels) = &image.layer_data.channel_data.pixels;
+        Self::from_rgba32f(
+            device,
+            upload,
+            size.width() as u32,
+            size.height() as u32,
+            pixels,
+        )
+    }
+}
@@ -0,0 +1,269 @@
+//! 3D lookup tables, applied to inputs to compare graded against ungraded images.
+//!
+//! A comparator created with a `lut` samples it with the values of the inputs listed in
+//! [`CompareInfo::lut_inputs`](crate::CompareInfo::lut_inputs), after their encoding and
+//! color transform. The table is a 3D image whose red, green and blue coordinates run along
+//! x, y and z, which is how [`CubeLut`] lays out the texels of a parsed `.cube` file.
+
+use anyhow::{Result, anyhow};
+use std::sync::Arc;
+use vulkanalia::prelude::v1_3::*;
+
+use crate::vulkan::{
+    descriptors::{
+        create_descriptor_pool, create_descriptor_set, create_image_descriptor_set_layout,
+        update_image_descriptor_set,
+    },
+    sampler::create_image_sampler,
+};
+use crate::{RenderTargetComparator, SamplerConfig};
+
+/// A 3D lookup table read from the `.cube` format.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CubeLut {
+    /// The title of the table, if it has one.
+    pub title: Option<String>,
+    /// The amount of entries along each axis.
+    pub size: u32,
+    /// The RGBA texels, with red changing fastest and blue slowest. Alpha is one.
+    pub texels: Vec<[f32; 4]>,
+}
+
+impl CubeLut {
+    /// Parses the text of a `.cube` file. Only 3D tables over the default domain of
+    /// `[0.0, 1.0]` are supported, given by `DOMAIN_MIN`/`DOMAIN_MAX` or the input ranges
+    /// of older files. Keywords of other applications are skipped.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut title = None;
+        let mut size = None;
+        let mut texels = Vec::new();
+
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (keyword, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let rest = rest.trim();
+            match keyword {
+                "TITLE" => title = Some(rest.trim_matches('"').to_string()),
+                "LUT_3D_SIZE" => {
+                    size = Some(rest.parse::<u32>().map_err(|_| {
+                        anyhow!("Invalid LUT size '{}' on line {}.", rest, number + 1)
+                    })?);
+                }
+                "LUT_1D_SIZE" => {
+                    return Err(anyhow!("1D lookup tables are not supported."));
+                }
+                "DOMAIN_MIN" | "DOMAIN_MAX" => {
+                    let default = if keyword == "DOMAIN_MIN" { 0.0 } else { 1.0 };
+                    if parse_numbers(rest, number)? != [default; 3] {
+                        return Err(anyhow!(
+                            "Only the default domain of [0.0, 1.0] is supported."
+                        ));
+                    }
+                }
+                "LUT_3D_INPUT_RANGE" | "LUT_1D_INPUT_RANGE" => {
+                    if parse_numbers(rest, number)? != [0.0, 1.0] {
+                        return Err(anyhow!(
+                            "Only the default input range of [0.0, 1.0] is supported, got \
+                             '{}' on line {}.",
+                            rest,
+                            number + 1
+                        ));
+                    }
+                }
+                // Keywords of other applications, e.g. `LUT_IN_VIDEO_RANGE`.
+                _ if keyword.starts_with(|c: char| c.is_ascii_alphabetic()) => {}
+                _ => {
+                    let [r, g, b] = parse_numbers(line, number)?;
+                    texels.push([r, g, b, 1.0]);
+                }
+            }
+        }
+
+        let size = size.ok_or_else(|| anyhow!("The LUT_3D_SIZE is missing."))?;
+        if size < 2 {
+            return Err(anyhow!("The LUT size must be at least 2, got {}.", size));
+        }
+        let expected = (size as usize).pow(3);
+        if texels.len() != expected {
+            return Err(anyhow!(
+                "Expected {} entries for a LUT of size {}, got {}.",
+                expected,
+                size,
+                texels.len()
+            ));
+        }
+
+        Ok(Self {
+            title,
+            size,
+            texels,
+        })
+    }
+
+    /// Returns the extent of the 3D image holding the table.
+    pub fn extent(&self) -> vk::Extent3D {
+        vk::Extent3D {
+            width: self.size,
+            height: self.size,
+            depth: self.size,
+        }
+    }
+
+    /// Returns the texels as tightly packed `R32G32B32A32_SFLOAT` data, ready to be copied
+    /// into the 3D image.
+    pub fn as_rgba32f(&self) -> &[f32] {
+        bytemuck::cast_slice(&self.texels)
+    }
+}
+
+/// Parses `N` whitespace separated floats.
+fn parse_numbers<const N: usize>(text: &str, number: usize) -> Result<[f32; N]> {
+    let values = text
+        .split_whitespace()
+        .map(str::parse::<f32>)
+        .collect::<Result<Vec<_>, _>>()
+        .ok()
+        .and_then(|values| <[f32; N]>::try_from(values).ok());
+    values.ok_or_else(|| anyhow!("Expected {} numbers on line {}.", N, number + 1))
+}
+
+/// The descriptor of the lookup table, and the sampler it's read with.
+#[derive(Debug)]
+pub(crate) struct LutResources {
+    device: Arc<Device>,
+    pub(crate) descriptor_set_layout: vk::DescriptorSetLayout,
+    descriptor_pool: vk::DescriptorPool,
+    pub(crate) descriptor_set: vk::DescriptorSet,
+    sampler: vk::Sampler,
+}
+
+impl Drop for LutResources {
+    fn drop(&mut self) {
+        unsafe {
+            self.device.destroy_sampler(self.sampler, None);
+            self.device
+                .destroy_descriptor_pool(self.descriptor_pool, None);
+            self.device
+                .destroy_descriptor_set_layout(self.descriptor_set_layout, None);
+        }
+    }
+}
+
+impl LutResources {
+    pub(crate) fn new(device: &Arc<Device>, view: vk::ImageView) -> Result<Self> {
+        let descriptor_set_layout = create_image_descriptor_set_layout(device)?;
+
+        // Owned right away, so that everything created so far is released on error.
+        let mut lut = Self {
+            device: Arc::clone(device),
+            descriptor_set_layout,
+            descriptor_pool: vk::DescriptorPool::null(),
+            descriptor_set: vk::DescriptorSet::null(),
+            sampler: vk::Sampler::null(),
+        };
+
+        // Interpolates between the entries, regardless of how the inputs are sampled.
+        lut.sampler = create_image_sampler(device, &SamplerConfig::default())?;
+        lut.descriptor_pool = create_descriptor_pool(device, 1, 1, 0, 0)?;
+        lut.descriptor_set =
+            create_descriptor_set(device, &lut.descriptor_pool, &descriptor_set_layout)?;
+        lut.update(view);
+
+        Ok(lut)
+    }
+
+    fn update(&self, view: vk::ImageView) {
+        update_image_descriptor_set(&self.device, &self.descriptor_set, &self.sampler, view);
+    }
+}
+
+impl RenderTargetComparator {
+    /// Replaces the lookup table the comparator was created with.
+    ///
+    /// # Safety
+    ///
+    /// No command buffer recorded with the comparator may be pending execution, as their
+    /// descriptor set is rewritten. The view must be of a 3D image in
+    /// `SHADER_READ_ONLY_OPTIMAL` layout.
+    pub unsafe fn set_lut(&mut self, view: vk::ImageView) -> Result<()> {
+        let lut = self
+            .lut
+            .as_ref()
+            .ok_or_else(|| anyhow!("The comparator was not created with a LUT."))?;
+        lut.update(view);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The identity table of size 2, red changing fastest.
+    const IDENTITY: &str = "0 0 0\n1 0 0\n0 1 0\n1 1 0\n0 0 1\n1 0 1\n0 1 1\n1 1 1\n";
+
+    #[test]
+    fn parses_header_and_entries() {
+        let text = format!("TITLE \"Identity\"\nLUT_3D_SIZE 2\n{}", IDENTITY);
+        let lut = CubeLut::parse(&text).unwrap();
+        assert_eq!(lut.title.as_deref(), Some("Identity"));
+        assert_eq!(lut.size, 2);
+        assert_eq!(lut.texels.len(), 8);
+        assert_eq!(lut.texels[1], [1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(lut.texels[6], [0.0, 1.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn skips_comments_and_blank_lines() {
+        let text = format!(
+            "# Made by hand\n\nLUT_3D_SIZE 2\n  # indented\n{}\n",
+            IDENTITY
+        );
+        assert_eq!(CubeLut::parse(&text).unwrap().texels.len(), 8);
+    }
+
+    #[test]
+    fn rejects_size_mismatch() {
+        let text = format!("LUT_3D_SIZE 3\n{}", IDENTITY);
+        assert!(CubeLut::parse(&text).is_err());
+        assert!(CubeLut::parse(IDENTITY).is_err());
+        assert!(CubeLut::parse("LUT_3D_SIZE 2\n0 0\n").is_err());
+    }
+
+    #[test]
+    fn accepts_default_domain_and_input_range() {
+        let text = format!(
+            "LUT_3D_SIZE 2\nDOMAIN_MIN 0 0 0\nDOMAIN_MAX 1 1 1\nLUT_3D_INPUT_RANGE 0 1\n{}",
+            IDENTITY
+        );
+        assert!(CubeLut::parse(&text).is_ok());
+    }
+
+    #[test]
+    fn rejects_other_domains_and_input_ranges() {
+        for header in [
+            "DOMAIN_MIN -1 -1 -1",
+            "DOMAIN_MAX 2 2 2",
+            "LUT_3D_INPUT_RANGE 0 4",
+            "LUT_1D_INPUT_RANGE -0.5 1",
+        ] {
+            let text = format!("LUT_3D_SIZE 2\n{}\n{}", header, IDENTITY);
+            assert!(CubeLut::parse(&text).is_err(), "{}", header);
+        }
+    }
+
+    #[test]
+    fn rejects_1d_tables() {
+        assert!(CubeLut::parse("LUT_1D_SIZE 2\n0 0 0\n1 1 1\n").is_err());
+    }
+
+    #[test]
+    fn skips_unknown_keywords() {
+        let text = format!("LUT_3D_SIZE 2\nLUT_IN_VIDEO_RANGE\n{}", IDENTITY);
+        assert!(CubeLut::parse(&text).is_ok());
+    }
+}
@@ -0,0 +1,189 @@
+//! Mip chain generation for input images.
+//!
+//! Huge inputs shown zoomed out shimmer when sampled from their base level alone. The
+//! comparator's sampler uses every mip level an input view provides, so generating the chain
+//! once after upload is enough to sample them smoothly.
+
+use anyhow::{Result, anyhow};
+use vulkanalia::prelude::v1_3::*;
+
+use crate::vulkan::commands::{begin_single_time_commands, end_single_time_commands};
+
+/// Returns the amount of mip levels of a full chain for an image of the given extent.
+pub fn mip_level_count(extent: vk::Extent2D) -> u32 {
+    32 - extent.width.max(extent.height).max(1).leading_zeros()
+}
+
+/// Generates the mip chain of an image on the queue and waits for it to finish.
+///
+/// See [`cmd_generate_mipmaps`] for the requirements on the image.
+///
+/// # Safety
+///
+/// The queue must belong to the family the command pool was created for, and support
+/// graphics operations.
+pub unsafe fn generate_mipmaps(
+    device: &Device,
+    queue: vk::Queue,
+    command_pool: vk::CommandPool,
+    image: vk::Image,
+    extent: vk::Extent2D,
+    mip_levels: u32,
+) -> Result<()> {
+    if mip_levels == 0 || mip_levels > mip_level_count(extent) {
+        return Err(anyhow!(
+            "Expected between 1 and {} mip levels, got {}.",
+            mip_level_count(extent),
+            mip_levels
+        ));
+    }
+
+    unsafe {
+        let command_buffer = begin_single_time_commands(device, command_pool)?;
+        cmd_generate_mipmaps(device, command_buffer, image, extent, mip_levels);
+        end_single_time_commands(device, queue, command_pool, command_buffer)
+    }
+}
+
+/// Records generating the mip chain of an image into the command buffer, by blitting every
+/// level down from the previous one.
+///
+/// # Safety
+///
+/// The image must have been created with `mip_levels` levels, `TRANSFER_SRC` and
+/// `TRANSFER_DST` usage, and a format supporting linear filtering of blit sources. All of its
+/// levels must be in `TRANSFER_DST_OPTIMAL` layout, with the base level holding the image
+/// data. All levels are left in `SHADER_READ_ONLY_OPTIMAL` layout.
+pub unsafe fn cmd_generate_mipmaps(
+    device: &Device,
+    command_buffer: vk::CommandBuffer,
+    image: vk::Image,
+    extent: vk::Extent2D,
+    mip_levels: u32,
+) {
+    let subresource = vk::ImageSubresourceRange::builder()
+        .aspect_mask(vk::ImageAspectFlags::COLOR)
+        .base_array_layer(0)
+        .layer_count(1)
+        .level_count(1)
+        .build();
+
+    let mut barrier = vk::ImageMemoryBarrier::builder()
+        .image(image)
+        .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .subresource_range(subresource)
+        .build();
+
+    let mut width = extent.width as i32;
+    let mut height = extent.height as i32;
+
+    for level in 1..mip_levels {
+        // The previous level has been written, by the upload or the last blit.
+        barrier.subresource_range.base_mip_level = level - 1;
+        barrier.old_layout = vk::ImageLayout::TRANSFER_DST_OPTIMAL;
+        barrier.new_layout = vk::ImageLayout::TRANSFER_SRC_OPTIMAL;
+        barrier.src_access_mask = vk::AccessFlags::TRANSFER_WRITE;
+        barrier.dst_access_mask = vk::AccessFlags::TRANSFER_READ;
+
+        unsafe {
+            device.cmd_pipeline_barrier(
+                command_buffer,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::DependencyFlags::empty(),
+                &[] as &[vk::MemoryBarrier],
+                &[] as &[vk::BufferMemoryBarrier],
+                &[barrier],
+            );
+        }
+
+        let next_width = (width / 2).max(1);
+        let next_height = (height / 2).max(1);
+
+        let src_subresource = vk::ImageSubresourceLayers::builder()
+            .aspect_mask(vk::ImageAspectFlags::COLOR)
+            .mip_level(level - 1)
+            .base_array_layer(0)
+            .layer_count(1)
+            .build();
+
+        let dst_subresource = vk::ImageSubresourceLayers::builder()
+            .aspect_mask(vk::ImageAspectFlags::COLOR)
+            .mip_level(level)
+            .base_array_layer(0)
+            .layer_count(1)
+            .build();
+
+        let blit = vk::ImageBlit::builder()
+            .src_offsets([
+                vk::Offset3D { x: 0, y: 0, z: 0 },
+                vk::Offset3D {
+                    x: width,
+                    y: height,
+                    z: 1,
+                },
+            ])
+            .src_subresource(src_subresource)
+            .dst_offsets([
+                vk::Offset3D { x: 0, y: 0, z: 0 },
+                vk::Offset3D {
+                    x: next_width,
+                    y: next_height,
+                    z: 1,
+                },
+            ])
+            .dst_subresource(dst_subresource)
+            .build();
+
+        // The previous level is done with once it has been read.
+        barrier.old_layout = vk::ImageLayout::TRANSFER_SRC_OPTIMAL;
+        barrier.new_layout = vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL;
+        barrier.src_access_mask = vk::AccessFlags::TRANSFER_READ;
+        barrier.dst_access_mask = vk::AccessFlags::SHADER_READ;
+
+        unsafe {
+            device.cmd_blit_image(
+                command_buffer,
+                image,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                image,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &[blit],
+                vk::Filter::LINEAR,
+            );
+
+            device.cmd_pipeline_barrier(
+                command_buffer,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::FRAGMENT_SHADER,
+                vk::DependencyFlags::empty(),
+                &[] as &[vk::MemoryBarrier],
+                &[] as &[vk::BufferMemoryBarrier],
+                &[barrier],
+            );
+        }
+
+        width = next_width;
+        height = next_height;
+    }
+
+    // The last level is only ever written to.
+    barrier.subresource_range.base_mip_level = mip_levels - 1;
+    barrier.old_layout = vk::ImageLayout::TRANSFER_DST_OPTIMAL;
+    barrier.new_layout = vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL;
+    barrier.src_access_mask = vk::AccessFlags::TRANSFER_WRITE;
+    barrier.dst_access_mask = vk::AccessFlags::SHADER_READ;
+
+    unsafe {
+        device.cmd_pipeline_barrier(
+            command_buffer,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::PipelineStageFlags::FRAGMENT_SHADER,
+            vk::DependencyFlags::empty(),
+            &[] as &[vk::MemoryBarrier],
+            &[] as &[vk::BufferMemoryBarrier],
+            &[barrier],
+        );
+    }
+}
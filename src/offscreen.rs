@@ -0,0 +1,119 @@
+//! Headless comparisons into an output image owned by the comparator.
+//!
+//! Snapshot tests and CI pipelines have no swapchain to render into. Rather than creating an
+//! output image by hand, [`RenderTargetComparator::offscreen`] creates the image, its memory
+//! and view of the configured format and extent along with the comparator, and destroys them
+//! with it. The image is retrieved with [`RenderTargetComparator::output_image`], e.g. to
+//! read it back with [`RenderTargetComparator::read_offscreen_output`].
+
+use anyhow::{Result, anyhow};
+use std::sync::Arc;
+use vulkanalia::prelude::v1_3::*;
+
+use crate::vulkan::image::{create_image, create_image_view};
+use crate::{RenderTargetComparator, RenderTargetComparatorCreateInfo};
+
+/// The output image of an offscreen comparator. Destroyed when dropped, after the
+/// comparator's framebuffers.
+#[derive(Debug)]
+pub(crate) struct OwnedOutput {
+    device: Arc<Device>,
+    image: vk::Image,
+    memory: vk::DeviceMemory,
+    view: vk::ImageView,
+}
+
+impl Drop for OwnedOutput {
+    fn drop(&mut self) {
+        unsafe {
+            self.device.destroy_image_view(self.view, None);
+            self.device.destroy_image(self.image, None);
+            self.device.free_memory(self.memory, None);
+        }
+    }
+}
+
+impl RenderTargetComparator {
+    /// Creates a comparator like [`new`](Self::new), rendering into an output image of
+    /// `info.format` (or the difference output's) and `info.extent` that it creates and owns,
+    /// instead of `info.out_image_view`, which may be left unset. Requires the memory
+    /// properties to be set.
+    ///
+    /// The image has a single mip level and can be sampled and copied from. It is left in
+    /// `info.final_layout`, except that the default, `PRESENT_SRC_KHR`, is replaced by
+    /// `TRANSFER_SRC_OPTIMAL` as the image is never presented. With `dynamic_rendering_only`,
+    /// it starts in `UNDEFINED` layout and must be transitioned to `COLOR_ATTACHMENT_OPTIMAL`
+    /// before the first comparison. Not available with multiview.
+    pub fn offscreen(info: &RenderTargetComparatorCreateInfo) -> Result<Self> {
+        if info.multiview {
+            return Err(anyhow!(
+                "Offscreen outputs are not available with multiview."
+            ));
+        }
+        let memory_properties = info.memory_properties.as_ref().ok_or_else(|| {
+            anyhow!("Offscreen outputs require memory_properties to be set at creation.")
+        })?;
+        let format = match info.difference_output {
+            Some(output) => output.format(),
+            None if info.format == vk::Format::UNDEFINED => {
+                return Err(anyhow!("The output format must be set."));
+            }
+            None => info.format,
+        };
+
+        let device = &info.device;
+        let (image, memory) = create_image(
+            device,
+            memory_properties,
+            info.extent,
+            format,
+            1,
+            vk::ImageUsageFlags::COLOR_ATTACHMENT
+                | vk::ImageUsageFlags::SAMPLED
+                | vk::ImageUsageFlags::TRANSFER_SRC,
+        )?;
+
+        // Owned right away, so that everything created so far is released on error.
+        let mut output = OwnedOutput {
+            device: Arc::clone(device),
+            image,
+            memory,
+            view: vk::ImageView::null(),
+        };
+        output.view = create_image_view(device, image, format, 1)?;
+
+        let final_layout = match info.final_layout {
+            vk::ImageLayout::PRESENT_SRC_KHR => vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+            layout => layout,
+        };
+        let info = RenderTargetComparatorCreateInfo {
+            out_image_view: output.view,
+            final_layout,
+            ..info.clone()
+        };
+        let mut comparator = Self::new(&info)?;
+        comparator.name_object(output.image, "output_image");
+        comparator.name_object(output.view, "output_image_view");
+        comparator.owned_output = Some(output);
+        Ok(comparator)
+    }
+
+    /// Returns the output image of a comparator created with [`offscreen`](Self::offscreen),
+    /// e.g. to copy it or sample it in further passes. It must not be destroyed.
+    pub fn output_image(&self) -> Option<vk::Image> {
+        self.owned_output.as_ref().map(|output| output.image)
+    }
+
+    /// Reads back the output image of a comparator created with
+    /// [`offscreen`](Self::offscreen), like [`read_output`](Self::read_output).
+    ///
+    /// # Safety
+    ///
+    /// The comparison must have finished rendering into the image.
+    pub unsafe fn read_offscreen_output(&mut self) -> Result<Vec<u8>> {
+        let image = self
+            .output_image()
+            .ok_or_else(|| anyhow!("The comparator does not own its output image."))?;
+        unsafe { self.read_output(image) }
+    }
+}
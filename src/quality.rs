@@ -0,0 +1,591 @@
+//! Image quality metrics of the first two inputs, computed on the GPU.
+//!
+//! Metrics are recorded into the caller's command buffer, e.g. next to a comparison, and
+//! return a query owning the buffer they are written to. Once the command buffer has
+//! finished executing, the query resolves to the metric. Like the metrics of batch
+//! comparisons, every workgroup reduces its share of the pixels into a partial result,
+//! and the partials are summed up on the host. Histograms are made of per-workgroup
+//! histograms the same way.
+//!
+//! With a metrics timeline, each query also gives a [`QueryCompletion`] for a value of a
+//! timeline semaphore that the caller's submission signals, so that engines with their own
+//! submission schedulers can poll for the results.
+
+use anyhow::{Result, anyhow};
+use std::sync::Arc;
+use vulkanalia::prelude::v1_3::*;
+
+use crate::RenderTargetComparator;
+use crate::batch::{MetricsPartial, PairMetrics, reduce_partials};
+use crate::timing::TimedPass;
+use crate::vulkan::{
+    descriptors::{
+        create_compute_buffer_descriptor_set_layout, create_descriptor_pool, create_descriptor_set,
+        update_storage_buffer_descriptor,
+    },
+    memory::create_buffer,
+    pipeline::{create_frame_metrics_pipeline, create_histogram_pipeline, create_ssim_pipeline},
+    push_constants::{HistogramPushConstantBuffer, MetricsPushConstantBuffer},
+};
+
+/// The amount of workgroups dispatched per metric. Each produces one partial result.
+const WORKGROUPS: (u32, u32) = (8, 8);
+const PARTIALS: usize = (WORKGROUPS.0 * WORKGROUPS.1) as usize;
+
+/// The amount of bins of each channel's histogram, one per 8-bit value.
+pub const HISTOGRAM_BINS: usize = 256;
+
+// Mirrors the `Partial` struct of the SSIM compute shader (std430 layout).
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct SsimPartial {
+    ssim_sum: f32,
+    pixel_count: u32,
+}
+
+/// The pipelines of the metrics, and the layout of the buffers they write to.
+#[derive(Debug)]
+pub(crate) struct QualityPipelines {
+    device: Arc<Device>,
+    descriptor_set_layout: vk::DescriptorSetLayout,
+    ssim_pipeline_layout: vk::PipelineLayout,
+    ssim_pipeline: vk::Pipeline,
+    metrics_pipeline_layout: vk::PipelineLayout,
+    metrics_pipeline: vk::Pipeline,
+    histogram_pipeline_layout: vk::PipelineLayout,
+    histogram_pipeline: vk::Pipeline,
+}
+
+impl Drop for QualityPipelines {
+    fn drop(&mut self) {
+        unsafe {
+            self.device.destroy_pipeline(self.ssim_pipeline, None);
+            self.device
+                .destroy_pipeline_layout(self.ssim_pipeline_layout, None);
+            self.device.destroy_pipeline(self.metrics_pipeline, None);
+            self.device
+                .destroy_pipeline_layout(self.metrics_pipeline_layout, None);
+            self.device.destroy_pipeline(self.histogram_pipeline, None);
+            self.device
+                .destroy_pipeline_layout(self.histogram_pipeline_layout, None);
+            self.device
+                .destroy_descriptor_set_layout(self.descriptor_set_layout, None);
+        }
+    }
+}
+
+impl QualityPipelines {
+    /// Creates the pipelines, binding the inputs with `input_layout` in set 0.
+    pub(crate) fn new(
+        device: &Arc<Device>,
+        pipeline_cache: vk::PipelineCache,
+        input_layout: vk::DescriptorSetLayout,
+    ) -> Result<Self> {
+        let descriptor_set_layout = create_compute_buffer_descriptor_set_layout(device)?;
+
+        // Owned right away, so that everything created so far is released on error.
+        let mut pipelines = Self {
+            device: Arc::clone(device),
+            descriptor_set_layout,
+            ssim_pipeline_layout: vk::PipelineLayout::null(),
+            ssim_pipeline: vk::Pipeline::null(),
+            metrics_pipeline_layout: vk::PipelineLayout::null(),
+            metrics_pipeline: vk::Pipeline::null(),
+            histogram_pipeline_layout: vk::PipelineLayout::null(),
+            histogram_pipeline: vk::Pipeline::null(),
+        };
+
+        let set_layouts = [input_layout, descriptor_set_layout];
+        (pipelines.ssim_pipeline_layout, pipelines.ssim_pipeline) =
+            create_ssim_pipeline(device, pipeline_cache, &set_layouts)?;
+        (
+            pipelines.metrics_pipeline_layout,
+            pipelines.metrics_pipeline,
+        ) = create_frame_metrics_pipeline(device, pipeline_cache, &set_layouts)?;
+        (
+            pipelines.histogram_pipeline_layout,
+            pipelines.histogram_pipeline,
+        ) = create_histogram_pipeline(device, pipeline_cache, &set_layouts)?;
+
+        Ok(pipelines)
+    }
+}
+
+/// The timeline semaphore the metrics queries complete on, see
+/// [`RenderTargetComparatorCreateInfo::metrics_timeline`].
+///
+/// [`RenderTargetComparatorCreateInfo::metrics_timeline`]:
+///     crate::RenderTargetComparatorCreateInfo::metrics_timeline
+#[derive(Debug)]
+pub(crate) struct MetricsTimeline {
+    device: Arc<Device>,
+    semaphore: vk::Semaphore,
+}
+
+impl Drop for MetricsTimeline {
+    fn drop(&mut self) {
+        unsafe { self.device.destroy_semaphore(self.semaphore, None) };
+    }
+}
+
+impl MetricsTimeline {
+    pub(crate) fn new(device: &Arc<Device>) -> Result<Self> {
+        let mut type_info = vk::SemaphoreTypeCreateInfo::builder()
+            .semaphore_type(vk::SemaphoreType::TIMELINE)
+            .initial_value(0)
+            .build();
+        let info = vk::SemaphoreCreateInfo::builder()
+            .push_next(&mut type_info)
+            .build();
+
+        let semaphore = unsafe { device.create_semaphore(&info, None) }?;
+        Ok(Self {
+            device: Arc::clone(device),
+            semaphore,
+        })
+    }
+
+    /// Returns the completion at the value.
+    fn completion(self: &Arc<Self>, value: u64) -> QueryCompletion {
+        QueryCompletion {
+            timeline: Arc::clone(self),
+            value,
+        }
+    }
+}
+
+/// The value of the comparator's metrics timeline semaphore at which a query completes.
+///
+/// The submission of the command buffer the query was recorded into must signal the
+/// semaphore to the value, with `VkTimelineSemaphoreSubmitInfo`. The caller picks the values,
+/// which must increase with every submission signaling the timeline, e.g. a frame counter.
+/// Keeps the semaphore alive, also past the comparator.
+#[derive(Clone, Debug)]
+pub struct QueryCompletion {
+    timeline: Arc<MetricsTimeline>,
+    value: u64,
+}
+
+impl QueryCompletion {
+    /// Returns the timeline semaphore to signal.
+    pub fn semaphore(&self) -> vk::Semaphore {
+        self.timeline.semaphore
+    }
+
+    /// Returns the value to signal the semaphore to.
+    pub fn value(&self) -> u64 {
+        self.value
+    }
+
+    /// Returns `true` once the semaphore has reached the value, and the query can be
+    /// resolved.
+    pub fn is_complete(&self) -> Result<bool> {
+        let device = &self.timeline.device;
+        let value = unsafe { device.get_semaphore_counter_value(self.timeline.semaphore) }?;
+        Ok(value >= self.value)
+    }
+
+    /// Waits for the semaphore to reach the value.
+    pub fn wait(&self) -> Result<()> {
+        let semaphores = [self.timeline.semaphore];
+        let values = [self.value];
+        let info = vk::SemaphoreWaitInfo::builder()
+            .semaphores(&semaphores)
+            .values(&values)
+            .build();
+        unsafe { self.timeline.device.wait_semaphores(&info, u64::MAX) }?;
+        Ok(())
+    }
+}
+
+/// The buffer a metric is written to, and its descriptor.
+#[derive(Debug)]
+struct QueryBuffer {
+    device: Arc<Device>,
+    descriptor_pool: vk::DescriptorPool,
+    descriptor_set: vk::DescriptorSet,
+    buffer: vk::Buffer,
+    memory: vk::DeviceMemory,
+    size: vk::DeviceSize,
+    timeline: Option<Arc<MetricsTimeline>>,
+}
+
+impl Drop for QueryBuffer {
+    fn drop(&mut self) {
+        unsafe {
+            self.device.destroy_buffer(self.buffer, None);
+            self.device.free_memory(self.memory, None);
+            self.device
+                .destroy_descriptor_pool(self.descriptor_pool, None);
+        }
+    }
+}
+
+impl QueryBuffer {
+    fn new(
+        device: &Arc<Device>,
+        memory_properties: &vk::PhysicalDeviceMemoryProperties,
+        descriptor_set_layout: vk::DescriptorSetLayout,
+        size: vk::DeviceSize,
+        timeline: Option<Arc<MetricsTimeline>>,
+    ) -> Result<Self> {
+        let (buffer, memory) = create_buffer(
+            device,
+            memory_properties,
+            size,
+            vk::BufferUsageFlags::STORAGE_BUFFER,
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+        )?;
+
+        // Owned right away, so that everything created so far is released on error.
+        let mut query = Self {
+            device: Arc::clone(device),
+            descriptor_pool: vk::DescriptorPool::null(),
+            descriptor_set: vk::DescriptorSet::null(),
+            buffer,
+            memory,
+            size,
+            timeline,
+        };
+
+        query.descriptor_pool = create_descriptor_pool(device, 1, 0, 1, 0)?;
+        query.descriptor_set =
+            create_descriptor_set(device, &query.descriptor_pool, &descriptor_set_layout)?;
+        update_storage_buffer_descriptor(device, &query.descriptor_set, 0, &buffer);
+
+        Ok(query)
+    }
+
+    /// Records dispatching the metric's pipeline over the inputs bound by `input_set`, and
+    /// making its partial results visible to the host.
+    unsafe fn cmd_dispatch(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        pipeline_layout: vk::PipelineLayout,
+        pipeline: vk::Pipeline,
+        input_set: vk::DescriptorSet,
+        push_constants: &[u8],
+    ) {
+        let barrier = vk::BufferMemoryBarrier::builder()
+            .src_access_mask(vk::AccessFlags::SHADER_WRITE)
+            .dst_access_mask(vk::AccessFlags::HOST_READ)
+            .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .buffer(self.buffer)
+            .offset(0)
+            .size(vk::WHOLE_SIZE)
+            .build();
+
+        unsafe {
+            self.device
+                .cmd_bind_pipeline(command_buffer, vk::PipelineBindPoint::COMPUTE, pipeline);
+            self.device.cmd_bind_descriptor_sets(
+                command_buffer,
+                vk::PipelineBindPoint::COMPUTE,
+                pipeline_layout,
+                0,
+                &[input_set, self.descriptor_set],
+                &[] as &[u32],
+            );
+            self.device.cmd_push_constants(
+                command_buffer,
+                pipeline_layout,
+                vk::ShaderStageFlags::COMPUTE,
+                0,
+                push_constants,
+            );
+            self.device
+                .cmd_dispatch(command_buffer, WORKGROUPS.0, WORKGROUPS.1, 1);
+            self.device.cmd_pipeline_barrier(
+                command_buffer,
+                vk::PipelineStageFlags::COMPUTE_SHADER,
+                vk::PipelineStageFlags::HOST,
+                vk::DependencyFlags::empty(),
+                &[] as &[vk::MemoryBarrier],
+                &[barrier],
+                &[] as &[vk::ImageMemoryBarrier],
+            );
+        }
+    }
+
+    fn completion(&self, value: u64) -> Option<QueryCompletion> {
+        self.timeline
+            .as_ref()
+            .map(|timeline| timeline.completion(value))
+    }
+
+    /// Reads back the partial results.
+    unsafe fn read<T: bytemuck::Pod>(&self) -> Result<Vec<T>> {
+        let count = self.size as usize / std::mem::size_of::<T>();
+        unsafe {
+            let memory =
+                self.device
+                    .map_memory(self.memory, 0, self.size, vk::MemoryMapFlags::empty())?;
+            let partials = std::slice::from_raw_parts(memory as *const T, count).to_vec();
+            self.device.unmap_memory(self.memory);
+            Ok(partials)
+        }
+    }
+}
+
+/// The structural similarity (SSIM) of the first two inputs, being computed on the GPU.
+#[derive(Debug)]
+pub struct SsimQuery {
+    buffer: QueryBuffer,
+}
+
+impl SsimQuery {
+    /// Returns the completion of the query at a value of the metrics timeline, if the
+    /// comparator was created with one. The submission of the command buffer the query was
+    /// recorded into must signal the timeline to the value.
+    pub fn completion(&self, value: u64) -> Option<QueryCompletion> {
+        self.buffer.completion(value)
+    }
+
+    /// Reads back the mean SSIM over all pixels, in the range `[-1.0, 1.0]`. Identical inputs
+    /// have an SSIM of `1.0`.
+    ///
+    /// # Safety
+    ///
+    /// The command buffer the query was recorded into must have finished executing, e.g.
+    /// once its [`completion`](Self::completion) at the signaled value is complete.
+    pub unsafe fn resolve(&self) -> Result<f32> {
+        let partials = unsafe { self.buffer.read::<SsimPartial>() }?;
+        let (ssim_sum, pixel_count) =
+            partials
+                .iter()
+                .fold((0.0_f64, 0_u64), |(sum, count), partial| {
+                    (
+                        sum + partial.ssim_sum as f64,
+                        count + partial.pixel_count as u64,
+                    )
+                });
+        if pixel_count == 0 {
+            return Err(anyhow!("The inputs hold no pixels to compute the SSIM of."));
+        }
+        Ok((ssim_sum / pixel_count as f64) as f32)
+    }
+}
+
+/// The error metrics of the first two inputs, being computed on the GPU.
+#[derive(Debug)]
+pub struct MetricsQuery {
+    buffer: QueryBuffer,
+}
+
+impl MetricsQuery {
+    /// Returns the completion of the query at a value of the metrics timeline, if the
+    /// comparator was created with one. The submission of the command buffer the query was
+    /// recorded into must signal the timeline to the value.
+    pub fn completion(&self, value: u64) -> Option<QueryCompletion> {
+        self.buffer.completion(value)
+    }
+
+    /// Reads back the metrics, like those of a batch comparison. See [`PairMetrics::psnr`]
+    /// for the PSNR.
+    ///
+    /// # Safety
+    ///
+    /// The command buffer the query was recorded into must have finished executing, e.g.
+    /// once its [`completion`](Self::completion) at the signaled value is complete.
+    pub unsafe fn resolve(&self) -> Result<PairMetrics> {
+        let partials = unsafe { self.buffer.read::<MetricsPartial>() }?;
+        Ok(reduce_partials(&partials))
+    }
+}
+
+/// The per-channel histograms of the first two inputs, and optionally of their difference.
+///
+/// Each histogram holds [`HISTOGRAM_BINS`] bins of the red, green, blue and alpha channel
+/// in turn. The values are binned in the range `[0.0, 1.0]` as they are displayed, sRGB
+/// encoded, with values beyond it in the outermost bins.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HistogramResult {
+    /// The histogram of the first input, over its whole extent.
+    pub a: [u32; HISTOGRAM_BINS * 4],
+    /// The histogram of the second input, over its whole extent.
+    pub b: [u32; HISTOGRAM_BINS * 4],
+    /// The histogram of the absolute difference of the encoded values, over the pixels both
+    /// inputs cover. Only computed when asked for.
+    pub difference: Option<[u32; HISTOGRAM_BINS * 4]>,
+}
+
+/// The histograms of the first two inputs, being computed on the GPU.
+#[derive(Debug)]
+pub struct HistogramQuery {
+    buffer: QueryBuffer,
+    histograms: usize,
+}
+
+impl HistogramQuery {
+    /// Returns the completion of the query at a value of the metrics timeline, if the
+    /// comparator was created with one. The submission of the command buffer the query was
+    /// recorded into must signal the timeline to the value.
+    pub fn completion(&self, value: u64) -> Option<QueryCompletion> {
+        self.buffer.completion(value)
+    }
+
+    /// Reads back the histograms.
+    ///
+    /// # Safety
+    ///
+    /// The command buffer the query was recorded into must have finished executing, e.g.
+    /// once its [`completion`](Self::completion) at the signaled value is complete.
+    pub unsafe fn resolve(&self) -> Result<HistogramResult> {
+        let partials = unsafe { self.buffer.read::<u32>() }?;
+        let mut bins = vec![0_u32; self.histograms * HISTOGRAM_BINS * 4];
+        for partial in partials.chunks_exact(bins.len()) {
+            for (bin, count) in bins.iter_mut().zip(partial) {
+                *bin += count;
+            }
+        }
+
+        let histogram = |index: usize| -> Result<[u32; HISTOGRAM_BINS * 4]> {
+            let len = HISTOGRAM_BINS * 4;
+            Ok(bins[index * len..(index + 1) * len].try_into()?)
+        };
+        Ok(HistogramResult {
+            a: histogram(0)?,
+            b: histogram(1)?,
+            difference: (self.histograms > 2).then(|| histogram(2)).transpose()?,
+        })
+    }
+}
+
+impl RenderTargetComparator {
+    /// Records computing the SSIM of the first two inputs of a frame in flight into the
+    /// command buffer, outside of a render pass. Requires `memory_properties` to have been
+    /// provided at creation.
+    ///
+    /// The SSIM is computed on the luma of the sRGB encoded inputs, over an 11x11 Gaussian
+    /// window, and averaged over the pixels both inputs cover. Unlike the comparison, it
+    /// ignores color transforms and lookup tables.
+    ///
+    /// # Safety
+    ///
+    /// The input images must be in `SHADER_READ_ONLY_OPTIMAL` layout when the command buffer
+    /// executes, and the returned query must outlive its execution.
+    pub unsafe fn compute_ssim(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        frame_index: usize,
+    ) -> Result<SsimQuery> {
+        self.check_unfiltered_reads("The SSIM")?;
+        self.check_float_reads("The SSIM")?;
+        self.check_descriptor_sets("The SSIM")?;
+        let buffer = self.create_query_buffer(std::mem::size_of::<SsimPartial>())?;
+        unsafe {
+            self.cmd_begin_timing(command_buffer, TimedPass::Metrics, frame_index);
+            buffer.cmd_dispatch(
+                command_buffer,
+                self.quality.ssim_pipeline_layout,
+                self.quality.ssim_pipeline,
+                self.frame(frame_index).descriptor_set,
+                bytemuck::bytes_of(&self.metrics_push_constants()),
+            );
+            self.cmd_end_timing(command_buffer, TimedPass::Metrics, frame_index);
+        }
+
+        Ok(SsimQuery { buffer })
+    }
+
+    /// Records computing the mean squared error and largest difference per channel of the
+    /// first two inputs of a frame in flight into the command buffer, outside of a render
+    /// pass. Requires `memory_properties` to have been provided at creation.
+    ///
+    /// The inputs are compared like by the metrics of batch comparisons, over the pixels both
+    /// inputs cover. The dispatch binds the same input descriptor set as the comparison, so
+    /// it may be recorded into the same command buffer as [`compare`](Self::compare).
+    ///
+    /// # Safety
+    ///
+    /// The input images must be in `SHADER_READ_ONLY_OPTIMAL` layout when the command buffer
+    /// executes, and the returned query must outlive its execution.
+    pub unsafe fn compute_metrics(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        frame_index: usize,
+    ) -> Result<MetricsQuery> {
+        self.check_unfiltered_reads("Computing metrics")?;
+        self.check_float_reads("Computing metrics")?;
+        self.check_descriptor_sets("Computing metrics")?;
+        let buffer = self.create_query_buffer(std::mem::size_of::<MetricsPartial>())?;
+        unsafe {
+            self.cmd_begin_timing(command_buffer, TimedPass::Metrics, frame_index);
+            buffer.cmd_dispatch(
+                command_buffer,
+                self.quality.metrics_pipeline_layout,
+                self.quality.metrics_pipeline,
+                self.frame(frame_index).descriptor_set,
+                bytemuck::bytes_of(&self.metrics_push_constants()),
+            );
+            self.cmd_end_timing(command_buffer, TimedPass::Metrics, frame_index);
+        }
+
+        Ok(MetricsQuery { buffer })
+    }
+
+    /// Records computing the per-channel histograms of the first two inputs of a frame in
+    /// flight, and of their difference if `difference` is set, into the command buffer,
+    /// outside of a render pass. Requires `memory_properties` to have been provided at
+    /// creation.
+    ///
+    /// Like the other metrics, the histograms ignore color transforms and lookup tables.
+    ///
+    /// # Safety
+    ///
+    /// The input images must be in `SHADER_READ_ONLY_OPTIMAL` layout when the command buffer
+    /// executes, and the returned query must outlive its execution.
+    pub unsafe fn compute_histograms(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        frame_index: usize,
+        difference: bool,
+    ) -> Result<HistogramQuery> {
+        self.check_unfiltered_reads("Computing histograms")?;
+        self.check_float_reads("Computing histograms")?;
+        self.check_descriptor_sets("Computing histograms")?;
+        let histograms = if difference { 3 } else { 2 };
+        let buffer =
+            self.create_query_buffer(histograms * HISTOGRAM_BINS * 4 * std::mem::size_of::<u32>())?;
+        let push_buffer = HistogramPushConstantBuffer {
+            srgb_inputs: self.srgb_inputs,
+            histograms: histograms as u32,
+        };
+        unsafe {
+            self.cmd_begin_timing(command_buffer, TimedPass::Metrics, frame_index);
+            buffer.cmd_dispatch(
+                command_buffer,
+                self.quality.histogram_pipeline_layout,
+                self.quality.histogram_pipeline,
+                self.frame(frame_index).descriptor_set,
+                bytemuck::bytes_of(&push_buffer),
+            );
+            self.cmd_end_timing(command_buffer, TimedPass::Metrics, frame_index);
+        }
+
+        Ok(HistogramQuery { buffer, histograms })
+    }
+
+    fn metrics_push_constants(&self) -> MetricsPushConstantBuffer {
+        MetricsPushConstantBuffer {
+            first_partial: 0,
+            srgb_inputs: self.srgb_inputs,
+        }
+    }
+
+    /// Creates a buffer for the partial results of a metric, of `partial_size` bytes each.
+    fn create_query_buffer(&self, partial_size: usize) -> Result<QueryBuffer> {
+        let memory_properties = self.memory_properties.as_ref().ok_or_else(|| {
+            anyhow!("Quality metrics require memory_properties to be set at creation.")
+        })?;
+
+        QueryBuffer::new(
+            &self.device,
+            memory_properties,
+            self.quality.descriptor_set_layout,
+            (PARTIALS * partial_size) as vk::DeviceSize,
+            self.metrics_timeline.clone(),
+        )
+    }
+}
@@ -0,0 +1,308 @@
+//! Scrubbing through two registered frame sequences, the core of a video A/B scrubber.
+//!
+//! The frames are either registered upfront as two lists of image views, or supplied on
+//! demand by a provider callback (e.g. backed by a decoder). Registered lists get one
+//! descriptor set per frame written once, so scrubbing only selects a set. Provider frames
+//! are written into a small ring of descriptor sets, and only when the frame changes.
+//!
+//! Besides scrubbing to arbitrary positions, a [`SequenceComparator`] steps through all frames
+//! in order, e.g. to review two encodes of a clip frame by frame.
+
+use anyhow::{Result, anyhow};
+use std::fmt;
+use std::sync::{Arc, Mutex, PoisonError};
+use vulkanalia::prelude::v1_3::*;
+
+use crate::vulkan::descriptors::{
+    MAX_INPUTS, create_descriptor_pool, create_input_descriptor_set, input_bindings,
+    update_descriptor_sets,
+};
+use crate::{CompareInfo, InputBinding, RenderTargetComparator, descriptors_per_sampler};
+
+/// The amount of descriptor sets cycled through for provider frames. At most this many
+/// scrubs to distinct frames may be pending execution at once.
+pub const PROVIDER_DESCRIPTOR_SETS: usize = 3;
+
+/// A callback returning the two input image views of the given frame index.
+pub type FrameProvider = Box<dyn FnMut(usize) -> [vk::ImageView; 2] + Send>;
+
+/// The frame sequences registered with a comparator, and the descriptors sampling them.
+pub(crate) struct Sequences {
+    device: Arc<Device>,
+    descriptor_pool: vk::DescriptorPool,
+    descriptor_sets: Vec<vk::DescriptorSet>,
+    frame_count: usize,
+    // Only ever called through `&mut`, the mutex merely keeps the comparator `Sync` without
+    // requiring the provider to be.
+    provider: Option<Mutex<FrameProvider>>,
+    // The last scrubbed frame and the index of the descriptor set it was written into.
+    current: Option<(usize, usize)>,
+}
+
+impl fmt::Debug for Sequences {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sequences")
+            .field("descriptor_pool", &self.descriptor_pool)
+            .field("descriptor_sets", &self.descriptor_sets)
+            .field("frame_count", &self.frame_count)
+            .field("provider", &self.provider.is_some())
+            .field("current", &self.current)
+            .finish()
+    }
+}
+
+impl Drop for Sequences {
+    fn drop(&mut self) {
+        unsafe {
+            self.device
+                .destroy_descriptor_pool(self.descriptor_pool, None);
+        }
+    }
+}
+
+impl Sequences {
+    fn new(comparator: &RenderTargetComparator, set_count: usize) -> Result<Self> {
+        comparator.check_descriptor_sets("Scrubbing")?;
+        let device = &comparator.device;
+        let count = set_count as u32;
+        let descriptors_per_set =
+            MAX_INPUTS as u32 * descriptors_per_sampler(comparator.ycbcr_conversion.as_deref());
+        let descriptor_pool =
+            create_descriptor_pool(device, count, count * descriptors_per_set, 0, 0)?;
+
+        let mut sequences = Self {
+            device: Arc::clone(device),
+            descriptor_pool,
+            descriptor_sets: Vec::with_capacity(set_count),
+            frame_count: 0,
+            provider: None,
+            current: None,
+        };
+
+        for _ in 0..set_count {
+            sequences.descriptor_sets.push(create_input_descriptor_set(
+                device,
+                &descriptor_pool,
+                &comparator.descriptor_set_layout,
+                MAX_INPUTS as u32,
+            )?);
+        }
+
+        Ok(sequences)
+    }
+}
+
+impl RenderTargetComparator {
+    /// Registers two equally long sequences of input image views for scrubbing, replacing
+    /// any previously registered sequences or provider.
+    ///
+    /// # Safety
+    ///
+    /// No command buffer recorded with [`scrub`](Self::scrub) may be pending execution, as the
+    /// descriptors of the previous registration are destroyed.
+    pub unsafe fn register_sequences(
+        &mut self,
+        left: &[vk::ImageView],
+        right: &[vk::ImageView],
+    ) -> Result<()> {
+        if left.len() != right.len() {
+            return Err(anyhow!(
+                "The sequences must have the same length, got {} and {} frames.",
+                left.len(),
+                right.len()
+            ));
+        }
+        if left.is_empty() {
+            return Err(anyhow!("The sequences must contain at least one frame."));
+        }
+
+        self.sequences = None;
+        let mut sequences = Sequences::new(self, left.len())?;
+        for (descriptor_set, image_views) in
+            sequences.descriptor_sets.iter().zip(left.iter().zip(right))
+        {
+            update_descriptor_sets(
+                &self.device,
+                descriptor_set,
+                &self.sampler,
+                &input_bindings(&[*image_views.0, *image_views.1]),
+            );
+        }
+        sequences.frame_count = left.len();
+        self.sequences = Some(sequences);
+
+        Ok(())
+    }
+
+    /// Registers a callback supplying the input image views of each of `frame_count` frames
+    /// for scrubbing, replacing any previously registered sequences or provider.
+    ///
+    /// The provider is called whenever [`scrub`](Self::scrub) lands on a different frame than
+    /// the previous call.
+    ///
+    /// # Safety
+    ///
+    /// No command buffer recorded with [`scrub`](Self::scrub) may be pending execution, as the
+    /// descriptors of the previous registration are destroyed.
+    pub unsafe fn register_frame_provider<F>(
+        &mut self,
+        frame_count: usize,
+        provider: F,
+    ) -> Result<()>
+    where
+        F: FnMut(usize) -> [vk::ImageView; 2] + Send + 'static,
+    {
+        if frame_count == 0 {
+            return Err(anyhow!("The provider must supply at least one frame."));
+        }
+
+        self.sequences = None;
+        let mut sequences = Sequences::new(self, PROVIDER_DESCRIPTOR_SETS)?;
+        sequences.frame_count = frame_count;
+        sequences.provider = Some(Mutex::new(Box::new(provider)));
+        self.sequences = Some(sequences);
+
+        Ok(())
+    }
+
+    /// Returns the frame index corresponding to the scrub position `t` in `[0.0, 1.0]`,
+    /// or `None` if no sequences are registered.
+    pub fn frame_index(&self, t: f32) -> Option<usize> {
+        let frame_count = self.sequences.as_ref()?.frame_count;
+        let last = (frame_count - 1) as f32;
+        Some((t.clamp(0.0, 1.0) * last).round() as usize)
+    }
+
+    /// Records the comparison of the frame pair at scrub position `t` in `[0.0, 1.0]` into
+    /// the provided command buffer, and returns the index of that frame.
+    ///
+    /// # Safety
+    ///
+    /// The same requirements as for [`compare`](Self::compare) apply. When a provider is
+    /// registered, at most [`PROVIDER_DESCRIPTOR_SETS`] scrubs landing on distinct frames may
+    /// be pending execution, as older descriptor sets are rewritten.
+    pub unsafe fn scrub(&mut self, t: f32, info: &CompareInfo) -> Result<usize> {
+        let frame = self
+            .frame_index(t)
+            .ok_or_else(|| anyhow!("No sequences are registered for scrubbing."))?;
+        unsafe { self.compare_frame(frame, info) }?;
+        Ok(frame)
+    }
+
+    /// Returns a driver stepping through the frames of the registered sequences in order,
+    /// starting at the first.
+    pub fn sequence_comparator(&mut self) -> Result<SequenceComparator<'_>> {
+        let frame_count = self
+            .sequences
+            .as_ref()
+            .ok_or_else(|| anyhow!("No sequences are registered for comparison."))?
+            .frame_count;
+        Ok(SequenceComparator {
+            comparator: self,
+            frame_count,
+            next_frame: 0,
+            divider_positions: &[],
+        })
+    }
+
+    // Records the comparison of a frame pair of the registered sequences.
+    unsafe fn compare_frame(&mut self, frame: usize, info: &CompareInfo) -> Result<()> {
+        if info.input_base != 0 {
+            return Err(anyhow!(
+                "Scrubbing binds the frames of the sequences, so it takes no input base."
+            ));
+        }
+        let sequences = self
+            .sequences
+            .as_mut()
+            .ok_or_else(|| anyhow!("No sequences are registered for scrubbing."))?;
+        let set_index = match (&mut sequences.provider, sequences.current) {
+            (None, _) => frame,
+            (Some(_), Some((current, set_index))) if current == frame => set_index,
+            (Some(provider), current) => {
+                let provider = provider.get_mut().unwrap_or_else(PoisonError::into_inner);
+                let set_index = current.map_or(0, |(_, i)| (i + 1) % PROVIDER_DESCRIPTOR_SETS);
+                update_descriptor_sets(
+                    &self.device,
+                    &sequences.descriptor_sets[set_index],
+                    &self.sampler,
+                    &input_bindings(&provider(frame)),
+                );
+                set_index
+            }
+        };
+        sequences.current = Some((frame, set_index));
+
+        let descriptor_set = sequences.descriptor_sets[set_index];
+        unsafe { self.cmd_compare(info, InputBinding::Set(descriptor_set), 2) }
+    }
+}
+
+/// Steps through the frames of the sequences registered with a comparator, recording one
+/// comparison per frame into the command buffers the caller supplies, see
+/// [`RenderTargetComparator::sequence_comparator`].
+#[derive(Debug)]
+pub struct SequenceComparator<'a> {
+    comparator: &'a mut RenderTargetComparator,
+    frame_count: usize,
+    next_frame: usize,
+    divider_positions: &'a [f32],
+}
+
+impl<'a> SequenceComparator<'a> {
+    /// Sets the divider position of each frame, by frame index. Frames past the end keep the
+    /// position of the [`CompareInfo`] they are recorded with.
+    pub fn with_divider_positions(mut self, divider_positions: &'a [f32]) -> Self {
+        self.divider_positions = divider_positions;
+        self
+    }
+
+    /// Returns the amount of frames in the sequences.
+    pub fn frame_count(&self) -> usize {
+        self.frame_count
+    }
+
+    /// Returns the index of the frame recorded next, or `None` if all have been recorded.
+    pub fn next_frame(&self) -> Option<usize> {
+        (self.next_frame < self.frame_count).then_some(self.next_frame)
+    }
+
+    /// Continues at the given frame, e.g. to step back or to replay the sequences.
+    pub fn seek(&mut self, frame: usize) -> Result<()> {
+        if frame >= self.frame_count {
+            return Err(anyhow!(
+                "Expected a frame index below {}, got {}.",
+                self.frame_count,
+                frame
+            ));
+        }
+        self.next_frame = frame;
+        Ok(())
+    }
+
+    /// Records the comparison of the next frame into the command buffer of `info`, with the
+    /// frame's divider position, and advances to the following frame. Returns the index of
+    /// the recorded frame, or `None` without recording anything past the last one.
+    ///
+    /// # Safety
+    ///
+    /// See [`RenderTargetComparator::scrub`]. With a frame provider, at most
+    /// [`PROVIDER_DESCRIPTOR_SETS`] of the recorded command buffers may be pending execution
+    /// at once.
+    pub unsafe fn record_next(&mut self, info: &CompareInfo) -> Result<Option<usize>> {
+        let Some(frame) = self.next_frame() else {
+            return Ok(None);
+        };
+        let info = CompareInfo {
+            divider_position: self
+                .divider_positions
+                .get(frame)
+                .copied()
+                .unwrap_or(info.divider_position),
+            ..*info
+        };
+        unsafe { self.comparator.compare_frame(frame, &info) }?;
+        self.next_frame += 1;
+        Ok(Some(frame))
+    }
+}
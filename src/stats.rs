@@ -0,0 +1,187 @@
+//! Basic difference statistics gathered by the comparison pass itself.
+//!
+//! A comparator created with `stats` enabled uses a fragment shader variant that atomically
+//! accumulates the amount of differing pixels and the largest difference into a small
+//! storage buffer, so no separate compute dispatch is needed for a quick verdict.
+
+use anyhow::{Result, anyhow};
+use std::sync::Arc;
+use vulkanalia::prelude::v1_3::*;
+
+use crate::RenderTargetComparator;
+use crate::vulkan::{
+    descriptors::{
+        create_descriptor_pool, create_descriptor_set, create_stats_descriptor_set_layout,
+        update_storage_buffer_descriptor,
+    },
+    memory::create_buffer,
+};
+
+// Mirrors the `Stats` buffer of the fragment shader (std430 layout).
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct StatsBuffer {
+    above_threshold: u32,
+    // The bits of a non-negative float, which order like the float itself.
+    max_difference: u32,
+    non_finite: u32,
+    mismatches: u32,
+}
+
+/// Statistics of the first two inputs, gathered during the last comparison.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CompareStats {
+    /// The amount of shaded pixels whose largest channel difference exceeds
+    /// [`CompareInfo::stats_threshold`](crate::CompareInfo::stats_threshold).
+    pub pixels_above_threshold: u32,
+    /// The largest absolute channel difference of any shaded pixel.
+    pub max_difference: f32,
+    /// The amount of shaded pixels where either input holds a NaN or infinite value.
+    /// These pixels count towards neither of the other statistics.
+    pub non_finite_pixels: u32,
+    /// The amount of shaded pixels whose texels differ in any bit, counted in
+    /// [`CompareMode::Exact`](crate::CompareMode::Exact) only.
+    pub mismatched_pixels: u32,
+}
+
+/// The storage buffer the statistics are accumulated into, and its descriptor.
+#[derive(Debug)]
+pub(crate) struct StatsResources {
+    device: Arc<Device>,
+    pub(crate) descriptor_set_layout: vk::DescriptorSetLayout,
+    descriptor_pool: vk::DescriptorPool,
+    pub(crate) descriptor_set: vk::DescriptorSet,
+    buffer: vk::Buffer,
+    memory: vk::DeviceMemory,
+}
+
+impl Drop for StatsResources {
+    fn drop(&mut self) {
+        unsafe {
+            self.device.destroy_buffer(self.buffer, None);
+            self.device.free_memory(self.memory, None);
+            self.device
+                .destroy_descriptor_pool(self.descriptor_pool, None);
+            self.device
+                .destroy_descriptor_set_layout(self.descriptor_set_layout, None);
+        }
+    }
+}
+
+impl StatsResources {
+    pub(crate) fn new(
+        device: &Arc<Device>,
+        memory_properties: &vk::PhysicalDeviceMemoryProperties,
+    ) -> Result<Self> {
+        let descriptor_set_layout = create_stats_descriptor_set_layout(device)?;
+        let (buffer, memory) = create_buffer(
+            device,
+            memory_properties,
+            std::mem::size_of::<StatsBuffer>() as vk::DeviceSize,
+            vk::BufferUsageFlags::STORAGE_BUFFER | vk::BufferUsageFlags::TRANSFER_DST,
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+        )?;
+
+        // Owned right away, so that everything created so far is released on error.
+        let mut stats = Self {
+            device: Arc::clone(device),
+            descriptor_set_layout,
+            descriptor_pool: vk::DescriptorPool::null(),
+            descriptor_set: vk::DescriptorSet::null(),
+            buffer,
+            memory,
+        };
+
+        stats.descriptor_pool = create_descriptor_pool(device, 1, 0, 1, 0)?;
+        stats.descriptor_set =
+            create_descriptor_set(device, &stats.descriptor_pool, &descriptor_set_layout)?;
+        update_storage_buffer_descriptor(device, &stats.descriptor_set, 0, &buffer);
+
+        Ok(stats)
+    }
+
+    /// Records resetting the counters, ahead of the render pass accumulating into them.
+    pub(crate) unsafe fn cmd_reset(&self, command_buffer: vk::CommandBuffer) {
+        let barrier = vk::BufferMemoryBarrier::builder()
+            .src_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+            .dst_access_mask(vk::AccessFlags::SHADER_READ | vk::AccessFlags::SHADER_WRITE)
+            .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .buffer(self.buffer)
+            .offset(0)
+            .size(vk::WHOLE_SIZE)
+            .build();
+
+        unsafe {
+            self.device
+                .cmd_fill_buffer(command_buffer, self.buffer, 0, vk::WHOLE_SIZE, 0);
+            self.device.cmd_pipeline_barrier(
+                command_buffer,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::FRAGMENT_SHADER,
+                vk::DependencyFlags::empty(),
+                &[] as &[vk::MemoryBarrier],
+                &[barrier],
+                &[] as &[vk::ImageMemoryBarrier],
+            );
+        }
+    }
+
+    /// Records making the accumulated counters visible to the host.
+    pub(crate) unsafe fn cmd_finish(&self, command_buffer: vk::CommandBuffer) {
+        let barrier = vk::BufferMemoryBarrier::builder()
+            .src_access_mask(vk::AccessFlags::SHADER_WRITE)
+            .dst_access_mask(vk::AccessFlags::HOST_READ)
+            .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .buffer(self.buffer)
+            .offset(0)
+            .size(vk::WHOLE_SIZE)
+            .build();
+
+        unsafe {
+            self.device.cmd_pipeline_barrier(
+                command_buffer,
+                vk::PipelineStageFlags::FRAGMENT_SHADER,
+                vk::PipelineStageFlags::HOST,
+                vk::DependencyFlags::empty(),
+                &[] as &[vk::MemoryBarrier],
+                &[barrier],
+                &[] as &[vk::ImageMemoryBarrier],
+            );
+        }
+    }
+}
+
+impl RenderTargetComparator {
+    /// Reads back the statistics gathered by the last recorded comparison.
+    ///
+    /// # Safety
+    ///
+    /// The command buffer the comparison was recorded into must have finished executing.
+    pub unsafe fn stats(&self) -> Result<CompareStats> {
+        let stats = self
+            .stats
+            .as_ref()
+            .ok_or_else(|| anyhow!("The comparator was not created with statistics enabled."))?;
+
+        let buffer = unsafe {
+            let memory = self.device.map_memory(
+                stats.memory,
+                0,
+                std::mem::size_of::<StatsBuffer>() as vk::DeviceSize,
+                vk::MemoryMapFlags::empty(),
+            )?;
+            let buffer = std::ptr::read_unaligned(memory as *const StatsBuffer);
+            self.device.unmap_memory(stats.memory);
+            buffer
+        };
+
+        Ok(CompareStats {
+            pixels_above_threshold: buffer.above_threshold,
+            max_difference: f32::from_bits(buffer.max_difference),
+            non_finite_pixels: buffer.non_finite,
+            mismatched_pixels: buffer.mismatches,
+        })
+    }
+}
@@ -0,0 +1,108 @@
+//! Consistent colors for all decorations of a comparison.
+//!
+//! Every decoration takes its own color, which makes it easy to end up with a divider that
+//! disappears against one set of inputs and highlights that clash with another. A [`Theme`]
+//! bundles them, and applying it to the per-comparison info styles everything at once.
+//! With the `serde` feature, themes can be stored alongside other user settings.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::thumbnails::ThumbnailStripInfoBuilder;
+use crate::{Background, Color, CompareInfoBuilder};
+
+/// The colors and sizes of the decorations drawn over and around the compared inputs.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Theme {
+    /// The color of the divider lines, and of the thumbnail strip's playhead marker.
+    pub divider_color: Color,
+    /// The width of the divider lines in pixels.
+    pub divider_width: u8,
+    /// The border color of the highlighted grid cell, and of differing pixels in
+    /// [`CompareMode::Exact`](crate::CompareMode::Exact) and
+    /// [`CompareMode::Threshold`](crate::CompareMode::Threshold).
+    pub highlight_color: Color,
+    /// The color of highlighted NaN and infinite texels.
+    pub non_finite_color: Color,
+    /// What is shown outside the inputs.
+    pub background: Background,
+    /// The color of the vector difference arrows.
+    pub vector_color: Color,
+    /// The color of the divider handle.
+    pub handle_color: Color,
+    /// The color of the debug label in GPU captures. All zeros leave it to the tool.
+    pub label_color: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::DARK
+    }
+}
+
+impl Theme {
+    /// Light decorations, for mostly dark inputs such as renders of night scenes.
+    pub const DARK: Self = Self {
+        divider_color: Color(0.9, 0.9, 0.9, 1.0),
+        divider_width: 4,
+        highlight_color: Color(1.0, 0.8, 0.0, 1.0),
+        non_finite_color: Color(1.0, 0.0, 1.0, 1.0),
+        background: Background::Hatched(Color(0.3, 0.3, 0.3, 1.0)),
+        vector_color: Color(0.0, 1.0, 0.0, 1.0),
+        handle_color: Color(0.9, 0.9, 0.9, 1.0),
+        label_color: Color(0.2, 0.4, 0.8, 1.0),
+    };
+
+    /// Dark decorations, for mostly bright inputs such as documents or UI captures.
+    pub const LIGHT: Self = Self {
+        divider_color: Color(0.1, 0.1, 0.1, 1.0),
+        divider_width: 4,
+        highlight_color: Color(0.9, 0.3, 0.0, 1.0),
+        non_finite_color: Color(0.8, 0.0, 0.8, 1.0),
+        background: Background::Hatched(Color(0.85, 0.85, 0.85, 1.0)),
+        vector_color: Color(0.0, 0.5, 0.0, 1.0),
+        handle_color: Color(0.1, 0.1, 0.1, 1.0),
+        label_color: Color(0.8, 0.6, 0.2, 1.0),
+    };
+
+    /// Wide, saturated decorations that stand out against any input.
+    pub const HIGH_CONTRAST: Self = Self {
+        divider_color: Color(1.0, 1.0, 0.0, 1.0),
+        divider_width: 6,
+        highlight_color: Color(1.0, 0.0, 0.0, 1.0),
+        non_finite_color: Color(0.0, 1.0, 1.0, 1.0),
+        background: Background::Solid(Color(0.0, 0.0, 0.0, 1.0)),
+        vector_color: Color(1.0, 0.0, 1.0, 1.0),
+        handle_color: Color(1.0, 1.0, 0.0, 1.0),
+        label_color: Color(1.0, 1.0, 0.0, 1.0),
+    };
+}
+
+impl CompareInfoBuilder<'_> {
+    /// Styles all decorations with the theme, like setting each of them. Decorations set
+    /// afterwards override the theme, and a vector overlay and divider handle set before
+    /// take their colors.
+    pub fn theme(&mut self, theme: &Theme) -> &mut Self {
+        if let Some(Some(overlay)) = &mut self.vector_overlay {
+            overlay.color = theme.vector_color;
+        }
+        if let Some(Some(handle)) = &mut self.divider_handle {
+            handle.color = Some(theme.handle_color);
+        }
+
+        self.divider_color(theme.divider_color)
+            .divider_width(theme.divider_width)
+            .highlight_color(theme.highlight_color)
+            .non_finite_color(theme.non_finite_color)
+            .background(theme.background)
+            .label_color(theme.label_color)
+    }
+}
+
+impl ThumbnailStripInfoBuilder {
+    /// Styles the playhead marker with the theme's divider color.
+    pub fn theme(&mut self, theme: &Theme) -> &mut Self {
+        self.marker_color(theme.divider_color)
+    }
+}
@@ -0,0 +1,310 @@
+//! A timeline of difference thumbnails for comparing two frame sequences.
+//!
+//! Every frame pair of a sequence is rendered as a small `abs(A - B)` thumbnail, laid out
+//! left to right in a single strip image, with a playhead marker on top. Bright cells show
+//! at a glance where two encodes of a clip diverge the most.
+
+use anyhow::{Result, anyhow};
+use derive_builder::Builder;
+use std::sync::Arc;
+use vulkanalia::prelude::v1_3::*;
+
+use crate::vulkan::{
+    descriptors::{
+        MAX_INPUTS, create_descriptor_pool, create_descriptor_set, create_descriptor_set_layout,
+        input_bindings, update_descriptor_sets,
+    },
+    pipeline::create_strip_pipeline,
+    push_constants::StripPushConstantBuffer,
+    render_pass::{create_framebuffer, create_render_pass},
+    sampler::create_image_sampler,
+};
+use crate::{Color, SamplerConfig};
+
+/// Configuration for the thumbnail strip.
+#[derive(Builder, Clone, Debug)]
+#[builder(setter(into), build_fn(name = "build"))]
+pub struct ThumbnailStripCreateInfo {
+    /// The Vulkan logical device.
+    pub device: Arc<Device>,
+    /// The format of the strip image.
+    pub format: vk::Format,
+    /// The extent (width and height) of the strip image.
+    pub extent: vk::Extent2D,
+    /// The strip image view to render into.
+    pub out_image_view: vk::ImageView,
+    /// The final layout of the strip image after rendering.
+    #[builder(default = "vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL")]
+    pub final_layout: vk::ImageLayout,
+    /// The maximum amount of frames the strip can show.
+    pub max_frames: u32,
+}
+
+impl ThumbnailStripCreateInfo {
+    pub fn builder() -> ThumbnailStripCreateInfoBuilder {
+        ThumbnailStripCreateInfoBuilder::default()
+    }
+}
+
+/// Configuration for a single recording of the thumbnail strip.
+#[derive(Builder, Clone, Copy, Debug)]
+#[builder(setter(into))]
+pub struct ThumbnailStripInfo {
+    /// The command buffer to record drawing commands into.
+    #[builder(default)]
+    pub command_buffer: vk::CommandBuffer,
+    /// The position of the playhead along the strip, in the range `[0.0, 1.0]`.
+    #[builder(default = "0.0_f32")]
+    pub playhead: f32,
+    /// The width of the playhead marker in pixels.
+    #[builder(default = "2_u8")]
+    pub marker_width: u8,
+    /// The color of the playhead marker.
+    #[builder(default = "Color(1.0, 0.0, 0.0, 1.0)")]
+    pub marker_color: Color,
+    /// The factor the differences are multiplied by, to make subtle ones visible.
+    #[builder(default = "1.0_f32")]
+    pub gain: f32,
+}
+
+impl ThumbnailStripInfo {
+    pub fn builder() -> ThumbnailStripInfoBuilder {
+        ThumbnailStripInfoBuilder::default()
+    }
+}
+
+/// Renders the difference thumbnails of two frame sequences into a strip image.
+#[derive(Debug)]
+pub struct ThumbnailStrip {
+    device: Arc<Device>,
+    render_pass: vk::RenderPass,
+    descriptor_set_layout: vk::DescriptorSetLayout,
+    descriptor_pool: vk::DescriptorPool,
+    descriptor_sets: Vec<vk::DescriptorSet>,
+    pipeline_layout: vk::PipelineLayout,
+    pipeline: vk::Pipeline,
+    extent: vk::Extent2D,
+    sampler: vk::Sampler,
+    framebuffer: vk::Framebuffer,
+    frame_count: usize,
+}
+
+impl Drop for ThumbnailStrip {
+    fn drop(&mut self) {
+        unsafe {
+            self.device.destroy_sampler(self.sampler, None);
+            self.device.destroy_framebuffer(self.framebuffer, None);
+            self.device.destroy_pipeline(self.pipeline, None);
+            self.device
+                .destroy_pipeline_layout(self.pipeline_layout, None);
+            self.device
+                .destroy_descriptor_pool(self.descriptor_pool, None);
+            self.device
+                .destroy_descriptor_set_layout(self.descriptor_set_layout, None);
+            self.device.destroy_render_pass(self.render_pass, None);
+        }
+    }
+}
+
+impl ThumbnailStrip {
+    /// Creates a new `ThumbnailStrip`. Allocates resources for `max_frames` frames upfront,
+    /// destroys them when dropped.
+    pub fn new(info: &ThumbnailStripCreateInfo) -> Result<Self> {
+        if info.max_frames == 0 {
+            return Err(anyhow!(
+                "A thumbnail strip needs room for at least one frame."
+            ));
+        }
+
+        let device = &info.device;
+        let render_pass = create_render_pass(
+            device,
+            info.format,
+            info.final_layout,
+            vk::AttachmentLoadOp::CLEAR,
+            0,
+        )?;
+        let descriptor_set_layout = create_descriptor_set_layout(device, None, false, None)?;
+
+        let (pipeline_layout, pipeline) = create_strip_pipeline(
+            device,
+            vk::PipelineCache::null(),
+            &render_pass,
+            &[descriptor_set_layout],
+        )?;
+
+        let sampler = create_image_sampler(device, &SamplerConfig::default())?;
+
+        let framebuffer =
+            create_framebuffer(device, render_pass, info.out_image_view, info.extent)?;
+
+        // The strip owns its descriptors, one set per frame.
+        let descriptor_pool = create_descriptor_pool(
+            device,
+            info.max_frames,
+            info.max_frames * MAX_INPUTS as u32,
+            0,
+            0,
+        )?;
+        let descriptor_sets = (0..info.max_frames)
+            .map(|_| create_descriptor_set(device, &descriptor_pool, &descriptor_set_layout))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            device: Arc::clone(device),
+            render_pass,
+            descriptor_set_layout,
+            descriptor_pool,
+            descriptor_sets,
+            pipeline_layout,
+            pipeline,
+            extent: info.extent,
+            sampler,
+            framebuffer,
+            frame_count: 0,
+        })
+    }
+
+    /// Sets the frame pairs shown in the strip, in timeline order.
+    ///
+    /// # Safety
+    ///
+    /// No command buffer recorded with this strip may be pending execution, as the
+    /// descriptor sets are rewritten in place.
+    pub unsafe fn set_frames(&mut self, frames: &[[vk::ImageView; 2]]) -> Result<()> {
+        if frames.len() > self.descriptor_sets.len() {
+            return Err(anyhow!(
+                "The strip holds at most {} frames, got {}.",
+                self.descriptor_sets.len(),
+                frames.len()
+            ));
+        }
+
+        for (descriptor_set, image_views) in self.descriptor_sets.iter().zip(frames) {
+            update_descriptor_sets(
+                &self.device,
+                descriptor_set,
+                &self.sampler,
+                &input_bindings(image_views),
+            );
+        }
+        self.frame_count = frames.len();
+
+        Ok(())
+    }
+
+    /// Returns the playhead position at the center of the given frame's thumbnail.
+    pub fn frame_position(&self, frame: usize) -> f32 {
+        (frame as f32 + 0.5) / self.frame_count.max(1) as f32
+    }
+
+    /// Records the drawing commands of the strip into the provided command buffer.
+    ///
+    /// # Safety
+    ///
+    /// The frame images must be in `SHADER_READ_ONLY_OPTIMAL` layout when the command buffer
+    /// executes.
+    pub unsafe fn record(&self, info: &ThumbnailStripInfo) -> Result<()> {
+        let render_area = vk::Rect2D::builder()
+            .offset(vk::Offset2D::default())
+            .extent(self.extent)
+            .build();
+
+        let color_clear_value = vk::ClearValue {
+            color: vk::ClearColorValue {
+                float32: [0.0, 0.0, 0.0, 1.0],
+            },
+        };
+
+        let clear_values = &[color_clear_value];
+        let begin_info = vk::RenderPassBeginInfo::builder()
+            .render_pass(self.render_pass)
+            .framebuffer(self.framebuffer)
+            .render_area(render_area)
+            .clear_values(clear_values)
+            .build();
+
+        let command_buffer = info.command_buffer;
+        let width = self.extent.width as u64;
+        let count = self.frame_count as u64;
+
+        unsafe {
+            self.device.cmd_begin_render_pass(
+                command_buffer,
+                &begin_info,
+                vk::SubpassContents::INLINE,
+            );
+
+            self.device.cmd_bind_pipeline(
+                command_buffer,
+                vk::PipelineBindPoint::GRAPHICS,
+                self.pipeline,
+            );
+
+            for (i, descriptor_set) in self.descriptor_sets[..self.frame_count].iter().enumerate() {
+                // Whole pixel columns per cell, so that neighbouring cells don't overlap. The
+                // products are taken in 64 bits, so that they can't overflow.
+                let start = (i as u64 * width / count) as u32;
+                let end = ((i as u64 + 1) * width / count) as u32;
+                if start == end {
+                    continue;
+                }
+
+                let viewport = vk::Viewport::builder()
+                    .x(start as f32)
+                    .y(0.0)
+                    .width((end - start) as f32)
+                    .height(self.extent.height as f32)
+                    .min_depth(0.0)
+                    .max_depth(1.0)
+                    .build();
+
+                let scissor = vk::Rect2D::builder()
+                    .offset(vk::Offset2D {
+                        x: start as i32,
+                        y: 0,
+                    })
+                    .extent(vk::Extent2D {
+                        width: end - start,
+                        height: self.extent.height,
+                    })
+                    .build();
+
+                self.device.cmd_set_viewport(command_buffer, 0, &[viewport]);
+                self.device.cmd_set_scissor(command_buffer, 0, &[scissor]);
+
+                self.device.cmd_bind_descriptor_sets(
+                    command_buffer,
+                    vk::PipelineBindPoint::GRAPHICS,
+                    self.pipeline_layout,
+                    0,
+                    &[*descriptor_set],
+                    &[] as &[u32],
+                );
+
+                let push_buffer = StripPushConstantBuffer {
+                    marker_color: info.marker_color,
+                    gain: info.gain,
+                    playhead: info.playhead,
+                    marker_width: info.marker_width as f32 / width as f32,
+                    cell_start: start as f32 / width as f32,
+                    cell_end: end as f32 / width as f32,
+                };
+
+                self.device.cmd_push_constants(
+                    command_buffer,
+                    self.pipeline_layout,
+                    vk::ShaderStageFlags::FRAGMENT,
+                    0,
+                    bytemuck::bytes_of(&push_buffer),
+                );
+
+                self.device.cmd_draw(command_buffer, 3, 1, 0, 0);
+            }
+
+            self.device.cmd_end_render_pass(command_buffer);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,180 @@
+//! GPU timing of the comparison and metric passes, to budget the frame time they cost.
+//!
+//! A comparator created with a `timestamp_period` writes a pair of timestamps around every
+//! comparison recorded with [`RenderTargetComparator::compare`] and around every metric pass,
+//! into queries of the frame in flight. The duration of the pass recorded for a frame in
+//! flight is read back once its command buffer has finished executing. Recording changes
+//! nothing on the host, so the caller passes the frame in flight to read.
+
+use anyhow::{Result, anyhow};
+use std::sync::Arc;
+use std::time::Duration;
+use vulkanalia::prelude::v1_3::*;
+
+use crate::RenderTargetComparator;
+
+/// The passes timed separately.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum TimedPass {
+    Comparison = 0,
+    Metrics = 1,
+}
+
+const PASS_COUNT: usize = 2;
+
+/// The timestamp queries of each frame in flight, two per timed pass.
+#[derive(Debug)]
+pub(crate) struct GpuTimer {
+    device: Arc<Device>,
+    query_pool: vk::QueryPool,
+    // The nanoseconds per timestamp tick.
+    period: f32,
+    frame_count: usize,
+}
+
+impl Drop for GpuTimer {
+    fn drop(&mut self) {
+        unsafe { self.device.destroy_query_pool(self.query_pool, None) };
+    }
+}
+
+impl GpuTimer {
+    /// Creates the queries, with the period validated by the comparator upfront.
+    pub(crate) fn new(device: &Arc<Device>, period: f32, frame_count: usize) -> Result<Self> {
+        let info = vk::QueryPoolCreateInfo::builder()
+            .query_type(vk::QueryType::TIMESTAMP)
+            .query_count((frame_count * PASS_COUNT * 2) as u32)
+            .build();
+        let query_pool = unsafe { device.create_query_pool(&info, None) }?;
+
+        Ok(Self {
+            device: Arc::clone(device),
+            query_pool,
+            period,
+            frame_count,
+        })
+    }
+
+    // The first of the two queries of the pass in the frame in flight.
+    fn first_query(&self, pass: TimedPass, frame: usize) -> u32 {
+        ((frame * PASS_COUNT + pass as usize) * 2) as u32
+    }
+
+    /// Records resetting the queries of the pass and writing the start timestamp, outside of
+    /// a render pass.
+    pub(crate) unsafe fn cmd_begin(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        pass: TimedPass,
+        frame_index: usize,
+    ) {
+        let query = self.first_query(pass, frame_index % self.frame_count);
+        unsafe {
+            self.device
+                .cmd_reset_query_pool(command_buffer, self.query_pool, query, 2);
+            self.device.cmd_write_timestamp(
+                command_buffer,
+                vk::PipelineStageFlags::TOP_OF_PIPE,
+                self.query_pool,
+                query,
+            );
+        }
+    }
+
+    /// Records writing the end timestamp of the pass, once all its work has finished.
+    pub(crate) unsafe fn cmd_end(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        pass: TimedPass,
+        frame_index: usize,
+    ) {
+        let query = self.first_query(pass, frame_index % self.frame_count);
+        unsafe {
+            self.device.cmd_write_timestamp(
+                command_buffer,
+                vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                self.query_pool,
+                query + 1,
+            );
+        }
+    }
+
+    // Reads the duration of the pass recorded for the frame in flight, if its timestamps are
+    // available.
+    unsafe fn read(&self, pass: TimedPass, frame_index: usize) -> Result<Option<Duration>> {
+        let frame = frame_index % self.frame_count;
+        let mut ticks = [0_u64; 2];
+        let result = unsafe {
+            self.device.get_query_pool_results(
+                self.query_pool,
+                self.first_query(pass, frame),
+                2,
+                bytemuck::cast_slice_mut(&mut ticks),
+                std::mem::size_of::<u64>() as vk::DeviceSize,
+                vk::QueryResultFlags::_64,
+            )
+        }?;
+        if result == vk::SuccessCode::NOT_READY {
+            return Ok(None);
+        }
+
+        let nanos = ticks[1].wrapping_sub(ticks[0]) as f64 * self.period as f64;
+        Ok(Some(Duration::from_nanos(nanos as u64)))
+    }
+}
+
+impl RenderTargetComparator {
+    /// Returns the GPU time of the last comparison recorded with
+    /// [`compare`](Self::compare) for the frame in flight, from the beginning of its render
+    /// pass to the end of it, or `None` if it hasn't finished executing yet. Requires
+    /// `timestamp_period` to have been set at creation.
+    ///
+    /// # Safety
+    ///
+    /// A comparison must have been recorded for the frame in flight and submitted, and the
+    /// comparisons of the same frame in flight recorded since must not be pending execution.
+    pub unsafe fn last_gpu_time(&self, frame_index: usize) -> Result<Option<Duration>> {
+        unsafe { self.timer()?.read(TimedPass::Comparison, frame_index) }
+    }
+
+    /// Returns the GPU time of the last metric pass for the frame in flight, such as
+    /// [`compute_metrics`](Self::compute_metrics), like
+    /// [`last_gpu_time`](Self::last_gpu_time).
+    ///
+    /// # Safety
+    ///
+    /// See [`last_gpu_time`](Self::last_gpu_time), for metric passes.
+    pub unsafe fn last_metrics_gpu_time(&self, frame_index: usize) -> Result<Option<Duration>> {
+        unsafe { self.timer()?.read(TimedPass::Metrics, frame_index) }
+    }
+
+    fn timer(&self) -> Result<&GpuTimer> {
+        self.timer
+            .as_ref()
+            .ok_or_else(|| anyhow!("The comparator was not created with a timestamp period."))
+    }
+
+    // Records the start timestamp of the pass, if the comparator times passes.
+    pub(crate) unsafe fn cmd_begin_timing(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        pass: TimedPass,
+        frame_index: usize,
+    ) {
+        if let Some(timer) = &self.timer {
+            unsafe { timer.cmd_begin(command_buffer, pass, frame_index) };
+        }
+    }
+
+    // Records the end timestamp of the pass, if the comparator times passes.
+    pub(crate) unsafe fn cmd_end_timing(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        pass: TimedPass,
+        frame_index: usize,
+    ) {
+        if let Some(timer) = &self.timer {
+            unsafe { timer.cmd_end(command_buffer, pass, frame_index) };
+        }
+    }
+}
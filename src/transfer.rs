@@ -0,0 +1,362 @@
+//! Staging buffer transfers between host memory and images.
+//!
+//! The blocking helpers submit a one-shot command buffer and wait for it, which suits
+//! loading and test tooling. The `cmd_*` variants only record, so that callers can batch
+//! transfers into their own command buffers and synchronize them with their own fences. Their
+//! staging buffers must then be kept alive until the command buffer has finished executing.
+
+use anyhow::{Result, anyhow};
+use std::sync::Arc;
+use vulkanalia::prelude::v1_3::*;
+
+use crate::vulkan::{
+    commands::{
+        begin_single_time_commands, cmd_copy_buffer_to_image, cmd_copy_image_to_buffer,
+        cmd_transition_image_layout, submit_single_time_commands,
+    },
+    memory::{create_buffer, create_staging_buffer},
+};
+
+/// What transfers to and from the device need: memory to allocate from and a queue to
+/// transfer on.
+#[derive(Clone, Copy, Debug)]
+pub struct UploadContext {
+    /// The memory properties of the physical device, as returned by
+    /// `get_physical_device_memory_properties`.
+    pub memory_properties: vk::PhysicalDeviceMemoryProperties,
+    /// The queue to submit the transfers to. Must support graphics operations.
+    pub queue: vk::Queue,
+    /// The command pool to allocate the transfer command buffers from. Must have been
+    /// created for the family of `queue`.
+    pub command_pool: vk::CommandPool,
+}
+
+/// A host visible buffer to transfer through. Destroyed when dropped.
+#[derive(Debug)]
+pub struct StagingBuffer {
+    device: Arc<Device>,
+    buffer: vk::Buffer,
+    memory: vk::DeviceMemory,
+    size: vk::DeviceSize,
+}
+
+impl Drop for StagingBuffer {
+    fn drop(&mut self) {
+        unsafe {
+            self.device.destroy_buffer(self.buffer, None);
+            self.device.free_memory(self.memory, None);
+        }
+    }
+}
+
+impl StagingBuffer {
+    /// Creates a staging buffer holding a copy of the data, to upload from.
+    pub fn with_data(
+        device: &Arc<Device>,
+        memory_properties: &vk::PhysicalDeviceMemoryProperties,
+        data: &[u8],
+    ) -> Result<Self> {
+        let (buffer, memory) = create_staging_buffer(device, memory_properties, data)?;
+        Ok(Self {
+            device: Arc::clone(device),
+            buffer,
+            memory,
+            size: data.len() as vk::DeviceSize,
+        })
+    }
+
+    /// Creates an empty staging buffer of the given size in bytes, to download into.
+    pub fn new(
+        device: &Arc<Device>,
+        memory_properties: &vk::PhysicalDeviceMemoryProperties,
+        size: vk::DeviceSize,
+    ) -> Result<Self> {
+        let (buffer, memory) = create_buffer(
+            device,
+            memory_properties,
+            size,
+            vk::BufferUsageFlags::TRANSFER_DST,
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+        )?;
+        Ok(Self {
+            device: Arc::clone(device),
+            buffer,
+            memory,
+            size,
+        })
+    }
+
+    /// Returns the buffer.
+    pub fn buffer(&self) -> vk::Buffer {
+        self.buffer
+    }
+
+    /// Returns the size of the buffer in bytes.
+    pub fn size(&self) -> vk::DeviceSize {
+        self.size
+    }
+
+    /// Reads back the contents of the buffer.
+    ///
+    /// # Safety
+    ///
+    /// Any command buffer writing to the buffer must have finished executing.
+    pub unsafe fn read(&self) -> Result<Vec<u8>> {
+        unsafe {
+            let memory =
+                self.device
+                    .map_memory(self.memory, 0, self.size, vk::MemoryMapFlags::empty())?;
+            let data = std::slice::from_raw_parts(memory as *const u8, self.size as usize).to_vec();
+            self.device.unmap_memory(self.memory);
+            Ok(data)
+        }
+    }
+}
+
+/// A transfer submitted without waiting, and the resources it uses until it has finished.
+///
+/// Dropping it waits for the transfer to finish, so that nothing in use is destroyed.
+#[derive(Debug)]
+pub struct PendingTransfer {
+    device: Arc<Device>,
+    command_pool: vk::CommandPool,
+    command_buffer: vk::CommandBuffer,
+    fence: vk::Fence,
+    staging: StagingBuffer,
+}
+
+impl Drop for PendingTransfer {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = self.device.wait_for_fences(&[self.fence], true, u64::MAX);
+            self.device.destroy_fence(self.fence, None);
+            self.device
+                .free_command_buffers(self.command_pool, &[self.command_buffer]);
+        }
+    }
+}
+
+impl PendingTransfer {
+    /// Returns `true` once the transfer has finished.
+    pub fn is_complete(&self) -> Result<bool> {
+        let status = unsafe { self.device.get_fence_status(self.fence) }?;
+        Ok(status == vk::SuccessCode::SUCCESS)
+    }
+
+    /// Waits for the transfer to finish.
+    pub fn wait(&self) -> Result<()> {
+        unsafe { self.device.wait_for_fences(&[self.fence], true, u64::MAX) }?;
+        Ok(())
+    }
+}
+
+/// A download submitted without waiting. See [`PendingTransfer`].
+#[derive(Debug)]
+pub struct PendingDownload {
+    transfer: PendingTransfer,
+}
+
+impl PendingDownload {
+    /// Returns `true` once the download has finished.
+    pub fn is_complete(&self) -> Result<bool> {
+        self.transfer.is_complete()
+    }
+
+    /// Waits for the download to finish and returns the downloaded data.
+    pub fn wait(self) -> Result<Vec<u8>> {
+        self.transfer.wait()?;
+        unsafe { self.transfer.staging.read() }
+    }
+}
+
+/// Uploads tightly packed pixels into the base level of a color image, and waits for it to
+/// finish. The previous contents of the image are discarded.
+///
+/// # Safety
+///
+/// See [`cmd_upload_to_image`].
+pub unsafe fn upload_to_image(
+    device: &Arc<Device>,
+    context: &UploadContext,
+    image: vk::Image,
+    extent: vk::Extent2D,
+    data: &[u8],
+    final_layout: vk::ImageLayout,
+) -> Result<()> {
+    unsafe { upload_to_image_async(device, context, image, extent, data, final_layout) }?.wait()
+}
+
+/// Submits an upload like [`upload_to_image`] without waiting for it to finish.
+///
+/// # Safety
+///
+/// See [`cmd_upload_to_image`]. The image must not be used before the transfer has finished,
+/// unless synchronized with it on the queue.
+pub unsafe fn upload_to_image_async(
+    device: &Arc<Device>,
+    context: &UploadContext,
+    image: vk::Image,
+    extent: vk::Extent2D,
+    data: &[u8],
+    final_layout: vk::ImageLayout,
+) -> Result<PendingTransfer> {
+    let staging = StagingBuffer::with_data(device, &context.memory_properties, data)?;
+    unsafe {
+        submit_async(device, context, staging, |command_buffer, staging| {
+            cmd_upload_to_image(device, command_buffer, staging, image, extent, final_layout)
+        })
+    }
+}
+
+/// Downloads the base level of a color image, tightly packed with `texel_size` bytes per
+/// texel, and waits for it to finish. The image is returned to `layout` afterwards.
+///
+/// # Safety
+///
+/// See [`cmd_download_from_image`].
+pub unsafe fn download_from_image(
+    device: &Arc<Device>,
+    context: &UploadContext,
+    image: vk::Image,
+    extent: vk::Extent2D,
+    texel_size: u32,
+    layout: vk::ImageLayout,
+) -> Result<Vec<u8>> {
+    unsafe { download_from_image_async(device, context, image, extent, texel_size, layout) }?.wait()
+}
+
+/// Submits a download like [`download_from_image`] without waiting for it to finish.
+///
+/// # Safety
+///
+/// See [`cmd_download_from_image`]. The image must not be written to before the transfer
+/// has finished, unless synchronized with it on the queue.
+pub unsafe fn download_from_image_async(
+    device: &Arc<Device>,
+    context: &UploadContext,
+    image: vk::Image,
+    extent: vk::Extent2D,
+    texel_size: u32,
+    layout: vk::ImageLayout,
+) -> Result<PendingDownload> {
+    let size = extent.width as vk::DeviceSize
+        * extent.height as vk::DeviceSize
+        * texel_size as vk::DeviceSize;
+    let staging = StagingBuffer::new(device, &context.memory_properties, size)?;
+    let transfer = unsafe {
+        submit_async(device, context, staging, |command_buffer, staging| {
+            cmd_download_from_image(device, command_buffer, image, extent, layout, staging)
+        })
+    }?;
+    Ok(PendingDownload { transfer })
+}
+
+/// Records uploading the staging buffer into the base level of a color image, and
+/// transitioning all of the image's levels to `final_layout`.
+///
+/// # Safety
+///
+/// The image must have been created with `TRANSFER_DST` usage and a single mip level, and
+/// must not be in use while the command buffer executes. The staging buffer must hold the
+/// tightly packed pixels of the whole image.
+pub unsafe fn cmd_upload_to_image(
+    device: &Device,
+    command_buffer: vk::CommandBuffer,
+    staging: &StagingBuffer,
+    image: vk::Image,
+    extent: vk::Extent2D,
+    final_layout: vk::ImageLayout,
+) -> Result<()> {
+    unsafe {
+        cmd_transition_image_layout(
+            device,
+            command_buffer,
+            image,
+            1,
+            vk::ImageLayout::UNDEFINED,
+            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+        )?;
+        cmd_copy_buffer_to_image(device, command_buffer, staging.buffer, image, extent);
+        cmd_transition_image_layout(
+            device,
+            command_buffer,
+            image,
+            1,
+            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            final_layout,
+        )
+    }
+}
+
+/// Records downloading the base level of a color image, currently in `layout`, into the
+/// staging buffer. The image is returned to `layout` afterwards.
+///
+/// # Safety
+///
+/// The image must have been created with `TRANSFER_SRC` usage and a single mip level. The
+/// staging buffer must be large enough for the tightly packed pixels of the whole image.
+pub unsafe fn cmd_download_from_image(
+    device: &Device,
+    command_buffer: vk::CommandBuffer,
+    image: vk::Image,
+    extent: vk::Extent2D,
+    layout: vk::ImageLayout,
+    staging: &StagingBuffer,
+) -> Result<()> {
+    if layout == vk::ImageLayout::UNDEFINED {
+        return Err(anyhow!(
+            "An image in UNDEFINED layout holds no data to download."
+        ));
+    }
+
+    unsafe {
+        cmd_transition_image_layout(
+            device,
+            command_buffer,
+            image,
+            1,
+            layout,
+            vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+        )?;
+        cmd_copy_image_to_buffer(device, command_buffer, image, extent, staging.buffer);
+        cmd_transition_image_layout(
+            device,
+            command_buffer,
+            image,
+            1,
+            vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+            layout,
+        )
+    }
+}
+
+/// Records a one-shot command buffer with `record` and submits it without waiting.
+unsafe fn submit_async<F>(
+    device: &Arc<Device>,
+    context: &UploadContext,
+    staging: StagingBuffer,
+    record: F,
+) -> Result<PendingTransfer>
+where
+    F: FnOnce(vk::CommandBuffer, &StagingBuffer) -> Result<()>,
+{
+    unsafe {
+        let command_buffer = begin_single_time_commands(device, context.command_pool)?;
+        let fence = record(command_buffer, &staging)
+            .and_then(|()| submit_single_time_commands(device, context.queue, command_buffer));
+
+        match fence {
+            Ok(fence) => Ok(PendingTransfer {
+                device: Arc::clone(device),
+                command_pool: context.command_pool,
+                command_buffer,
+                fence,
+                staging,
+            }),
+            Err(e) => {
+                device.free_command_buffers(context.command_pool, &[command_buffer]);
+                Err(e)
+            }
+        }
+    }
+}
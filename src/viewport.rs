@@ -0,0 +1,227 @@
+//! Mapping the cursor onto the comparison, e.g. to drag the divider with the mouse.
+//!
+//! Windowing libraries report the cursor in window coordinates, in logical pixels on HiDPI
+//! displays, while the comparison is drawn into a viewport of the swapchain image in physical
+//! pixels, which may be a part of it or letterboxed within it. A [`ViewportMapper`] converts
+//! between the two, into the positions relative to the viewport that
+//! [`CompareInfo`](crate::CompareInfo) takes.
+
+use vulkanalia::prelude::v1_3::*;
+
+use crate::DividerOrientation;
+
+/// Converts window coordinates of the cursor to positions relative to a viewport.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ViewportMapper {
+    /// The viewport the comparison is drawn into, in physical pixels, as passed to the
+    /// comparator or [`CompareInfo::viewport`](crate::CompareInfo::viewport). Viewports with
+    /// a negative height, flipped vertically, map like they are drawn.
+    pub viewport: vk::Viewport,
+    /// The physical pixels per window coordinate, e.g. `2.0` on a HiDPI display reporting
+    /// the cursor in logical pixels. `1.0` if the cursor is in physical pixels already.
+    pub scale_factor: f32,
+}
+
+impl ViewportMapper {
+    /// Maps onto the viewport, with the cursor in physical pixels.
+    pub fn new(viewport: vk::Viewport) -> Self {
+        Self {
+            viewport,
+            scale_factor: 1.0,
+        }
+    }
+
+    /// Maps onto the largest viewport of the aspect ratio (width over height) that fits the
+    /// surface, centered with bars on either side, like an output letterboxed into a window.
+    pub fn letterboxed(surface: vk::Extent2D, aspect_ratio: f32) -> Self {
+        let (surface_width, surface_height) = (surface.width as f32, surface.height as f32);
+        let (width, height) = if surface_width > surface_height * aspect_ratio {
+            (surface_height * aspect_ratio, surface_height)
+        } else {
+            (surface_width, surface_width / aspect_ratio)
+        };
+        Self::new(
+            vk::Viewport::builder()
+                .x((surface_width - width) / 2.0)
+                .y((surface_height - height) / 2.0)
+                .width(width)
+                .height(height)
+                .min_depth(0.0)
+                .max_depth(1.0)
+                .build(),
+        )
+    }
+
+    /// Sets the physical pixels per window coordinate, see
+    /// [`scale_factor`](Self::scale_factor).
+    pub fn with_scale_factor(self, scale_factor: f32) -> Self {
+        Self {
+            scale_factor,
+            ..self
+        }
+    }
+
+    /// Returns the position of the cursor relative to the viewport, `[0.0, 0.0]` at its
+    /// origin and `[1.0, 1.0]` at the opposite corner. Positions outside of the viewport lie
+    /// outside of that range.
+    pub fn position(&self, cursor: [f32; 2]) -> [f32; 2] {
+        let viewport = self.viewport;
+        [
+            relative(cursor[0] * self.scale_factor, viewport.x, viewport.width),
+            relative(cursor[1] * self.scale_factor, viewport.y, viewport.height),
+        ]
+    }
+
+    /// Returns the position of the cursor relative to the viewport, clamped to it, e.g. for
+    /// the center of a [`Magnifier`](crate::Magnifier) or both dividers of
+    /// [`CompareMode::Quad`](crate::CompareMode::Quad).
+    pub fn clamped_position(&self, cursor: [f32; 2]) -> [f32; 2] {
+        self.position(cursor).map(|p| p.clamp(0.0, 1.0))
+    }
+
+    /// Returns whether the cursor is over the viewport, e.g. to only start dragging there.
+    pub fn contains(&self, cursor: [f32; 2]) -> bool {
+        self.position(cursor)
+            .iter()
+            .all(|p| (0.0..=1.0).contains(p))
+    }
+
+    /// Returns the divider position that puts a divider of the orientation under the cursor,
+    /// clamped to `[0.0, 1.0]`, for [`CompareInfo::divider_position`]. Angled dividers pass
+    /// through their position on both axes, so the position is where the line through the
+    /// cursor crosses the diagonal of the viewport.
+    ///
+    /// [`CompareInfo::divider_position`]: crate::CompareInfo::divider_position
+    pub fn divider_position(&self, cursor: [f32; 2], orientation: DividerOrientation) -> f32 {
+        let [x, y] = self.position(cursor);
+        let position = match orientation {
+            DividerOrientation::Vertical => x,
+            DividerOrientation::Horizontal => y,
+            DividerOrientation::Angled(angle) => {
+                // The normal of the divider as drawn, scaled by the aspect ratio of the
+                // viewport like in the shader.
+                let aspect = (self.viewport.height / self.viewport.width).abs();
+                let normal = [angle.cos(), angle.sin() * aspect];
+                let sum = normal[0] + normal[1];
+                // A divider parallel to the diagonal doesn't move with its position.
+                if sum.abs() < f32::EPSILON {
+                    return 0.5;
+                }
+                (x * normal[0] + y * normal[1]) / sum
+            }
+        };
+        position.clamp(0.0, 1.0)
+    }
+}
+
+// The position of a coordinate relative to the range from the origin over the length.
+fn relative(coordinate: f32, origin: f32, length: f32) -> f32 {
+    if length == 0.0 {
+        return 0.0;
+    }
+    (coordinate - origin) / length
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_PI_4;
+
+    fn viewport(x: f32, y: f32, width: f32, height: f32) -> vk::Viewport {
+        vk::Viewport::builder()
+            .x(x)
+            .y(y)
+            .width(width)
+            .height(height)
+            .min_depth(0.0)
+            .max_depth(1.0)
+            .build()
+    }
+
+    #[test]
+    fn maps_corners_of_the_viewport() {
+        let mapper = ViewportMapper::new(viewport(100.0, 50.0, 200.0, 100.0));
+        assert_eq!(mapper.position([100.0, 50.0]), [0.0, 0.0]);
+        assert_eq!(mapper.position([300.0, 150.0]), [1.0, 1.0]);
+        assert_eq!(mapper.position([200.0, 75.0]), [0.5, 0.25]);
+    }
+
+    #[test]
+    fn scales_logical_cursor_coordinates() {
+        let mapper =
+            ViewportMapper::new(viewport(100.0, 50.0, 200.0, 100.0)).with_scale_factor(2.0);
+        assert_eq!(mapper.position([50.0, 25.0]), [0.0, 0.0]);
+        assert_eq!(mapper.position([150.0, 75.0]), [1.0, 1.0]);
+    }
+
+    #[test]
+    fn maps_flipped_viewports_like_they_are_drawn() {
+        let mapper = ViewportMapper::new(viewport(0.0, 150.0, 200.0, -100.0));
+        assert_eq!(mapper.position([0.0, 150.0]), [0.0, 0.0]);
+        assert_eq!(mapper.position([200.0, 50.0]), [1.0, 1.0]);
+        assert!(mapper.contains([100.0, 100.0]));
+        assert!(!mapper.contains([100.0, 160.0]));
+    }
+
+    #[test]
+    fn letterboxes_into_the_surface() {
+        let surface = vk::Extent2D {
+            width: 200,
+            height: 100,
+        };
+        let pillarboxed = ViewportMapper::letterboxed(surface, 1.0).viewport;
+        assert_eq!(
+            [
+                pillarboxed.x,
+                pillarboxed.y,
+                pillarboxed.width,
+                pillarboxed.height
+            ],
+            [50.0, 0.0, 100.0, 100.0]
+        );
+        let letterboxed = ViewportMapper::letterboxed(surface, 4.0).viewport;
+        assert_eq!(
+            [
+                letterboxed.x,
+                letterboxed.y,
+                letterboxed.width,
+                letterboxed.height
+            ],
+            [0.0, 25.0, 200.0, 50.0]
+        );
+    }
+
+    #[test]
+    fn clamps_to_the_viewport() {
+        let mapper = ViewportMapper::new(viewport(100.0, 50.0, 200.0, 100.0));
+        assert!(!mapper.contains([50.0, 100.0]));
+        assert_eq!(mapper.clamped_position([50.0, 400.0]), [0.0, 1.0]);
+        let position = mapper.divider_position([400.0, 100.0], DividerOrientation::Vertical);
+        assert_eq!(position, 1.0);
+    }
+
+    #[test]
+    fn puts_the_divider_under_the_cursor() {
+        let mapper = ViewportMapper::new(viewport(0.0, 0.0, 100.0, 100.0));
+        let cursor = [25.0, 75.0];
+        assert_eq!(
+            mapper.divider_position(cursor, DividerOrientation::Vertical),
+            0.25
+        );
+        assert_eq!(
+            mapper.divider_position(cursor, DividerOrientation::Horizontal),
+            0.75
+        );
+        let angled = mapper.divider_position(cursor, DividerOrientation::Angled(FRAC_PI_4));
+        assert!((angled - 0.5).abs() < 1e-6);
+        let unrotated = mapper.divider_position(cursor, DividerOrientation::Angled(0.0));
+        assert_eq!(unrotated, 0.25);
+    }
+
+    #[test]
+    fn centers_dividers_parallel_to_the_diagonal() {
+        let mapper = ViewportMapper::new(viewport(0.0, 0.0, 100.0, 100.0));
+        let parallel = DividerOrientation::Angled(3.0 * FRAC_PI_4);
+        assert_eq!(mapper.divider_position([10.0, 20.0], parallel), 0.5);
+    }
+}
@@ -0,0 +1,278 @@
+use anyhow::{Result, anyhow};
+use vulkanalia::prelude::v1_3::*;
+
+/// Allocates a primary command buffer from the pool and begins recording it for a single
+/// submission.
+pub(crate) unsafe fn begin_single_time_commands(
+    device: &Device,
+    command_pool: vk::CommandPool,
+) -> Result<vk::CommandBuffer> {
+    let info = vk::CommandBufferAllocateInfo::builder()
+        .level(vk::CommandBufferLevel::PRIMARY)
+        .command_pool(command_pool)
+        .command_buffer_count(1)
+        .build();
+
+    let command_buffer = unsafe { device.allocate_command_buffers(&info) }?[0];
+
+    let begin_info = vk::CommandBufferBeginInfo::builder()
+        .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT)
+        .build();
+
+    if let Err(e) = unsafe { device.begin_command_buffer(command_buffer, &begin_info) } {
+        unsafe { device.free_command_buffers(command_pool, &[command_buffer]) };
+        return Err(e.into());
+    }
+
+    Ok(command_buffer)
+}
+
+/// Ends recording the command buffer, submits it to the queue and waits for it to finish.
+/// The command buffer is freed in any case.
+pub(crate) unsafe fn end_single_time_commands(
+    device: &Device,
+    queue: vk::Queue,
+    command_pool: vk::CommandPool,
+    command_buffer: vk::CommandBuffer,
+) -> Result<()> {
+    let result = unsafe { submit_single_time_commands(device, queue, command_buffer) }.and_then(
+        |fence| unsafe {
+            // Waiting for the fence rather than the queue leaves other work on it running.
+            let waited = device.wait_for_fences(&[fence], true, u64::MAX);
+            device.destroy_fence(fence, None);
+            waited.map(|_| ()).map_err(Into::into)
+        },
+    );
+    unsafe { device.free_command_buffers(command_pool, &[command_buffer]) };
+    result
+}
+
+/// Ends recording the command buffer and submits it to the queue without waiting. Returns
+/// the fence signalled when it has finished, which the caller must destroy afterwards, as
+/// well as free the command buffer.
+pub(crate) unsafe fn submit_single_time_commands(
+    device: &Device,
+    queue: vk::Queue,
+    command_buffer: vk::CommandBuffer,
+) -> Result<vk::Fence> {
+    unsafe { device.end_command_buffer(command_buffer) }?;
+
+    let fence = unsafe { device.create_fence(&vk::FenceCreateInfo::default(), None) }?;
+
+    let command_buffers = &[command_buffer];
+    let info = vk::SubmitInfo::builder()
+        .command_buffers(command_buffers)
+        .build();
+
+    if let Err(e) = unsafe { device.queue_submit(queue, &[info], fence) } {
+        unsafe { device.destroy_fence(fence, None) };
+        return Err(e.into());
+    }
+
+    Ok(fence)
+}
+
+/// Records a layout transition of all mip levels of a color image. Whatever wrote the image
+/// before is waited for, as the previous use is unknown.
+pub(crate) unsafe fn cmd_transition_image_layout(
+    device: &Device,
+    command_buffer: vk::CommandBuffer,
+    image: vk::Image,
+    mip_levels: u32,
+    old_layout: vk::ImageLayout,
+    new_layout: vk::ImageLayout,
+) -> Result<()> {
+    let (src_access_mask, src_stage_mask) = match old_layout {
+        vk::ImageLayout::UNDEFINED => (
+            vk::AccessFlags::empty(),
+            vk::PipelineStageFlags::TOP_OF_PIPE,
+        ),
+        _ => (
+            vk::AccessFlags::MEMORY_WRITE,
+            vk::PipelineStageFlags::ALL_COMMANDS,
+        ),
+    };
+
+    let (dst_access_mask, dst_stage_mask) = match new_layout {
+        vk::ImageLayout::TRANSFER_DST_OPTIMAL => (
+            vk::AccessFlags::TRANSFER_WRITE,
+            vk::PipelineStageFlags::TRANSFER,
+        ),
+        vk::ImageLayout::TRANSFER_SRC_OPTIMAL => (
+            vk::AccessFlags::TRANSFER_READ,
+            vk::PipelineStageFlags::TRANSFER,
+        ),
+        vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL => (
+            vk::AccessFlags::SHADER_READ,
+            vk::PipelineStageFlags::FRAGMENT_SHADER | vk::PipelineStageFlags::COMPUTE_SHADER,
+        ),
+        vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL => (
+            vk::AccessFlags::COLOR_ATTACHMENT_READ | vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+            vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+        ),
+        vk::ImageLayout::GENERAL => (
+            vk::AccessFlags::MEMORY_READ | vk::AccessFlags::MEMORY_WRITE,
+            vk::PipelineStageFlags::ALL_COMMANDS,
+        ),
+        // Presentation is synchronized with semaphores.
+        vk::ImageLayout::PRESENT_SRC_KHR => (
+            vk::AccessFlags::empty(),
+            vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+        ),
+        _ => {
+            return Err(anyhow!(
+                "Unsupported image layout transition from {:?} to {:?}.",
+                old_layout,
+                new_layout
+            ));
+        }
+    };
+
+    let subresource = vk::ImageSubresourceRange::builder()
+        .aspect_mask(vk::ImageAspectFlags::COLOR)
+        .base_mip_level(0)
+        .level_count(mip_levels)
+        .base_array_layer(0)
+        .layer_count(1)
+        .build();
+
+    let barrier = vk::ImageMemoryBarrier::builder()
+        .old_layout(old_layout)
+        .new_layout(new_layout)
+        .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .image(image)
+        .subresource_range(subresource)
+        .src_access_mask(src_access_mask)
+        .dst_access_mask(dst_access_mask)
+        .build();
+
+    unsafe {
+        device.cmd_pipeline_barrier(
+            command_buffer,
+            src_stage_mask,
+            dst_stage_mask,
+            vk::DependencyFlags::empty(),
+            &[] as &[vk::MemoryBarrier],
+            &[] as &[vk::BufferMemoryBarrier],
+            &[barrier],
+        );
+    }
+
+    Ok(())
+}
+
+/// Records copying tightly packed pixels from the buffer into the base level of a color
+/// image in `TRANSFER_DST_OPTIMAL` layout.
+pub(crate) unsafe fn cmd_copy_buffer_to_image(
+    device: &Device,
+    command_buffer: vk::CommandBuffer,
+    buffer: vk::Buffer,
+    image: vk::Image,
+    extent: vk::Extent2D,
+) {
+    let subresource = vk::ImageSubresourceLayers::builder()
+        .aspect_mask(vk::ImageAspectFlags::COLOR)
+        .mip_level(0)
+        .base_array_layer(0)
+        .layer_count(1)
+        .build();
+
+    let region = vk::BufferImageCopy::builder()
+        .buffer_offset(0)
+        // Zero means tightly packed.
+        .buffer_row_length(0)
+        .buffer_image_height(0)
+        .image_subresource(subresource)
+        .image_offset(vk::Offset3D { x: 0, y: 0, z: 0 })
+        .image_extent(vk::Extent3D {
+            width: extent.width,
+            height: extent.height,
+            depth: 1,
+        })
+        .build();
+
+    unsafe {
+        device.cmd_copy_buffer_to_image(
+            command_buffer,
+            buffer,
+            image,
+            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            &[region],
+        );
+    }
+}
+
+/// Records copying the base level of a color image in `TRANSFER_SRC_OPTIMAL` layout into the
+/// buffer, tightly packed, and making the copy visible to the host.
+pub(crate) unsafe fn cmd_copy_image_to_buffer(
+    device: &Device,
+    command_buffer: vk::CommandBuffer,
+    image: vk::Image,
+    extent: vk::Extent2D,
+    buffer: vk::Buffer,
+) {
+    let subresource = vk::ImageSubresourceLayers::builder()
+        .aspect_mask(vk::ImageAspectFlags::COLOR)
+        .mip_level(0)
+        .base_array_layer(0)
+        .layer_count(1)
+        .build();
+
+    let region = vk::BufferImageCopy::builder()
+        .buffer_offset(0)
+        .buffer_row_length(0)
+        .buffer_image_height(0)
+        .image_subresource(subresource)
+        .image_offset(vk::Offset3D { x: 0, y: 0, z: 0 })
+        .image_extent(vk::Extent3D {
+            width: extent.width,
+            height: extent.height,
+            depth: 1,
+        })
+        .build();
+
+    let barrier = vk::BufferMemoryBarrier::builder()
+        .src_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+        .dst_access_mask(vk::AccessFlags::HOST_READ)
+        .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .buffer(buffer)
+        .offset(0)
+        .size(vk::WHOLE_SIZE)
+        .build();
+
+    unsafe {
+        device.cmd_copy_image_to_buffer(
+            command_buffer,
+            image,
+            vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+            buffer,
+            &[region],
+        );
+
+        device.cmd_pipeline_barrier(
+            command_buffer,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::PipelineStageFlags::HOST,
+            vk::DependencyFlags::empty(),
+            &[] as &[vk::MemoryBarrier],
+            &[barrier],
+            &[] as &[vk::ImageMemoryBarrier],
+        );
+    }
+}
+
+/// Creates a command pool for short-lived one-shot command buffers.
+pub(crate) fn create_command_pool(
+    device: &Device,
+    queue_family_index: u32,
+) -> Result<vk::CommandPool> {
+    let info = vk::CommandPoolCreateInfo::builder()
+        .flags(vk::CommandPoolCreateFlags::TRANSIENT)
+        .queue_family_index(queue_family_index)
+        .build();
+
+    let command_pool = unsafe { device.create_command_pool(&info, None) }?;
+    Ok(command_pool)
+}
@@ -0,0 +1,18 @@
+use std::ffi::CString;
+use vulkanalia::prelude::v1_3::*;
+
+/// Names an object in validation messages and GPU captures. Requires the instance to have
+/// been created with `VK_EXT_debug_utils`. Naming only aids debugging, so failures are
+/// ignored.
+pub(crate) fn set_object_name<H: vk::Handle<Repr = u64>>(device: &Device, handle: H, name: &str) {
+    let Ok(name) = CString::new(name) else {
+        return;
+    };
+    let info = vk::DebugUtilsObjectNameInfoEXT::builder()
+        .object_type(H::TYPE)
+        .object_handle(handle.as_raw())
+        .object_name(name.as_bytes_with_nul())
+        .build();
+
+    let _ = unsafe { (device.commands().set_debug_utils_object_name_ext)(device.handle(), &info) };
+}
@@ -1,21 +1,74 @@
 use anyhow::Result;
 use vulkanalia::prelude::v1_3::*;
 
-pub(crate) fn create_descriptor_set_layout(device: &Device) -> Result<vk::DescriptorSetLayout> {
-    let bindings = (0..2)
-        .map(|i| {
-            vk::DescriptorSetLayoutBinding::builder()
-                .binding(i)
-                .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
-                .descriptor_count(1)
-                .stage_flags(vk::ShaderStageFlags::FRAGMENT)
-                .build()
-        })
-        .collect::<Vec<_>>();
+/// The size of the input image array of the comparison shader.
+pub(crate) const MAX_INPUTS: usize = 8;
 
-    let info = vk::DescriptorSetLayoutCreateInfo::builder()
-        .bindings(&bindings)
+/// Fills the whole input array with the given views, repeating them for unused inputs so
+/// that every element is valid.
+pub(crate) fn input_bindings(image_views: &[vk::ImageView]) -> [vk::ImageView; MAX_INPUTS] {
+    std::array::from_fn(|i| image_views[i % image_views.len()])
+}
+
+/// The bindings of the comparison's descriptor set layout.
+pub(crate) fn input_layout_bindings() -> [vk::DescriptorSetLayoutBinding; 1] {
+    // All inputs are bound as one array, so that the shader can select them by index. The
+    // vector overlay samples them per arrow, in the vertex shader, and the pixel inspector in
+    // a compute shader.
+    [vk::DescriptorSetLayoutBinding::builder()
+        .binding(0)
+        .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+        .descriptor_count(MAX_INPUTS as u32)
+        .stage_flags(
+            vk::ShaderStageFlags::VERTEX
+                | vk::ShaderStageFlags::FRAGMENT
+                | vk::ShaderStageFlags::COMPUTE,
+        )
+        .build()]
+}
+
+/// The flags of the input array with bindless inputs, of which only the written elements are
+/// valid and each set is allocated with its own length.
+pub(crate) fn bindless_binding_flags() -> vk::DescriptorBindingFlags {
+    vk::DescriptorBindingFlags::PARTIALLY_BOUND
+        | vk::DescriptorBindingFlags::VARIABLE_DESCRIPTOR_COUNT
+}
+
+/// Creates the comparison's descriptor set layout. With an immutable sampler, such as one
+/// with a YCbCr conversion, all inputs are sampled with it regardless of the written ones.
+/// A layout for push descriptors can't be allocated from, its inputs are pushed with
+/// [`cmd_push_input_descriptors`]. With bindless inputs, the array holds up to that many
+/// elements, as many as each set is allocated with, of which only the written ones are valid.
+pub(crate) fn create_descriptor_set_layout(
+    device: &Device,
+    immutable_sampler: Option<vk::Sampler>,
+    push_descriptors: bool,
+    bindless_inputs: Option<u32>,
+) -> Result<vk::DescriptorSetLayout> {
+    let mut bindings = input_layout_bindings();
+    let immutable_samplers = immutable_sampler.map(|sampler| [sampler; MAX_INPUTS]);
+    if let Some(samplers) = &immutable_samplers {
+        bindings[0].immutable_samplers = samplers.as_ptr();
+    }
+    if let Some(count) = bindless_inputs {
+        bindings[0].descriptor_count = count;
+    }
+
+    let flags = match push_descriptors {
+        true => vk::DescriptorSetLayoutCreateFlags::PUSH_DESCRIPTOR_KHR,
+        false => vk::DescriptorSetLayoutCreateFlags::empty(),
+    };
+    let binding_flags = [bindless_binding_flags()];
+    let mut binding_flags_info = vk::DescriptorSetLayoutBindingFlagsCreateInfo::builder()
+        .binding_flags(&binding_flags)
         .build();
+    let mut info = vk::DescriptorSetLayoutCreateInfo::builder()
+        .flags(flags)
+        .bindings(&bindings);
+    if bindless_inputs.is_some() {
+        info = info.push_next(&mut binding_flags_info);
+    }
+    let info = info.build();
 
     let descriptor_set_layout = unsafe { device.create_descriptor_set_layout(&info, None) }?;
     Ok(descriptor_set_layout)
@@ -37,13 +90,11 @@ pub(crate) fn create_descriptor_set(
     Ok(descriptor_sets[0])
 }
 
-pub(crate) fn update_descriptor_sets(
-    device: &Device,
-    descriptor_set: &vk::DescriptorSet,
+fn image_infos(
     sampler: &vk::Sampler,
-    image_views: &[vk::ImageView; 2],
-) {
-    let infos = image_views
+    image_views: &[vk::ImageView],
+) -> Vec<vk::DescriptorImageInfo> {
+    image_views
         .iter()
         .map(|image_view| {
             vk::DescriptorImageInfo::builder()
@@ -52,22 +103,302 @@ pub(crate) fn update_descriptor_sets(
                 .sampler(*sampler)
                 .build()
         })
-        .collect::<Vec<_>>();
+        .collect()
+}
 
-    let writes = infos
-        .iter()
-        .enumerate()
-        .map(|(i, image_info)| {
-            vk::WriteDescriptorSet::builder()
-                .dst_set(*descriptor_set)
-                .dst_binding(i as u32)
-                .dst_array_element(0)
-                .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
-                .image_info(std::slice::from_ref(image_info))
-                .build()
-        })
-        .collect::<Vec<_>>();
+/// Allocates an input descriptor set whose array holds `count` elements with bindless
+/// inputs. Without, the layout's array size is fixed and the count is ignored.
+pub(crate) fn create_input_descriptor_set(
+    device: &Device,
+    pool: &vk::DescriptorPool,
+    layout: &vk::DescriptorSetLayout,
+    count: u32,
+) -> Result<vk::DescriptorSet> {
+    let counts = [count];
+    let mut count_info = vk::DescriptorSetVariableDescriptorCountAllocateInfo::builder()
+        .descriptor_counts(&counts)
+        .build();
+    let info = vk::DescriptorSetAllocateInfo::builder()
+        .descriptor_pool(*pool)
+        .set_layouts(std::slice::from_ref(layout))
+        .push_next(&mut count_info)
+        .build();
+
+    let descriptor_sets = unsafe { device.allocate_descriptor_sets(&info) }?;
+
+    Ok(descriptor_sets[0])
+}
+
+pub(crate) fn update_descriptor_sets(
+    device: &Device,
+    descriptor_set: &vk::DescriptorSet,
+    sampler: &vk::Sampler,
+    image_views: &[vk::ImageView],
+) {
+    update_input_array(device, descriptor_set, sampler, 0, image_views);
+}
+
+/// Writes views into the input array from the given element on.
+pub(crate) fn update_input_array(
+    device: &Device,
+    descriptor_set: &vk::DescriptorSet,
+    sampler: &vk::Sampler,
+    first_element: u32,
+    image_views: &[vk::ImageView],
+) {
+    let infos = image_infos(sampler, image_views);
+
+    // The views are written to consecutive elements of the input array at binding 0.
+    let writes = [vk::WriteDescriptorSet::builder()
+        .dst_set(*descriptor_set)
+        .dst_binding(0)
+        .dst_array_element(first_element)
+        .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+        .image_info(&infos)
+        .build()];
 
     // The second argument can be used to copy descriptor sets to each other.
     unsafe { device.update_descriptor_sets(&writes, &[] as &[vk::CopyDescriptorSet]) };
 }
+
+/// Records pushing the input views as set 0 of a pipeline layout whose first set layout was
+/// created for push descriptors, with `VK_KHR_push_descriptor`.
+pub(crate) unsafe fn cmd_push_input_descriptors(
+    device: &Device,
+    command_buffer: vk::CommandBuffer,
+    layout: vk::PipelineLayout,
+    sampler: &vk::Sampler,
+    image_views: &[vk::ImageView],
+) {
+    let infos = image_infos(sampler, image_views);
+
+    // The destination set is ignored, the writes go to the pushed set.
+    let writes = [vk::WriteDescriptorSet::builder()
+        .dst_binding(0)
+        .dst_array_element(0)
+        .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+        .image_info(&infos)
+        .build()];
+
+    unsafe {
+        (device.commands().cmd_push_descriptor_set_khr)(
+            command_buffer,
+            vk::PipelineBindPoint::GRAPHICS,
+            layout,
+            0,
+            writes.len() as u32,
+            writes.as_ptr(),
+        )
+    };
+}
+
+pub(crate) fn create_metrics_descriptor_set_layout(
+    device: &Device,
+) -> Result<vk::DescriptorSetLayout> {
+    let bindings = [
+        vk::DescriptorSetLayoutBinding::builder()
+            .binding(0)
+            .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+            .descriptor_count(2)
+            .stage_flags(vk::ShaderStageFlags::COMPUTE)
+            .build(),
+        // The per-workgroup partial results of the metrics reduction.
+        vk::DescriptorSetLayoutBinding::builder()
+            .binding(1)
+            .descriptor_type(vk::DescriptorType::STORAGE_BUFFER)
+            .descriptor_count(1)
+            .stage_flags(vk::ShaderStageFlags::COMPUTE)
+            .build(),
+    ];
+
+    let info = vk::DescriptorSetLayoutCreateInfo::builder()
+        .bindings(&bindings)
+        .build();
+
+    let descriptor_set_layout = unsafe { device.create_descriptor_set_layout(&info, None) }?;
+    Ok(descriptor_set_layout)
+}
+
+pub(crate) fn create_stats_descriptor_set_layout(
+    device: &Device,
+) -> Result<vk::DescriptorSetLayout> {
+    let bindings = [vk::DescriptorSetLayoutBinding::builder()
+        .binding(0)
+        .descriptor_type(vk::DescriptorType::STORAGE_BUFFER)
+        .descriptor_count(1)
+        .stage_flags(vk::ShaderStageFlags::FRAGMENT)
+        .build()];
+
+    let info = vk::DescriptorSetLayoutCreateInfo::builder()
+        .bindings(&bindings)
+        .build();
+
+    let descriptor_set_layout = unsafe { device.create_descriptor_set_layout(&info, None) }?;
+    Ok(descriptor_set_layout)
+}
+
+/// A single storage buffer written by a compute shader, such as the inspected texels or the
+/// partial results of a metric.
+pub(crate) fn create_compute_buffer_descriptor_set_layout(
+    device: &Device,
+) -> Result<vk::DescriptorSetLayout> {
+    let bindings = [vk::DescriptorSetLayoutBinding::builder()
+        .binding(0)
+        .descriptor_type(vk::DescriptorType::STORAGE_BUFFER)
+        .descriptor_count(1)
+        .stage_flags(vk::ShaderStageFlags::COMPUTE)
+        .build()];
+
+    let info = vk::DescriptorSetLayoutCreateInfo::builder()
+        .bindings(&bindings)
+        .build();
+
+    let descriptor_set_layout = unsafe { device.create_descriptor_set_layout(&info, None) }?;
+    Ok(descriptor_set_layout)
+}
+
+pub(crate) fn create_color_transform_descriptor_set_layout(
+    device: &Device,
+) -> Result<vk::DescriptorSetLayout> {
+    let bindings = [vk::DescriptorSetLayoutBinding::builder()
+        .binding(0)
+        .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER)
+        .descriptor_count(1)
+        .stage_flags(vk::ShaderStageFlags::FRAGMENT)
+        .build()];
+
+    let info = vk::DescriptorSetLayoutCreateInfo::builder()
+        .bindings(&bindings)
+        .build();
+
+    let descriptor_set_layout = unsafe { device.create_descriptor_set_layout(&info, None) }?;
+    Ok(descriptor_set_layout)
+}
+
+/// A single image read by the fragment shader, such as the lookup table or the glyph atlas.
+pub(crate) fn create_image_descriptor_set_layout(
+    device: &Device,
+) -> Result<vk::DescriptorSetLayout> {
+    let bindings = [vk::DescriptorSetLayoutBinding::builder()
+        .binding(0)
+        .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+        .descriptor_count(1)
+        .stage_flags(vk::ShaderStageFlags::FRAGMENT)
+        .build()];
+
+    let info = vk::DescriptorSetLayoutCreateInfo::builder()
+        .bindings(&bindings)
+        .build();
+
+    let descriptor_set_layout = unsafe { device.create_descriptor_set_layout(&info, None) }?;
+    Ok(descriptor_set_layout)
+}
+
+pub(crate) fn create_descriptor_pool(
+    device: &Device,
+    max_sets: u32,
+    image_sampler_count: u32,
+    storage_buffer_count: u32,
+    uniform_buffer_count: u32,
+) -> Result<vk::DescriptorPool> {
+    // Pool sizes must not be empty, so unused descriptor types are left out.
+    let pool_sizes = [
+        (
+            vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+            image_sampler_count,
+        ),
+        (vk::DescriptorType::STORAGE_BUFFER, storage_buffer_count),
+        (vk::DescriptorType::UNIFORM_BUFFER, uniform_buffer_count),
+    ]
+    .into_iter()
+    .filter(|(_, count)| *count > 0)
+    .map(|(type_, count)| {
+        vk::DescriptorPoolSize::builder()
+            .type_(type_)
+            .descriptor_count(count)
+            .build()
+    })
+    .collect::<Vec<_>>();
+
+    let info = vk::DescriptorPoolCreateInfo::builder()
+        .pool_sizes(&pool_sizes)
+        .max_sets(max_sets)
+        .build();
+
+    let descriptor_pool = unsafe { device.create_descriptor_pool(&info, None) }?;
+    Ok(descriptor_pool)
+}
+
+pub(crate) fn update_image_descriptor_set(
+    device: &Device,
+    descriptor_set: &vk::DescriptorSet,
+    sampler: &vk::Sampler,
+    image_view: vk::ImageView,
+) {
+    update_descriptor_sets(device, descriptor_set, sampler, &[image_view]);
+}
+
+pub(crate) fn update_metrics_descriptor_set(
+    device: &Device,
+    descriptor_set: &vk::DescriptorSet,
+    sampler: &vk::Sampler,
+    image_views: &[vk::ImageView; 2],
+    buffer: &vk::Buffer,
+) {
+    update_descriptor_sets(device, descriptor_set, sampler, image_views);
+    update_storage_buffer_descriptor(device, descriptor_set, 1, buffer);
+}
+
+pub(crate) fn update_storage_buffer_descriptor(
+    device: &Device,
+    descriptor_set: &vk::DescriptorSet,
+    binding: u32,
+    buffer: &vk::Buffer,
+) {
+    update_buffer_descriptor(
+        device,
+        descriptor_set,
+        binding,
+        vk::DescriptorType::STORAGE_BUFFER,
+        buffer,
+    );
+}
+
+pub(crate) fn update_uniform_buffer_descriptor(
+    device: &Device,
+    descriptor_set: &vk::DescriptorSet,
+    binding: u32,
+    buffer: &vk::Buffer,
+) {
+    update_buffer_descriptor(
+        device,
+        descriptor_set,
+        binding,
+        vk::DescriptorType::UNIFORM_BUFFER,
+        buffer,
+    );
+}
+
+fn update_buffer_descriptor(
+    device: &Device,
+    descriptor_set: &vk::DescriptorSet,
+    binding: u32,
+    descriptor_type: vk::DescriptorType,
+    buffer: &vk::Buffer,
+) {
+    let buffer_info = vk::DescriptorBufferInfo::builder()
+        .buffer(*buffer)
+        .offset(0)
+        .range(vk::WHOLE_SIZE)
+        .build();
+
+    let write = vk::WriteDescriptorSet::builder()
+        .dst_set(*descriptor_set)
+        .dst_binding(binding)
+        .dst_array_element(0)
+        .descriptor_type(descriptor_type)
+        .buffer_info(std::slice::from_ref(&buffer_info))
+        .build();
+
+    unsafe { device.update_descriptor_sets(&[write], &[] as &[vk::CopyDescriptorSet]) };
+}
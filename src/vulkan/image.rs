@@ -0,0 +1,129 @@
+use anyhow::{Result, anyhow};
+use vulkanalia::prelude::v1_3::*;
+
+use super::memory::allocate_memory;
+
+/// Returns the size of a texel in bytes, for the color formats images are commonly rendered
+/// in.
+pub(crate) fn texel_size(format: vk::Format) -> Result<u32> {
+    match format {
+        vk::Format::R8G8B8A8_UNORM
+        | vk::Format::R8G8B8A8_SRGB
+        | vk::Format::B8G8R8A8_UNORM
+        | vk::Format::B8G8R8A8_SRGB
+        | vk::Format::A2B10G10R10_UNORM_PACK32
+        | vk::Format::A2R10G10B10_UNORM_PACK32
+        | vk::Format::B10G11R11_UFLOAT_PACK32 => Ok(4),
+        vk::Format::R16G16B16A16_UNORM | vk::Format::R16G16B16A16_SFLOAT => Ok(8),
+        vk::Format::R32G32B32A32_SFLOAT => Ok(16),
+        _ => Err(anyhow!(
+            "Reading back images of format {:?} is not supported.",
+            format
+        )),
+    }
+}
+
+/// Creates a 2D color image with optimal tiling, bound to device local memory.
+pub(crate) fn create_image(
+    device: &Device,
+    memory_properties: &vk::PhysicalDeviceMemoryProperties,
+    extent: vk::Extent2D,
+    format: vk::Format,
+    mip_levels: u32,
+    usage: vk::ImageUsageFlags,
+) -> Result<(vk::Image, vk::DeviceMemory)> {
+    let info = vk::ImageCreateInfo::builder()
+        .image_type(vk::ImageType::_2D)
+        .extent(vk::Extent3D {
+            width: extent.width,
+            height: extent.height,
+            depth: 1,
+        })
+        .mip_levels(mip_levels)
+        .array_layers(1)
+        .format(format)
+        .tiling(vk::ImageTiling::OPTIMAL)
+        .initial_layout(vk::ImageLayout::UNDEFINED)
+        .usage(usage)
+        .sharing_mode(vk::SharingMode::EXCLUSIVE)
+        .samples(vk::SampleCountFlags::_1)
+        .build();
+
+    let image = unsafe { device.create_image(&info, None) }?;
+
+    let requirements = unsafe { device.get_image_memory_requirements(image) };
+    match allocate_memory(
+        device,
+        memory_properties,
+        &requirements,
+        vk::MemoryPropertyFlags::DEVICE_LOCAL,
+    ) {
+        Ok(memory) => match unsafe { device.bind_image_memory(image, memory, 0) } {
+            Ok(()) => Ok((image, memory)),
+            Err(e) => {
+                unsafe {
+                    device.free_memory(memory, None);
+                    device.destroy_image(image, None);
+                }
+                Err(e.into())
+            }
+        },
+        Err(e) => {
+            unsafe { device.destroy_image(image, None) };
+            Err(e)
+        }
+    }
+}
+
+/// Creates a view of all mip levels of a 2D color image.
+pub(crate) fn create_image_view(
+    device: &Device,
+    image: vk::Image,
+    format: vk::Format,
+    mip_levels: u32,
+) -> Result<vk::ImageView> {
+    create_subresource_view(device, image, format, 0, mip_levels, 0)
+}
+
+/// Creates a 2D view of `level_count` mip levels of a single layer of a color image, e.g. of
+/// a cubemap face. Views of depth images are of the depth aspect, as sampled by the shaders.
+pub(crate) fn create_subresource_view(
+    device: &Device,
+    image: vk::Image,
+    format: vk::Format,
+    base_mip_level: u32,
+    level_count: u32,
+    layer: u32,
+) -> Result<vk::ImageView> {
+    let subresource_range = vk::ImageSubresourceRange::builder()
+        .aspect_mask(sampled_aspect(format))
+        .base_mip_level(base_mip_level)
+        .level_count(level_count)
+        .base_array_layer(layer)
+        .layer_count(1)
+        .build();
+
+    let info = vk::ImageViewCreateInfo::builder()
+        .image(image)
+        .view_type(vk::ImageViewType::_2D)
+        .format(format)
+        .subresource_range(subresource_range)
+        .build();
+
+    let image_view = unsafe { device.create_image_view(&info, None) }?;
+    Ok(image_view)
+}
+
+// The aspect of an image the shaders sample: the depth of depth and depth/stencil formats,
+// the color of all others.
+fn sampled_aspect(format: vk::Format) -> vk::ImageAspectFlags {
+    match format {
+        vk::Format::D16_UNORM
+        | vk::Format::X8_D24_UNORM_PACK32
+        | vk::Format::D32_SFLOAT
+        | vk::Format::D16_UNORM_S8_UINT
+        | vk::Format::D24_UNORM_S8_UINT
+        | vk::Format::D32_SFLOAT_S8_UINT => vk::ImageAspectFlags::DEPTH,
+        _ => vk::ImageAspectFlags::COLOR,
+    }
+}
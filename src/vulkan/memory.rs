@@ -0,0 +1,100 @@
+use anyhow::{Result, anyhow};
+use vulkanalia::prelude::v1_3::*;
+
+pub(crate) fn find_memory_type(
+    memory_properties: &vk::PhysicalDeviceMemoryProperties,
+    requirements: &vk::MemoryRequirements,
+    properties: vk::MemoryPropertyFlags,
+) -> Result<u32> {
+    // Each bit of memory_type_bits marks a memory type the resource can be bound to.
+    (0..memory_properties.memory_type_count)
+        .find(|i| {
+            let suitable = (requirements.memory_type_bits & (1 << i)) != 0;
+            let memory_type = memory_properties.memory_types[*i as usize];
+            suitable && memory_type.property_flags.contains(properties)
+        })
+        .ok_or_else(|| anyhow!("Failed to find a suitable memory type."))
+}
+
+pub(crate) fn create_buffer(
+    device: &Device,
+    memory_properties: &vk::PhysicalDeviceMemoryProperties,
+    size: vk::DeviceSize,
+    usage: vk::BufferUsageFlags,
+    properties: vk::MemoryPropertyFlags,
+) -> Result<(vk::Buffer, vk::DeviceMemory)> {
+    let buffer_info = vk::BufferCreateInfo::builder()
+        .size(size)
+        .usage(usage)
+        .sharing_mode(vk::SharingMode::EXCLUSIVE)
+        .build();
+
+    let buffer = unsafe { device.create_buffer(&buffer_info, None) }?;
+
+    let requirements = unsafe { device.get_buffer_memory_requirements(buffer) };
+    match allocate_memory(device, memory_properties, &requirements, properties) {
+        Ok(memory) => match unsafe { device.bind_buffer_memory(buffer, memory, 0) } {
+            Ok(()) => Ok((buffer, memory)),
+            Err(e) => {
+                unsafe {
+                    device.free_memory(memory, None);
+                    device.destroy_buffer(buffer, None);
+                }
+                Err(e.into())
+            }
+        },
+        Err(e) => {
+            unsafe { device.destroy_buffer(buffer, None) };
+            Err(e)
+        }
+    }
+}
+
+pub(crate) fn allocate_memory(
+    device: &Device,
+    memory_properties: &vk::PhysicalDeviceMemoryProperties,
+    requirements: &vk::MemoryRequirements,
+    properties: vk::MemoryPropertyFlags,
+) -> Result<vk::DeviceMemory> {
+    let memory_info = vk::MemoryAllocateInfo::builder()
+        .allocation_size(requirements.size)
+        .memory_type_index(find_memory_type(
+            memory_properties,
+            requirements,
+            properties,
+        )?)
+        .build();
+
+    Ok(unsafe { device.allocate_memory(&memory_info, None) }?)
+}
+
+/// Creates a host visible buffer holding a copy of the data, to transfer from.
+pub(crate) fn create_staging_buffer(
+    device: &Device,
+    memory_properties: &vk::PhysicalDeviceMemoryProperties,
+    data: &[u8],
+) -> Result<(vk::Buffer, vk::DeviceMemory)> {
+    let size = data.len() as vk::DeviceSize;
+    let (buffer, memory) = create_buffer(
+        device,
+        memory_properties,
+        size,
+        vk::BufferUsageFlags::TRANSFER_SRC,
+        vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+    )?;
+
+    match unsafe { device.map_memory(memory, 0, size, vk::MemoryMapFlags::empty()) } {
+        Ok(mapped) => unsafe {
+            std::ptr::copy_nonoverlapping(data.as_ptr(), mapped.cast(), data.len());
+            device.unmap_memory(memory);
+            Ok((buffer, memory))
+        },
+        Err(e) => {
+            unsafe {
+                device.destroy_buffer(buffer, None);
+                device.free_memory(memory, None);
+            }
+            Err(e.into())
+        }
+    }
+}
@@ -1,4 +1,8 @@
+pub(crate) mod commands;
+pub(crate) mod debug;
 pub(crate) mod descriptors;
+pub(crate) mod image;
+pub(crate) mod memory;
 pub(crate) mod pipeline;
 pub(crate) mod push_constants;
 pub(crate) mod render_pass;
@@ -1,21 +1,393 @@
-use anyhow::Result;
+use anyhow::{Result, anyhow};
 use vulkanalia::bytecode::Bytecode;
 use vulkanalia::prelude::v1_3::*;
 
-use crate::vulkan::push_constants::PushConstantBuffer;
+use crate::vulkan::push_constants::{
+    CrosshairPushConstantBuffer, GridPushConstantBuffer, HandlePushConstantBuffer,
+    HistogramPushConstantBuffer, InspectPushConstantBuffer, LabelPushConstantBuffer,
+    MetricsPushConstantBuffer, PushConstantBuffer, StripPushConstantBuffer,
+    VectorPushConstantBuffer,
+};
+use crate::{CompareMode, DepthRange, IntegerMapping, MAX_PALETTE_COLORS};
 
+// The SPIR-V of a shader by the name of its binary: compiled by the build script with the
+// `compile-shaders` feature, or checked in next to its source otherwise.
+#[cfg(feature = "compile-shaders")]
+macro_rules! spirv {
+    ($name:literal) => {
+        include_bytes!(concat!(env!("OUT_DIR"), "/", $name, ".spv"))
+    };
+}
+#[cfg(not(feature = "compile-shaders"))]
+macro_rules! spirv {
+    ($name:literal) => {
+        include_bytes!(concat!("shaders/", $name, ".spv"))
+    };
+}
+
+pub(crate) fn create_pipeline_layout(
+    device: &Device,
+    descriptor_set_layouts: &[vk::DescriptorSetLayout],
+) -> Result<vk::PipelineLayout> {
+    create_graphics_pipeline_layout(
+        device,
+        descriptor_set_layouts,
+        vk::ShaderStageFlags::FRAGMENT,
+        std::mem::size_of::<PushConstantBuffer>(),
+    )
+}
+
+pub(crate) fn create_vector_pipeline_layout(
+    device: &Device,
+    descriptor_set_layout: vk::DescriptorSetLayout,
+) -> Result<vk::PipelineLayout> {
+    create_graphics_pipeline_layout(
+        device,
+        &[descriptor_set_layout],
+        vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
+        std::mem::size_of::<VectorPushConstantBuffer>(),
+    )
+}
+
+pub(crate) fn create_handle_pipeline_layout(device: &Device) -> Result<vk::PipelineLayout> {
+    create_graphics_pipeline_layout(
+        device,
+        &[],
+        vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
+        std::mem::size_of::<HandlePushConstantBuffer>(),
+    )
+}
+
+pub(crate) fn create_grid_pipeline_layout(device: &Device) -> Result<vk::PipelineLayout> {
+    create_graphics_pipeline_layout(
+        device,
+        &[],
+        vk::ShaderStageFlags::FRAGMENT,
+        std::mem::size_of::<GridPushConstantBuffer>(),
+    )
+}
+
+pub(crate) fn create_crosshair_pipeline_layout(device: &Device) -> Result<vk::PipelineLayout> {
+    create_graphics_pipeline_layout(
+        device,
+        &[],
+        vk::ShaderStageFlags::FRAGMENT,
+        std::mem::size_of::<CrosshairPushConstantBuffer>(),
+    )
+}
+
+pub(crate) fn create_label_pipeline_layout(
+    device: &Device,
+    descriptor_set_layout: vk::DescriptorSetLayout,
+) -> Result<vk::PipelineLayout> {
+    create_graphics_pipeline_layout(
+        device,
+        &[descriptor_set_layout],
+        vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
+        std::mem::size_of::<LabelPushConstantBuffer>(),
+    )
+}
+
+/// What a graphics pipeline is used within.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum PipelineTarget {
+    /// A subpass of a render pass, by index.
+    Subpass(vk::RenderPass, u32),
+    /// Dynamic rendering with a single color attachment of the format, and the view mask of
+    /// multiview rendering.
+    Rendering(vk::Format, u32),
+}
+
+/// Creates the pipeline drawing the vector difference arrows, as instanced line lists.
+pub(crate) fn create_vector_pipeline(
+    device: &Device,
+    cache: vk::PipelineCache,
+    target: PipelineTarget,
+    pipeline_layout: vk::PipelineLayout,
+) -> Result<vk::Pipeline> {
+    let shaders = PipelineShaders {
+        vert: spirv!("vectors_vert"),
+        frag: spirv!("vectors_frag"),
+        topology: vk::PrimitiveTopology::LINE_LIST,
+        specialization: &[],
+        blend: Blend::None,
+    };
+
+    create_graphics_pipeline(device, cache, target, pipeline_layout, &shaders)
+}
+
+/// Creates the pipeline drawing the divider handle, as a square blended over the comparison.
+pub(crate) fn create_handle_pipeline(
+    device: &Device,
+    cache: vk::PipelineCache,
+    target: PipelineTarget,
+    pipeline_layout: vk::PipelineLayout,
+) -> Result<vk::Pipeline> {
+    let shaders = PipelineShaders {
+        vert: spirv!("handle_vert"),
+        frag: spirv!("handle_frag"),
+        topology: vk::PrimitiveTopology::TRIANGLE_LIST,
+        specialization: &[],
+        blend: Blend::Alpha,
+    };
+
+    create_graphics_pipeline(device, cache, target, pipeline_layout, &shaders)
+}
+
+/// Creates the pipeline drawing the pixel grid, as a fullscreen triangle blended over the
+/// comparison.
+pub(crate) fn create_grid_pipeline(
+    device: &Device,
+    cache: vk::PipelineCache,
+    target: PipelineTarget,
+    pipeline_layout: vk::PipelineLayout,
+) -> Result<vk::Pipeline> {
+    let shaders = PipelineShaders {
+        blend: Blend::Alpha,
+        ..PipelineShaders::fullscreen(spirv!("grid"), &[])
+    };
+
+    create_graphics_pipeline(device, cache, target, pipeline_layout, &shaders)
+}
+
+/// Creates the pipeline drawing the crosshair, as a fullscreen triangle over the comparison.
+pub(crate) fn create_crosshair_pipeline(
+    device: &Device,
+    cache: vk::PipelineCache,
+    target: PipelineTarget,
+    pipeline_layout: vk::PipelineLayout,
+) -> Result<vk::Pipeline> {
+    let shaders = PipelineShaders {
+        blend: Blend::Alpha,
+        ..PipelineShaders::fullscreen(spirv!("crosshair"), &[])
+    };
+
+    create_graphics_pipeline(device, cache, target, pipeline_layout, &shaders)
+}
+
+/// Creates the pipeline drawing the input labels, as instanced glyph squares blended over
+/// the comparison.
+pub(crate) fn create_label_pipeline(
+    device: &Device,
+    cache: vk::PipelineCache,
+    target: PipelineTarget,
+    pipeline_layout: vk::PipelineLayout,
+) -> Result<vk::Pipeline> {
+    let shaders = PipelineShaders {
+        vert: spirv!("labels_vert"),
+        frag: spirv!("labels_frag"),
+        topology: vk::PrimitiveTopology::TRIANGLE_LIST,
+        specialization: &[],
+        blend: Blend::Alpha,
+    };
+
+    create_graphics_pipeline(device, cache, target, pipeline_layout, &shaders)
+}
+
+/// Creates the comparison pipeline of a single mode, which is baked into the fragment shader
+/// as a specialization constant along with the input mapping. With `multiview`, each eye
+/// samples inputs of its own. With `composite`, the comparison is blended over the
+/// attachment by the opacity in the blend constants. A custom `fragment_shader` replaces
+/// the built-in variant.
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn create_pipeline(
     device: &Device,
-    extent: &vk::Extent2D,
+    cache: vk::PipelineCache,
+    target: PipelineTarget,
+    pipeline_layout: vk::PipelineLayout,
+    variant: FragmentVariant,
+    mode: CompareMode,
+    multiview: bool,
+    composite: bool,
+    mapping: &InputMapping,
+    fragment_shader: Option<&[u8]>,
+) -> Result<vk::Pipeline> {
+    let specialization = mapping.specialization(mode);
+    let frag = fragment_shader.unwrap_or_else(|| comparison_shader(variant));
+    let shaders = PipelineShaders::fullscreen(frag, &specialization);
+    let shaders = match multiview {
+        true => PipelineShaders {
+            vert: spirv!("vert_multiview"),
+            ..shaders
+        },
+        false => shaders,
+    };
+    let shaders = match composite {
+        true => PipelineShaders {
+            blend: Blend::Opacity,
+            ..shaders
+        },
+        false => shaders,
+    };
+
+    create_graphics_pipeline(device, cache, target, pipeline_layout, &shaders)
+}
+
+/// How the sampled input values are mapped before they are compared, and how many inputs
+/// they are selected from.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct InputMapping<'a> {
+    pub(crate) depth_range: Option<DepthRange>,
+    pub(crate) integer: Option<&'a IntegerMapping>,
+    // The size of the input array, larger than `MAX_INPUTS` with bindless inputs.
+    pub(crate) array_size: u32,
+}
+
+impl InputMapping<'_> {
+    /// The values of the comparison shader's specialization constants, starting with the
+    /// mode.
+    fn specialization(&self, mode: CompareMode) -> Vec<u32> {
+        // The depth curve is 0 without a depth range, and the curve's index plus one with one.
+        let (depth_curve, depth_near, depth_far) = match self.depth_range {
+            Some(range) => (range.curve as u32 + 1, range.near, range.far),
+            None => (0, 0.0, 1.0),
+        };
+        // Normalized (0) or a palette (1), the scale of normalized values, and the palette.
+        let (integer_mapping, integer_scale, palette) = match self.integer {
+            Some(IntegerMapping::Palette(colors)) => (1, 1.0, colors.as_slice()),
+            Some(IntegerMapping::Normalize(max)) => (0, 1.0 / *max as f32, &[][..]),
+            None => (0, 1.0, &[][..]),
+        };
+
+        let mut specialization = vec![
+            mode as u32,
+            depth_curve,
+            depth_near.to_bits(),
+            depth_far.to_bits(),
+            integer_mapping,
+            integer_scale.to_bits(),
+            palette.len().max(1) as u32,
+        ];
+        specialization.extend(
+            (0..MAX_PALETTE_COLORS).map(|i| palette.get(i).map_or(u32::MAX, |c| c.pack_unorm())),
+        );
+        specialization.push(self.array_size);
+        specialization
+    }
+}
+
+/// The optional features compiled into the comparison's fragment shader. Each binds a
+/// descriptor set of its own, in this order after the inputs.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct FragmentVariant {
+    pub(crate) stats: bool,
+    pub(crate) color_transforms: bool,
+    pub(crate) lut: bool,
+    // Samples the inputs as unsigned integers, without color transforms and lookup tables.
+    pub(crate) integer: bool,
+}
+
+fn comparison_shader(variant: FragmentVariant) -> &'static [u8] {
+    // The stats variant writes to a storage buffer, which requires the
+    // fragmentStoresAndAtomics feature, so it's only used when asked for. The other
+    // variants read descriptors bound only when asked for.
+    if variant.integer {
+        return match variant.stats {
+            false => spirv!("frag_integer"),
+            true => spirv!("frag_stats_integer"),
+        };
+    }
+    match (variant.stats, variant.color_transforms, variant.lut) {
+        (false, false, false) => spirv!("frag"),
+        (true, false, false) => spirv!("frag_stats"),
+        (false, true, false) => spirv!("frag_color"),
+        (true, true, false) => spirv!("frag_stats_color"),
+        (false, false, true) => spirv!("frag_lut"),
+        (true, false, true) => spirv!("frag_stats_lut"),
+        (false, true, true) => spirv!("frag_color_lut"),
+        (true, true, true) => spirv!("frag_stats_color_lut"),
+    }
+}
+
+pub(crate) fn create_strip_pipeline(
+    device: &Device,
+    cache: vk::PipelineCache,
     render_pass: &vk::RenderPass,
     descriptor_set_layouts: &[vk::DescriptorSetLayout],
-    viewport: Option<vk::Viewport>,
 ) -> Result<(vk::PipelineLayout, vk::Pipeline)> {
-    let vert = include_bytes!("shaders/vert.spv");
-    let frag = include_bytes!("shaders/frag.spv");
+    let pipeline_layout = create_graphics_pipeline_layout(
+        device,
+        descriptor_set_layouts,
+        vk::ShaderStageFlags::FRAGMENT,
+        std::mem::size_of::<StripPushConstantBuffer>(),
+    )?;
+    let pipeline = create_graphics_pipeline(
+        device,
+        cache,
+        PipelineTarget::Subpass(*render_pass, 0),
+        pipeline_layout,
+        &PipelineShaders::fullscreen(spirv!("strip"), &[]),
+    )?;
+    Ok((pipeline_layout, pipeline))
+}
+
+/// The shaders of a graphics pipeline and the primitives they draw.
+struct PipelineShaders<'a> {
+    vert: &'a [u8],
+    frag: &'a [u8],
+    topology: vk::PrimitiveTopology,
+    // The values of the fragment shader's specialization constants, by id.
+    specialization: &'a [u32],
+    blend: Blend,
+}
+
+// How the output of a pipeline is combined with the attachment.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Blend {
+    // It replaces the attachment's content.
+    None,
+    // It is blended over the attachment by its alpha.
+    Alpha,
+    // It is blended over the attachment by the alpha of the blend constants, which are set
+    // when recording.
+    Opacity,
+}
+
+impl<'a> PipelineShaders<'a> {
+    /// A fullscreen triangle shaded by the given fragment shader.
+    fn fullscreen(frag: &'a [u8], specialization: &'a [u32]) -> Self {
+        Self {
+            vert: spirv!("vert"),
+            frag,
+            topology: vk::PrimitiveTopology::TRIANGLE_LIST,
+            specialization,
+            blend: Blend::None,
+        }
+    }
+}
+
+fn create_graphics_pipeline_layout(
+    device: &Device,
+    descriptor_set_layouts: &[vk::DescriptorSetLayout],
+    push_constant_stages: vk::ShaderStageFlags,
+    push_constant_size: usize,
+) -> Result<vk::PipelineLayout> {
+    let push_constant_ranges = [vk::PushConstantRange::builder()
+        .stage_flags(push_constant_stages)
+        .offset(0)
+        .size(push_constant_size as u32)
+        .build()];
+
+    let set_layouts = descriptor_set_layouts;
+    let layout_info = vk::PipelineLayoutCreateInfo::builder()
+        .set_layouts(set_layouts)
+        .push_constant_ranges(&push_constant_ranges)
+        .build();
+
+    let pipeline_layout = unsafe { device.create_pipeline_layout(&layout_info, None) }?;
+    Ok(pipeline_layout)
+}
 
-    let vert_module = create_shader_module(device, vert)?;
-    let frag_module = create_shader_module(device, frag)?;
+/// Creates a pipeline drawing with the given shaders within the target.
+fn create_graphics_pipeline(
+    device: &Device,
+    cache: vk::PipelineCache,
+    target: PipelineTarget,
+    pipeline_layout: vk::PipelineLayout,
+    shaders: &PipelineShaders,
+) -> Result<vk::Pipeline> {
+    let vert_module = create_shader_module(device, shaders.vert)?;
+    let frag_module = create_shader_module(device, shaders.frag)?;
 
     let vert_stage = vk::PipelineShaderStageCreateInfo::builder()
         .stage(vk::ShaderStageFlags::VERTEX)
@@ -23,44 +395,53 @@ pub(crate) fn create_pipeline(
         .name(b"main\0")
         .build();
 
-    let frag_stage = vk::PipelineShaderStageCreateInfo::builder()
+    let map_entries = (0..shaders.specialization.len())
+        .map(|id| {
+            vk::SpecializationMapEntry::builder()
+                .constant_id(id as u32)
+                .offset((id * std::mem::size_of::<u32>()) as u32)
+                .size(std::mem::size_of::<u32>())
+                .build()
+        })
+        .collect::<Vec<_>>();
+    let specialization_data = bytemuck::cast_slice::<u32, u8>(shaders.specialization);
+    let specialization_info = vk::SpecializationInfo::builder()
+        .map_entries(&map_entries)
+        .data(specialization_data)
+        .build();
+
+    let mut frag_stage = vk::PipelineShaderStageCreateInfo::builder()
         .stage(vk::ShaderStageFlags::FRAGMENT)
         .module(frag_module)
-        .name(b"main\0")
-        .build();
+        .name(b"main\0");
+    if !shaders.specialization.is_empty() {
+        frag_stage = frag_stage.specialization_info(&specialization_info);
+    }
+    let frag_stage = frag_stage.build();
 
     let input_assembly_state = vk::PipelineInputAssemblyStateCreateInfo::builder()
-        .topology(vk::PrimitiveTopology::TRIANGLE_LIST)
+        .topology(shaders.topology)
         .primitive_restart_enable(false)
         .build();
 
-    // Area of the framebuffer to render to. In our case the whole area.
-    let viewport = match viewport {
-        Some(vp) => vp,
-        None => vk::Viewport::builder()
-            .x(0.0)
-            .y(0.0)
-            .width(extent.width as f32)
-            .height(extent.height as f32)
-            .min_depth(0.0)
-            .max_depth(1.0)
-            .build(),
-    };
-
-    // Area of the framebuffer that fragments are allowed to affect. In our case the whole area.
-    let scissor = vk::Rect2D::builder()
-        .offset(vk::Offset2D { x: 0, y: 0 })
-        .extent(*extent)
-        .build();
-
-    let viewports = &[viewport];
-    let scissors = &[scissor];
+    // The viewport and scissor are set when recording, so that a single pipeline can render
+    // into outputs (or regions of outputs) of any size. Only their count matters here.
+    let viewports = &[vk::Viewport::default()];
+    let scissors = &[vk::Rect2D::default()];
 
     let viewport_state = vk::PipelineViewportStateCreateInfo::builder()
         .viewports(viewports)
         .scissors(scissors)
         .build();
 
+    let mut dynamic_states = vec![vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
+    if shaders.blend == Blend::Opacity {
+        dynamic_states.push(vk::DynamicState::BLEND_CONSTANTS);
+    }
+    let dynamic_state = vk::PipelineDynamicStateCreateInfo::builder()
+        .dynamic_states(&dynamic_states)
+        .build();
+
     // The rasterization state divides polygons into fragments (which end up being pixels on the screen)
     // and performs fragment culling - removing fragments that don't make it into the view.
     let rasterization_state = vk::PipelineRasterizationStateCreateInfo::builder()
@@ -78,14 +459,26 @@ pub(crate) fn create_pipeline(
         .rasterization_samples(vk::SampleCountFlags::_1)
         .build();
 
+    let (src_factor, dst_factor, src_alpha_factor) = match shaders.blend {
+        Blend::Opacity => (
+            vk::BlendFactor::CONSTANT_ALPHA,
+            vk::BlendFactor::ONE_MINUS_CONSTANT_ALPHA,
+            vk::BlendFactor::CONSTANT_ALPHA,
+        ),
+        Blend::None | Blend::Alpha => (
+            vk::BlendFactor::SRC_ALPHA,
+            vk::BlendFactor::ONE_MINUS_SRC_ALPHA,
+            vk::BlendFactor::ONE,
+        ),
+    };
     let attachment = vk::PipelineColorBlendAttachmentState::builder()
         .color_write_mask(vk::ColorComponentFlags::all())
-        .blend_enable(false)
-        .src_color_blend_factor(vk::BlendFactor::SRC_ALPHA)
-        .dst_color_blend_factor(vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
+        .blend_enable(shaders.blend != Blend::None)
+        .src_color_blend_factor(src_factor)
+        .dst_color_blend_factor(dst_factor)
         .color_blend_op(vk::BlendOp::ADD)
-        .src_alpha_blend_factor(vk::BlendFactor::ONE)
-        .dst_alpha_blend_factor(vk::BlendFactor::ZERO)
+        .src_alpha_blend_factor(src_alpha_factor)
+        .dst_alpha_blend_factor(dst_factor)
         .alpha_blend_op(vk::BlendOp::ADD)
         .build();
 
@@ -98,27 +491,25 @@ pub(crate) fn create_pipeline(
         .blend_constants([0.0, 0.0, 0.0, 0.0])
         .build();
 
-    let push_constant_ranges = [vk::PushConstantRange::builder()
-        .stage_flags(vk::ShaderStageFlags::FRAGMENT)
-        .offset(0)
-        .size(std::mem::size_of::<PushConstantBuffer>() as u32)
-        .build()];
-
-    let set_layouts = descriptor_set_layouts;
-    let layout_info = vk::PipelineLayoutCreateInfo::builder()
-        .set_layouts(set_layouts)
-        .push_constant_ranges(&push_constant_ranges)
-        .build();
-
-    let pipeline_layout = unsafe { device.create_pipeline_layout(&layout_info, None) }?;
-
     let vertex_input_state = vk::PipelineVertexInputStateCreateInfo::builder()
         .vertex_binding_descriptions(&[] as &[vk::VertexInputBindingDescription])
         .vertex_attribute_descriptions(&[] as &[vk::VertexInputAttributeDescription])
         .build();
 
+    let (render_pass, subpass, rendering_format, view_mask) = match target {
+        PipelineTarget::Subpass(render_pass, subpass) => (render_pass, subpass, None, 0),
+        PipelineTarget::Rendering(format, view_mask) => {
+            (vk::RenderPass::null(), 0, Some(format), view_mask)
+        }
+    };
+    let color_attachment_formats: Vec<vk::Format> = rendering_format.into_iter().collect();
+    let mut rendering_info = vk::PipelineRenderingCreateInfo::builder()
+        .view_mask(view_mask)
+        .color_attachment_formats(&color_attachment_formats)
+        .build();
+
     let stages = &[vert_stage, frag_stage];
-    let info = vk::GraphicsPipelineCreateInfo::builder()
+    let mut info = vk::GraphicsPipelineCreateInfo::builder()
         .stages(stages)
         .vertex_input_state(&vertex_input_state)
         .input_assembly_state(&input_assembly_state)
@@ -126,26 +517,155 @@ pub(crate) fn create_pipeline(
         .rasterization_state(&rasterization_state)
         .multisample_state(&multisample_state)
         .color_blend_state(&color_blend_state)
+        .dynamic_state(&dynamic_state)
         .layout(pipeline_layout)
-        .render_pass(*render_pass)
-        .subpass(0)
-        .build();
+        .render_pass(render_pass)
+        .subpass(subpass);
+
+    if rendering_format.is_some() {
+        info = info.push_next(&mut rendering_info);
+    }
+    let info = info.build();
 
     let pipeline = unsafe {
-        let pipeline = device
-            .create_graphics_pipelines(vk::PipelineCache::null(), &[info], None)?
-            .0[0];
+        let pipeline = device.create_graphics_pipelines(cache, &[info], None)?.0[0];
 
         device.destroy_shader_module(vert_module, None);
         device.destroy_shader_module(frag_module, None);
         pipeline
     };
 
+    Ok(pipeline)
+}
+
+pub(crate) fn create_metrics_pipeline(
+    device: &Device,
+    cache: vk::PipelineCache,
+    descriptor_set_layout: &vk::DescriptorSetLayout,
+) -> Result<(vk::PipelineLayout, vk::Pipeline)> {
+    create_compute_pipeline(
+        device,
+        cache,
+        spirv!("metrics"),
+        std::slice::from_ref(descriptor_set_layout),
+        std::mem::size_of::<MetricsPushConstantBuffer>(),
+    )
+}
+
+pub(crate) fn create_inspect_pipeline(
+    device: &Device,
+    cache: vk::PipelineCache,
+    descriptor_set_layouts: &[vk::DescriptorSetLayout],
+) -> Result<(vk::PipelineLayout, vk::Pipeline)> {
+    create_compute_pipeline(
+        device,
+        cache,
+        spirv!("inspect"),
+        descriptor_set_layouts,
+        std::mem::size_of::<InspectPushConstantBuffer>(),
+    )
+}
+
+pub(crate) fn create_frame_metrics_pipeline(
+    device: &Device,
+    cache: vk::PipelineCache,
+    descriptor_set_layouts: &[vk::DescriptorSetLayout],
+) -> Result<(vk::PipelineLayout, vk::Pipeline)> {
+    create_compute_pipeline(
+        device,
+        cache,
+        spirv!("metrics_frame"),
+        descriptor_set_layouts,
+        std::mem::size_of::<MetricsPushConstantBuffer>(),
+    )
+}
+
+pub(crate) fn create_histogram_pipeline(
+    device: &Device,
+    cache: vk::PipelineCache,
+    descriptor_set_layouts: &[vk::DescriptorSetLayout],
+) -> Result<(vk::PipelineLayout, vk::Pipeline)> {
+    create_compute_pipeline(
+        device,
+        cache,
+        spirv!("histogram"),
+        descriptor_set_layouts,
+        std::mem::size_of::<HistogramPushConstantBuffer>(),
+    )
+}
+
+pub(crate) fn create_ssim_pipeline(
+    device: &Device,
+    cache: vk::PipelineCache,
+    descriptor_set_layouts: &[vk::DescriptorSetLayout],
+) -> Result<(vk::PipelineLayout, vk::Pipeline)> {
+    create_compute_pipeline(
+        device,
+        cache,
+        spirv!("ssim"),
+        descriptor_set_layouts,
+        std::mem::size_of::<MetricsPushConstantBuffer>(),
+    )
+}
+
+fn create_compute_pipeline(
+    device: &Device,
+    cache: vk::PipelineCache,
+    comp: &[u8],
+    descriptor_set_layouts: &[vk::DescriptorSetLayout],
+    push_constant_size: usize,
+) -> Result<(vk::PipelineLayout, vk::Pipeline)> {
+    let comp_module = create_shader_module(device, comp)?;
+
+    let stage = vk::PipelineShaderStageCreateInfo::builder()
+        .stage(vk::ShaderStageFlags::COMPUTE)
+        .module(comp_module)
+        .name(b"main\0")
+        .build();
+
+    let push_constant_ranges = [vk::PushConstantRange::builder()
+        .stage_flags(vk::ShaderStageFlags::COMPUTE)
+        .offset(0)
+        .size(push_constant_size as u32)
+        .build()];
+
+    let layout_info = vk::PipelineLayoutCreateInfo::builder()
+        .set_layouts(descriptor_set_layouts)
+        .push_constant_ranges(&push_constant_ranges)
+        .build();
+
+    let pipeline_layout = unsafe { device.create_pipeline_layout(&layout_info, None) }?;
+
+    let info = vk::ComputePipelineCreateInfo::builder()
+        .stage(stage)
+        .layout(pipeline_layout)
+        .build();
+
+    let pipeline = unsafe {
+        let pipeline = device.create_compute_pipelines(cache, &[info], None)?.0[0];
+
+        device.destroy_shader_module(comp_module, None);
+        pipeline
+    };
+
     Ok((pipeline_layout, pipeline))
 }
 
+/// Creates a pipeline cache, starting out with data previously read from one. Data of
+/// another driver or device is ignored by the driver.
+pub(crate) fn create_pipeline_cache(
+    device: &Device,
+    initial_data: &[u8],
+) -> Result<vk::PipelineCache> {
+    let info = vk::PipelineCacheCreateInfo::builder()
+        .initial_data(initial_data)
+        .build();
+    Ok(unsafe { device.create_pipeline_cache(&info, None) }?)
+}
+
 fn create_shader_module(device: &Device, bytecode: &[u8]) -> Result<vk::ShaderModule> {
-    let bytecode = Bytecode::new(bytecode).unwrap();
+    let bytecode =
+        Bytecode::new(bytecode).map_err(|e| anyhow!("Invalid SPIR-V bytecode: {:?}", e))?;
     let info = vk::ShaderModuleCreateInfo::builder()
         .code_size(bytecode.code_size())
         .code(bytecode.code());
@@ -1,10 +1,204 @@
 use crate::Color;
+use crate::vulkan::descriptors::MAX_INPUTS;
+
+/// Renders the raw difference of the first two inputs, ignoring the mode.
+pub const FLAG_DIFFERENCE_OUTPUT: u32 = 1 << 0;
+/// Keeps the sign of the difference output.
+pub const FLAG_SIGNED_DIFFERENCE: u32 = 1 << 1;
+/// Paints non-finite input texels in the debug color.
+pub const FLAG_HIGHLIGHT_NON_FINITE: u32 = 1 << 2;
+/// Reads the base level texel under each coordinate instead of filtering.
+pub const FLAG_NEAREST: u32 = 1 << 3;
+/// Encodes the output to sRGB.
+pub const FLAG_ENCODE_SRGB: u32 = 1 << 4;
+/// Highlights the grid cell being drawn.
+pub const FLAG_HIGHLIGHTED: u32 = 1 << 5;
+/// Tone maps the shown inputs with Reinhard's operator.
+pub const FLAG_TONE_MAP_REINHARD: u32 = 1 << 6;
+/// Tone maps the shown inputs with the ACES filmic curve.
+pub const FLAG_TONE_MAP_ACES: u32 = 1 << 7;
+/// The flags above this shift hold a bit per input the lookup table is applied to.
+pub const LUT_INPUTS_SHIFT: u32 = 8;
+/// Fits the inputs within the output, keeping their aspect ratio.
+pub const FLAG_FIT_CONTAIN: u32 = 1 << 16;
+/// Fits the inputs to cover the output, keeping their aspect ratio.
+pub const FLAG_FIT_COVER: u32 = 1 << 17;
+/// Scales the other inputs to the extent of the first.
+pub const FLAG_EXTENT_SCALE_TO_FIRST: u32 = 1 << 18;
+/// Shows the other inputs at the pixel size of the first.
+pub const FLAG_EXTENT_CROP: u32 = 1 << 19;
+/// The flags above this shift hold a bit per input whose sampled values are sRGB encoded.
+pub const SRGB_INPUTS_SHIFT: u32 = 20;
+/// The flags above this shift hold the background pattern, see `Background::shader_params`.
+pub const BACKGROUND_SHIFT: u32 = 28;
+/// Composites the shown inputs over a checkerboard by their alpha.
+pub const FLAG_ALPHA_CHECKERBOARD: u32 = 1 << 30;
+/// Samples the inputs at a fixed mip level instead of biasing the one picked.
+pub const FLAG_FIXED_LOD: u32 = 1 << 31;
+/// The cell index holds the isolated channel above this shift, see `ChannelMask`.
+pub const CHANNEL_SHIFT: u32 = 4;
+/// The cell index holds the amount of inputs per eye with multiview above this shift.
+pub const EYE_INPUTS_SHIFT: u32 = 8;
+/// Set in the cell index to show the first two inputs in each other's place.
+pub const CELL_SWAP_INPUTS: u32 = 1 << 12;
+/// The cell index holds the element of the bindless input array holding the first input
+/// above this shift.
+pub const INPUT_BASE_SHIFT: u32 = 16;
 
 // The Push constant buffer's size must not exceed 128 bytes as it's one of the requirements of Vulkan.
+// The colors are packed to 8 bits per channel to fit, see `Color::pack_unorm`, and the input
+// encodings and background pattern are packed into the flags. The divider height and normal
+// are derived from the pixel size in the shader.
 #[repr(C)]
 #[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct PushConstantBuffer {
-    pub color: Color,
+    pub color: u32,
+    pub highlight_color: u32,
+    pub non_finite_color: u32,
+    pub background_color: u32,
+    pub alpha_checker_colors: [u32; 2],
+    pub pan: [f32; 2],
+    pub magnifier_center: [f32; 2],
+    // The per-channel threshold of the threshold mode, see `pack_half2`.
+    pub difference_threshold: [u32; 2],
     pub divider_pos: f32,
     pub divider_width: f32,
+    pub divider_angle: f32,
+    pub divider_pos_y: f32,
+    pub zoom: f32,
+    pub cell: u32,
+    pub exposure: f32,
+    pub flags: u32,
+    pub stats_threshold: f32,
+    pub mode_parameter: f32,
+    // The radius and zoom of the magnifier, see `pack_half2`.
+    pub magnifier: u32,
+    // The mip level or bias of the inputs, and the tile size of the alpha checkerboard, see
+    // `pack_half2`.
+    pub lod_checker_size: u32,
+    // The alignment offset of each input, see `pack_half2`.
+    pub input_offsets: [u32; MAX_INPUTS],
+}
+
+/// Packs two values as half floats, as read by `unpackHalf2x16`.
+pub fn pack_half2(values: [f32; 2]) -> u32 {
+    half_bits(values[0]) as u32 | ((half_bits(values[1]) as u32) << 16)
+}
+
+// The bits of the half float nearest to the value. Values beyond its range become infinite,
+// values too small for it zero.
+fn half_bits(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    if value.is_nan() {
+        return sign | 0x7e00;
+    }
+
+    let exponent = ((bits >> 23) & 0xff) as i32 - 127 + 15;
+    let mantissa = bits & 0x007f_ffff;
+    if exponent >= 31 {
+        return sign | 0x7c00;
+    }
+    if exponent <= 0 {
+        // Subnormal, with the implicit leading bit shifted into the mantissa.
+        if exponent < -10 {
+            return sign;
+        }
+        let mantissa = mantissa | 0x0080_0000;
+        let shift = (14 - exponent) as u32;
+        let rounding = (mantissa >> (shift - 1)) & 1;
+        return sign | ((mantissa >> shift) + rounding) as u16;
+    }
+
+    // Rounded to nearest. A carry into the exponent still yields the right value.
+    let half = ((exponent as u32) << 10) | (mantissa >> 13);
+    sign | (half + ((mantissa >> 12) & 1)) as u16
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct MetricsPushConstantBuffer {
+    pub first_partial: u32,
+    pub srgb_inputs: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct HistogramPushConstantBuffer {
+    pub srgb_inputs: u32,
+    pub histograms: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct InspectPushConstantBuffer {
+    pub uv: [f32; 2],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct StripPushConstantBuffer {
+    pub marker_color: Color,
+    pub gain: f32,
+    pub playhead: f32,
+    pub marker_width: f32,
+    pub cell_start: f32,
+    pub cell_end: f32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct HandlePushConstantBuffer {
+    pub color: Color,
+    pub center: [f32; 2],
+    pub viewport_size: [f32; 2],
+    pub normal: [f32; 2],
+    pub radius: f32,
+    pub shape: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct CrosshairPushConstantBuffer {
+    pub color: Color,
+    pub viewport_size: [f32; 2],
+    pub position: [f32; 2],
+    pub width: f32,
+    pub gap: f32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct GridPushConstantBuffer {
+    pub color: Color,
+    pub viewport_size: [f32; 2],
+    pub spacing: f32,
+    pub major_every: u32,
+    pub opacity: f32,
+    pub style: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct LabelPushConstantBuffer {
+    pub color: Color,
+    pub origin: [f32; 2],
+    pub viewport_size: [f32; 2],
+    pub glyph_size: [f32; 2],
+    pub atlas_grid: [u32; 2],
+    pub first_char: u32,
+    // The characters, four per word. See `labels::MAX_LABEL_LENGTH`.
+    pub text: [u32; 16],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct VectorPushConstantBuffer {
+    pub color: Color,
+    pub pan: [f32; 2],
+    pub viewport_size: [f32; 2],
+    pub zoom: f32,
+    pub scale: f32,
+    pub columns: u32,
+    pub rows: u32,
 }
@@ -1,27 +1,46 @@
 use anyhow::Result;
 use vulkanalia::prelude::v1_3::*;
 
-pub fn create_render_pass(
-    device: &Device,
+/// The only attachment of the comparator's render pass. Loading it keeps its content, which
+/// is expected in `final_layout`, as left by a previous pass.
+pub(crate) fn color_attachment(
     format: vk::Format,
     final_layout: vk::ImageLayout,
-) -> Result<vk::RenderPass> {
-    let color_attachment = vk::AttachmentDescription::builder()
+    load_op: vk::AttachmentLoadOp,
+) -> vk::AttachmentDescription {
+    let initial_layout = match load_op {
+        vk::AttachmentLoadOp::LOAD => final_layout,
+        _ => vk::ImageLayout::UNDEFINED,
+    };
+
+    vk::AttachmentDescription::builder()
         // Format of the color attachment should be same as the swapchain images.
         .format(format)
         // We don't multipsample in the comparator, as we're just copying pixels one by one.
         .samples(vk::SampleCountFlags::_1)
         // Defines what happens to the attachment at the start of rendering
-        .load_op(vk::AttachmentLoadOp::CLEAR)
+        .load_op(load_op)
         // What happens to the attachment after rendering
         .store_op(vk::AttachmentStoreOp::STORE)
         .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
         .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
         // Expected layout of the attachment before rendering.
-        .initial_layout(vk::ImageLayout::UNDEFINED)
+        .initial_layout(initial_layout)
         // Defines what the final layout of the attachment should be after rendering.
         .final_layout(final_layout)
-        .build();
+        .build()
+}
+
+/// Creates the comparator's render pass. A non-zero `view_mask` renders to the layers in it
+/// at once with multiview. Passes differing in `load_op` only are compatible.
+pub fn create_render_pass(
+    device: &Device,
+    format: vk::Format,
+    final_layout: vk::ImageLayout,
+    load_op: vk::AttachmentLoadOp,
+    view_mask: u32,
+) -> Result<vk::RenderPass> {
+    let color_attachment = color_attachment(format, final_layout, load_op);
 
     let color_attachment_ref = vk::AttachmentReference::builder()
         .attachment(0)
@@ -62,10 +81,35 @@ pub fn create_render_pass(
         // doesn't overwrite data that's still being processed from prior operations.
         .dst_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE);
 
-    let rp_info = vk::RenderPassCreateInfo::builder()
+    let view_masks = [view_mask];
+    let mut multiview_info = vk::RenderPassMultiviewCreateInfo::builder()
+        .view_masks(&view_masks)
+        .build();
+
+    let mut rp_info = vk::RenderPassCreateInfo::builder()
         .attachments(std::slice::from_ref(&color_attachment))
         .subpasses(std::slice::from_ref(&subpass))
         .dependencies(std::slice::from_ref(&dependency));
+    if view_mask != 0 {
+        rp_info = rp_info.push_next(&mut multiview_info);
+    }
 
     Ok(unsafe { device.create_render_pass(&rp_info, None) }?)
 }
+
+pub(crate) fn create_framebuffer(
+    device: &Device,
+    render_pass: vk::RenderPass,
+    image_view: vk::ImageView,
+    extent: vk::Extent2D,
+) -> Result<vk::Framebuffer> {
+    let attachments = &[image_view];
+    let framebuffer_info = vk::FramebufferCreateInfo::builder()
+        .render_pass(render_pass)
+        .attachments(attachments)
+        .width(extent.width)
+        .height(extent.height)
+        .layers(1);
+
+    Ok(unsafe { device.create_framebuffer(&framebuffer_info, None) }?)
+}
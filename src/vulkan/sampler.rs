@@ -1,18 +1,24 @@
 use anyhow::Result;
 use vulkanalia::prelude::v1_3::*;
 
-pub(crate) fn create_image_sampler(device: &Device) -> Result<vk::Sampler> {
+use crate::SamplerConfig;
+
+pub(crate) fn create_image_sampler(device: &Device, config: &SamplerConfig) -> Result<vk::Sampler> {
     let sampler_create_info = vk::SamplerCreateInfo::builder()
-        .address_mode_u(vk::SamplerAddressMode::CLAMP_TO_EDGE)
-        .address_mode_v(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+        .address_mode_u(config.address_mode_u)
+        .address_mode_v(config.address_mode_v)
         .address_mode_w(vk::SamplerAddressMode::CLAMP_TO_EDGE)
-        .anisotropy_enable(false)
-        .min_filter(vk::Filter::LINEAR)
-        .mag_filter(vk::Filter::LINEAR)
-        .border_color(vk::BorderColor::INT_OPAQUE_BLACK)
+        .anisotropy_enable(config.max_anisotropy.is_some())
+        .max_anisotropy(config.max_anisotropy.unwrap_or(1.0))
+        .min_filter(config.min_filter)
+        .mag_filter(config.mag_filter)
+        .border_color(config.border_color)
         .unnormalized_coordinates(false)
         .compare_enable(false)
-        .mipmap_mode(vk::SamplerMipmapMode::LINEAR)
+        .mipmap_mode(config.mipmap_mode)
+        .min_lod(config.min_lod)
+        .max_lod(config.max_lod)
+        .mip_lod_bias(config.mip_lod_bias)
         .build();
 
     let sampler = unsafe { device.create_sampler(&sampler_create_info, None)? };
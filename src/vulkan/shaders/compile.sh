@@ -2,7 +2,7 @@
 
 
 echo "compiling vertex shader..."
-if ! glslc shader.vert -o vert.spv; then
+if ! glslc shader.vert -o vert.spv || ! glslc -DMULTIVIEW shader.vert -o vert_multiview.spv; then
     echo "Error: Failed to compile vertex shader!"
     exit 1
 fi
@@ -14,3 +14,100 @@ if ! glslc shader.frag -o frag.spv; then
     exit 2
 fi
 echo "success!"
+
+echo "compiling metrics compute shader..."
+if ! glslc metrics.comp -o metrics.spv || ! glslc -DFRAME_INPUTS metrics.comp -o metrics_frame.spv; then
+    echo "Error: Failed to compile metrics compute shader!"
+    exit 3
+fi
+echo "success!"
+
+echo "compiling thumbnail strip fragment shader..."
+if ! glslc strip.frag -o strip.spv; then
+    echo "Error: Failed to compile thumbnail strip fragment shader!"
+    exit 4
+fi
+echo "success!"
+
+echo "compiling fragment shader with statistics..."
+if ! glslc -DSTATS shader.frag -o frag_stats.spv; then
+    echo "Error: Failed to compile fragment shader with statistics!"
+    exit 5
+fi
+echo "success!"
+
+echo "compiling fragment shaders with color transforms and lookup tables..."
+for variant in color lut color_lut stats_color stats_lut stats_color_lut; do
+    defines=""
+    case $variant in *stats*) defines="$defines -DSTATS";; esac
+    case $variant in *color*) defines="$defines -DCOLOR_TRANSFORMS";; esac
+    case $variant in *lut*) defines="$defines -DLUT";; esac
+    if ! glslc $defines shader.frag -o frag_$variant.spv; then
+        echo "Error: Failed to compile fragment shader variant $variant!"
+        exit 7
+    fi
+done
+echo "success!"
+
+echo "compiling fragment shaders with integer inputs..."
+if ! glslc -DINTEGER_INPUTS shader.frag -o frag_integer.spv || ! glslc -DINTEGER_INPUTS -DSTATS shader.frag -o frag_stats_integer.spv; then
+    echo "Error: Failed to compile fragment shaders with integer inputs!"
+    exit 15
+fi
+echo "success!"
+
+echo "compiling pixel inspector compute shader..."
+if ! glslc inspect.comp -o inspect.spv; then
+    echo "Error: Failed to compile pixel inspector compute shader!"
+    exit 8
+fi
+echo "success!"
+
+echo "compiling SSIM compute shader..."
+if ! glslc ssim.comp -o ssim.spv; then
+    echo "Error: Failed to compile SSIM compute shader!"
+    exit 9
+fi
+echo "success!"
+
+echo "compiling histogram compute shader..."
+if ! glslc histogram.comp -o histogram.spv; then
+    echo "Error: Failed to compile histogram compute shader!"
+    exit 10
+fi
+echo "success!"
+
+echo "compiling divider handle shaders..."
+if ! glslc handle.vert -o handle_vert.spv || ! glslc handle.frag -o handle_frag.spv; then
+    echo "Error: Failed to compile divider handle shaders!"
+    exit 11
+fi
+echo "success!"
+
+echo "compiling pixel grid fragment shader..."
+if ! glslc grid.frag -o grid.spv; then
+    echo "Error: Failed to compile pixel grid fragment shader!"
+    exit 13
+fi
+echo "success!"
+
+echo "compiling crosshair fragment shader..."
+if ! glslc crosshair.frag -o crosshair.spv; then
+    echo "Error: Failed to compile crosshair fragment shader!"
+    exit 14
+fi
+echo "success!"
+
+echo "compiling label shaders..."
+if ! glslc labels.vert -o labels_vert.spv || ! glslc labels.frag -o labels_frag.spv; then
+    echo "Error: Failed to compile label shaders!"
+    exit 12
+fi
+echo "success!"
+
+echo "compiling vector overlay shaders..."
+if ! glslc vectors.vert -o vectors_vert.spv || ! glslc vectors.frag -o vectors_frag.spv; then
+    echo "Error: Failed to compile vector overlay shaders!"
+    exit 6
+fi
+echo "success!"
@@ -0,0 +1,28 @@
+#version 460
+
+layout (push_constant) uniform CrosshairPC {
+    vec4 color;
+    vec2 viewport_size;
+    // The point the lines cross at, relative to the viewport.
+    vec2 position;
+    // The width of the lines, and the radius left clear around the point, in pixels.
+    float width;
+    float gap;
+} pc;
+
+layout (location = 0) in vec2 texPosition;
+
+layout (location = 0) out vec4 outColor;
+
+void main() {
+    vec2 position = texPosition * pc.viewport_size;
+    // Centered on the pixel holding the point, so that a line a pixel wide covers exactly
+    // its row and column.
+    vec2 offset = abs(position - (floor(pc.position * pc.viewport_size) + 0.5));
+
+    bool on_line = offset.x < pc.width / 2.0 || offset.y < pc.width / 2.0;
+    if (!on_line || max(offset.x, offset.y) < pc.gap) {
+        discard;
+    }
+    outColor = pc.color;
+}
@@ -0,0 +1,52 @@
+#version 460
+
+layout (push_constant) uniform GridPC {
+    vec4 color;
+    vec2 viewport_size;
+    // The distance between lines, in pixels.
+    float spacing;
+    // Every this many lines is a major line. 0 for none.
+    uint major_every;
+    float opacity;
+    uint style;
+} pc;
+
+layout (location = 0) in vec2 texPosition;
+
+layout (location = 0) out vec4 outColor;
+
+const uint STYLE_LINES = 0;
+const uint STYLE_RULERS = 1;
+
+// The depth of the rulers along the top and left edges, in pixels.
+const float RULER_SIZE = 12.0;
+
+void main() {
+    vec2 position = texPosition * pc.viewport_size;
+    // Lines are a pixel wide, on the first pixel of every interval.
+    bvec2 on_line = lessThan(mod(position, pc.spacing), vec2(1.0));
+    uvec2 line = uvec2(position / pc.spacing);
+    bvec2 major = bvec2(
+        pc.major_every != 0 && line.x % pc.major_every == 0,
+        pc.major_every != 0 && line.y % pc.major_every == 0
+    );
+
+    // Per axis, whether the pixel is on a line, and whether that line is major.
+    bool vertical = on_line.x;
+    bool horizontal = on_line.y;
+    if (pc.style == STYLE_RULERS) {
+        // Ticks down from the top edge and right from the left edge, major ones reaching
+        // across the whole ruler.
+        vertical = vertical && position.y < (major.x ? RULER_SIZE : RULER_SIZE / 2.0);
+        horizontal = horizontal && position.x < (major.y ? RULER_SIZE : RULER_SIZE / 2.0);
+    }
+
+    if (!vertical && !horizontal) {
+        discard;
+    }
+
+    // Major lines are drawn at twice the opacity of minor ones.
+    bool is_major = (vertical && major.x) || (horizontal && major.y);
+    float opacity = is_major ? min(pc.opacity * 2.0, 1.0) : pc.opacity;
+    outColor = vec4(pc.color.rgb, pc.color.a * opacity);
+}
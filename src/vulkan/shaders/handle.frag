@@ -0,0 +1,45 @@
+#version 460
+
+layout (push_constant) uniform HandlePC {
+    vec4 color;
+    vec2 center;
+    vec2 viewport_size;
+    vec2 normal;
+    float radius;
+    uint shape;
+} pc;
+
+layout (location = 0) in vec2 offset;
+
+layout (location = 0) out vec4 outColor;
+
+const uint SHAPE_CIRCLE = 0;
+const uint SHAPE_CHEVRONS = 1;
+
+float segment_distance(vec2 p, vec2 a, vec2 b) {
+    vec2 ab = b - a;
+    float t = clamp(dot(p - a, ab) / dot(ab, ab), 0.0, 1.0);
+    return length(p - a - ab * t);
+}
+
+void main() {
+    // The signed distance to the outline of the shape, in pixels.
+    float distance;
+    if (pc.shape == SHAPE_CHEVRONS) {
+        // Along and across the divider. Mirrored on both axes, so that a single stroke makes
+        // both arms of the chevrons on either side, pointing away from the divider.
+        vec2 p = abs(vec2(dot(offset, pc.normal), dot(offset, vec2(-pc.normal.y, pc.normal.x))));
+        vec2 tip = vec2(pc.radius * 0.8, 0.0);
+        vec2 arm = vec2(pc.radius * 0.4, pc.radius * 0.5);
+        float thickness = max(pc.radius * 0.15, 1.0);
+        distance = segment_distance(p, tip, arm) - thickness / 2.0;
+    } else {
+        distance = length(offset) - pc.radius;
+    }
+
+    float coverage = clamp(0.5 - distance, 0.0, 1.0);
+    if (coverage <= 0.0) {
+        discard;
+    }
+    outColor = vec4(pc.color.rgb, pc.color.a * coverage);
+}
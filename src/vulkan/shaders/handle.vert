@@ -0,0 +1,31 @@
+#version 460
+
+layout (push_constant) uniform HandlePC {
+    vec4 color;
+    // The center of the handle relative to the viewport, in pixels.
+    vec2 center;
+    vec2 viewport_size;
+    // The normal of the divider, which the chevrons point along.
+    vec2 normal;
+    float radius;
+    uint shape;
+} pc;
+
+// The offset from the center, in pixels.
+layout (location = 0) out vec2 offset;
+
+void main() {
+    // A square around the handle, as two triangles.
+    const vec2 corners[6] = vec2[](
+        vec2(-1.0, -1.0),
+        vec2(-1.0, 1.0),
+        vec2(1.0, -1.0),
+        vec2(1.0, -1.0),
+        vec2(-1.0, 1.0),
+        vec2(1.0, 1.0)
+    );
+
+    // A pixel of margin leaves room for the smoothed edge.
+    offset = corners[gl_VertexIndex] * (pc.radius + 1.0);
+    gl_Position = vec4((pc.center + offset) / pc.viewport_size * 2.0 - 1.0, 0.0, 1.0);
+}
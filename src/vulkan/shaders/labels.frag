@@ -0,0 +1,16 @@
+#version 460
+
+layout (set = 0, binding = 0) uniform sampler2D atlas;
+
+layout (push_constant) uniform LabelPC {
+    vec4 color;
+} pc;
+
+layout (location = 0) in vec2 uv;
+
+layout (location = 0) out vec4 outColor;
+
+void main() {
+    // The coverage of the glyph is in the red channel.
+    outColor = vec4(pc.color.rgb, pc.color.a * texture(atlas, uv).r);
+}
@@ -0,0 +1,42 @@
+#version 460
+
+layout (push_constant) uniform LabelPC {
+    vec4 color;
+    // The top left corner of the label relative to the viewport, in pixels.
+    vec2 origin;
+    vec2 viewport_size;
+    vec2 glyph_size;
+    // The amount of cells per row and column of the atlas.
+    uvec2 atlas_grid;
+    // The character of the first cell.
+    uint first_char;
+    // The characters of the label, four per word, the first in the lowest byte.
+    uint text[16];
+} pc;
+
+layout (location = 0) out vec2 uv;
+
+void main() {
+    // A square per glyph instance, as two triangles.
+    const vec2 corners[6] = vec2[](
+        vec2(0.0, 0.0),
+        vec2(0.0, 1.0),
+        vec2(1.0, 0.0),
+        vec2(1.0, 0.0),
+        vec2(0.0, 1.0),
+        vec2(1.0, 1.0)
+    );
+
+    uint glyph = gl_InstanceIndex;
+    uint character = (pc.text[glyph / 4] >> (8 * (glyph % 4))) & 0xff;
+    // Characters without a cell collapse to a point, which draws nothing.
+    vec2 corner = corners[gl_VertexIndex];
+    uint cell = character - pc.first_char;
+    if (character < pc.first_char || cell >= pc.atlas_grid.x * pc.atlas_grid.y) {
+        corner = vec2(0.0);
+    }
+
+    uv = (vec2(cell % pc.atlas_grid.x, cell / pc.atlas_grid.x) + corner) / vec2(pc.atlas_grid);
+    vec2 position = pc.origin + (vec2(glyph, 0.0) + corner) * pc.glyph_size;
+    gl_Position = vec4(position / pc.viewport_size * 2.0 - 1.0, 0.0, 1.0);
+}
@@ -1,26 +1,774 @@
 #version 460
 
-layout (binding = 0) uniform sampler2D leftImage;
-layout (binding = 1) uniform sampler2D rightImage;
+// The size of the input array, larger with bindless inputs, which only bind some elements.
+layout (constant_id = 23) const uint INPUT_ARRAY_SIZE = 8;
+
+// The inputs repeat when fewer than eight are bound, so every element is valid.
+#ifdef INTEGER_INPUTS
+layout (binding = 0) uniform usampler2D inputs[INPUT_ARRAY_SIZE];
+#else
+layout (binding = 0) uniform sampler2D inputs[INPUT_ARRAY_SIZE];
+#endif
+
+const uint MODE_WIPE = 0;
+const uint MODE_QUAD = 1;
+const uint MODE_GRID = 2;
+const uint MODE_EXACT = 3;
+const uint MODE_ONION = 4;
+const uint MODE_DIFFERENCE = 5;
+const uint MODE_CHECKERBOARD = 6;
+const uint MODE_FLICKER = 7;
+const uint MODE_SPLIT = 8;
+const uint MODE_THRESHOLD = 9;
+const uint MODE_DELTA_E = 10;
+const uint MODE_MASK = 11;
+
+// Every mode is a pipeline of its own, so that the unused modes are compiled out.
+layout (constant_id = 0) const uint MODE = MODE_WIPE;
+
+const uint DEPTH_OFF = 0;
+const uint DEPTH_LINEAR = 1;
+const uint DEPTH_LOGARITHMIC = 2;
+
+// Depth inputs are remapped from the range to grayscale, see `DepthRange`.
+layout (constant_id = 1) const uint DEPTH_CURVE = DEPTH_OFF;
+layout (constant_id = 2) const float DEPTH_NEAR = 0.0;
+layout (constant_id = 3) const float DEPTH_FAR = 1.0;
+
+const uint INTEGER_NORMALIZE = 0;
+const uint INTEGER_PALETTE = 1;
+
+// Integer inputs are mapped to colors, see `IntegerMapping`.
+layout (constant_id = 4) const uint INTEGER_MAPPING = INTEGER_NORMALIZE;
+layout (constant_id = 5) const float INTEGER_SCALE = 1.0;
+layout (constant_id = 6) const uint PALETTE_SIZE = 1;
+layout (constant_id = 7) const uint PALETTE_0 = 0xffffffffu;
+layout (constant_id = 8) const uint PALETTE_1 = 0xffffffffu;
+layout (constant_id = 9) const uint PALETTE_2 = 0xffffffffu;
+layout (constant_id = 10) const uint PALETTE_3 = 0xffffffffu;
+layout (constant_id = 11) const uint PALETTE_4 = 0xffffffffu;
+layout (constant_id = 12) const uint PALETTE_5 = 0xffffffffu;
+layout (constant_id = 13) const uint PALETTE_6 = 0xffffffffu;
+layout (constant_id = 14) const uint PALETTE_7 = 0xffffffffu;
+layout (constant_id = 15) const uint PALETTE_8 = 0xffffffffu;
+layout (constant_id = 16) const uint PALETTE_9 = 0xffffffffu;
+layout (constant_id = 17) const uint PALETTE_10 = 0xffffffffu;
+layout (constant_id = 18) const uint PALETTE_11 = 0xffffffffu;
+layout (constant_id = 19) const uint PALETTE_12 = 0xffffffffu;
+layout (constant_id = 20) const uint PALETTE_13 = 0xffffffffu;
+layout (constant_id = 21) const uint PALETTE_14 = 0xffffffffu;
+layout (constant_id = 22) const uint PALETTE_15 = 0xffffffffu;
+
+const uint BACKGROUND_CLAMP = 0;
+const uint BACKGROUND_SOLID = 1;
+const uint BACKGROUND_HATCHED = 2;
+
+const uint FLAG_DIFFERENCE_OUTPUT = 1 << 0;
+const uint FLAG_SIGNED_DIFFERENCE = 1 << 1;
+const uint FLAG_HIGHLIGHT_NON_FINITE = 1 << 2;
+const uint FLAG_NEAREST = 1 << 3;
+const uint FLAG_ENCODE_SRGB = 1 << 4;
+const uint FLAG_HIGHLIGHTED = 1 << 5;
+const uint FLAG_TONE_MAP_REINHARD = 1 << 6;
+const uint FLAG_TONE_MAP_ACES = 1 << 7;
+// The flags above hold a bit per input the lookup table is applied to.
+const uint LUT_INPUTS_SHIFT = 8;
+const uint FLAG_FIT_CONTAIN = 1 << 16;
+const uint FLAG_FIT_COVER = 1 << 17;
+const uint FLAG_EXTENT_SCALE_TO_FIRST = 1 << 18;
+const uint FLAG_EXTENT_CROP = 1 << 19;
+// The flags above hold a bit per input whose sampled values are sRGB encoded.
+const uint SRGB_INPUTS_SHIFT = 20;
+// The flags above hold what is shown where zoom and pan expose areas outside the inputs.
+const uint BACKGROUND_SHIFT = 28;
+const uint FLAG_ALPHA_CHECKERBOARD = 1 << 30;
+const uint FLAG_FIXED_LOD = 1u << 31;
+
+const uint CHANNEL_ALL = 0;
+const uint CHANNEL_ALPHA = 4;
+const uint CHANNEL_LUMA = 5;
+// The cell index holds the isolated channel above this shift.
+const uint CHANNEL_SHIFT = 4;
+// And the amount of inputs per eye with multiview above this one.
+const uint EYE_INPUTS_SHIFT = 8;
+// Set in the cell index to show the first two inputs in each other's place.
+const uint CELL_SWAP_INPUTS = 1 << 12;
+// And the element of the bindless input array holding the first input above this shift.
+const uint INPUT_BASE_SHIFT = 16;
 
 layout (push_constant) uniform ComparatorPC {
     // Sort the types descending by size to avoid alignment issues
-    vec4 divider_color;
+    // The colors are packed to 8 bits per channel, read with unpackUnorm4x8.
+    uint divider_color;
+    uint highlight_color;
+    uint non_finite_color;
+    uint background_color;
+    // The two colors of the checkerboard behind transparent inputs.
+    uvec2 alpha_checker_colors;
+    // Offset of the sampled area, in texture coordinates.
+    vec2 pan;
+    // The center of the magnifier, in framebuffer pixels.
+    vec2 magnifier_center;
+    // The per-channel threshold of the threshold mode, as four half floats.
+    uvec2 difference_threshold;
     float divider;
     float divider_width;
+    // The clockwise angle of the wipe divider from vertical, in radians.
+    float divider_angle;
+    // The horizontal divider of the quad mode.
+    float divider_y;
+    float zoom;
+    // The grid cell or split strip being drawn, the isolated channel above CHANNEL_SHIFT,
+    // the amount of inputs per eye above EYE_INPUTS_SHIFT, CELL_SWAP_INPUTS, and the input
+    // base above INPUT_BASE_SHIFT, see cell_index.
+    uint cell;
+    // The factor the shown inputs are scaled by before tone mapping.
+    float exposure;
+    uint flags;
+    // Differences above this count towards the statistics.
+    float stats_threshold;
+    // The blend factor of the onion skin, the gain of the difference, the tile size of the
+    // checkerboard in pixels, the shown input of the flicker, the brightness of the pixels
+    // within the threshold, or the Delta E shown as white.
+    float mode_parameter;
+    // The radius of the magnifier in pixels, zero without one, and its zoom, as two half
+    // floats.
+    uint magnifier;
+    // The mip level the inputs are sampled at with FLAG_FIXED_LOD, or else the bias of the
+    // level picked, and the edge length of the checkerboard tiles behind transparent
+    // inputs in pixels, as two half floats.
+    uint lod_checker_size;
+    // The offset of each input in its pixels, as two half floats.
+    uint input_offsets[8];
 } pc;
 
+// The eye being drawn with multiview, zero otherwise.
+layout (location = 1) flat in uint view;
+
+// The binding of the first input of the eye being drawn. With multiview, the inputs of the
+// right eye follow those of the left.
+uint eye_base() {
+    return view * ((pc.cell >> EYE_INPUTS_SHIFT) & 0xfu);
+}
+
+// The element of the bindless input array holding the first input.
+uint input_base() {
+    return pc.cell >> INPUT_BASE_SHIFT;
+}
+
+// The binding of an input of the eye being drawn. Swapping exchanges the bindings of the
+// first two, so that everything set per input moves along with its image.
+uint eye_input(uint index) {
+    if ((pc.cell & CELL_SWAP_INPUTS) != 0 && index < 2) {
+        index ^= 1u;
+    }
+    return eye_base() + index;
+}
+
+bool is_non_finite(vec4 color) {
+    return any(isnan(color)) || any(isinf(color));
+}
+
+#ifdef COLOR_TRANSFORMS
+// Follows the statistics buffer when there is one.
+#ifdef STATS
+layout (set = 2, binding = 0) uniform ColorTransforms {
+#else
+layout (set = 1, binding = 0) uniform ColorTransforms {
+#endif
+    mat4 transforms[8];
+} color_transforms;
+#endif
+
+#ifdef LUT
+// Follows the other optional sets.
+#if defined(STATS) && defined(COLOR_TRANSFORMS)
+layout (set = 3, binding = 0) uniform sampler3D lut;
+#elif defined(STATS) || defined(COLOR_TRANSFORMS)
+layout (set = 2, binding = 0) uniform sampler3D lut;
+#else
+layout (set = 1, binding = 0) uniform sampler3D lut;
+#endif
+
+// Looks the color up between the centers of the outermost entries.
+vec3 apply_lut(vec3 color) {
+    float size = float(textureSize(lut, 0).x);
+    vec3 uvw = (clamp(color, 0.0, 1.0) * (size - 1.0) + 0.5) / size;
+    return texture(lut, uvw).rgb;
+}
+#endif
+
+// Normalizes a value sampled from an input to linear, the working space of all modes, and
+// applies the input's color transform and lookup table.
+vec4 decode_input(uint index, vec4 color) {
+    if (DEPTH_CURVE != DEPTH_OFF) {
+        float depth = DEPTH_CURVE == DEPTH_LOGARITHMIC
+            ? log(color.r / DEPTH_NEAR) / log(DEPTH_FAR / DEPTH_NEAR)
+            : (color.r - DEPTH_NEAR) / (DEPTH_FAR - DEPTH_NEAR);
+        color = vec4(vec3(clamp(depth, 0.0, 1.0)), 1.0);
+    }
+    if ((pc.flags & (1u << (SRGB_INPUTS_SHIFT + index))) != 0) {
+        bvec3 curved = greaterThan(color.rgb, vec3(0.04045));
+        color.rgb = mix(color.rgb / 12.92, pow((color.rgb + 0.055) / 1.055, vec3(2.4)), curved);
+    }
+#ifdef COLOR_TRANSFORMS
+    color.rgb = (color_transforms.transforms[index] * vec4(color.rgb, 1.0)).rgb;
+#endif
+#ifdef LUT
+    if ((pc.flags & (1u << (LUT_INPUTS_SHIFT + index))) != 0) {
+        color.rgb = apply_lut(color.rgb);
+    }
+#endif
+    return color;
+}
+
+// Encodes a linear output value to sRGB, for outputs the hardware doesn't encode.
+vec4 encode_output(vec4 color) {
+    vec3 clamped = clamp(color.rgb, 0.0, 1.0);
+    bvec3 curved = greaterThan(clamped, vec3(0.0031308));
+    vec3 encoded = mix(clamped * 12.92, 1.055 * pow(clamped, vec3(1.0 / 2.4)) - 0.055, curved);
+    return vec4(encoded, color.a);
+}
+
+// The change of the output position per pixel.
+vec2 pixel_size;
+
+// The divider width relative to the output height, for horizontal dividers.
+float divider_height() {
+    return pc.divider_width * abs(pixel_size.y / pixel_size.x);
+}
+
+#ifdef INTEGER_INPUTS
+vec4 integer_color(uvec4 value) {
+    if (INTEGER_MAPPING == INTEGER_PALETTE) {
+        uint palette[16] = uint[](
+            PALETTE_0, PALETTE_1, PALETTE_2, PALETTE_3,
+            PALETTE_4, PALETTE_5, PALETTE_6, PALETTE_7,
+            PALETTE_8, PALETTE_9, PALETTE_10, PALETTE_11,
+            PALETTE_12, PALETTE_13, PALETTE_14, PALETTE_15
+        );
+        return unpackUnorm4x8(palette[value.r % PALETTE_SIZE]);
+    }
+    return vec4(vec3(value.rgb) * INTEGER_SCALE, 1.0);
+}
+#define INPUT_COLOR(value) integer_color(value)
+#else
+#define INPUT_COLOR(value) (value)
+#endif
+
+// Samplers with a YCbCr conversion may only be indexed by constants, so the inputs are
+// selected by a switch rather than indexed directly. Bindless inputs, which have no such
+// samplers, are indexed from the input base on.
+#define SELECT_INPUT(index, ACCESS) \
+    if (INPUT_ARRAY_SIZE > 8) { \
+        return ACCESS(inputs[input_base() + index]); \
+    } \
+    switch (index) { \
+    case 1u: return ACCESS(inputs[1]); \
+    case 2u: return ACCESS(inputs[2]); \
+    case 3u: return ACCESS(inputs[3]); \
+    case 4u: return ACCESS(inputs[4]); \
+    case 5u: return ACCESS(inputs[5]); \
+    case 6u: return ACCESS(inputs[6]); \
+    case 7u: return ACCESS(inputs[7]); \
+    default: return ACCESS(inputs[0]); \
+    }
+
+ivec2 input_size(uint index, int level) {
+#define ACCESS(input) textureSize(input, level)
+    SELECT_INPUT(index, ACCESS)
+#undef ACCESS
+}
+
+int input_levels(uint index) {
+#define ACCESS(input) textureQueryLevels(input)
+    SELECT_INPUT(index, ACCESS)
+#undef ACCESS
+}
+
+vec4 input_texel(uint index, ivec2 texel, int level) {
+#define ACCESS(input) INPUT_COLOR(texelFetch(input, texel, level))
+    SELECT_INPUT(index, ACCESS)
+#undef ACCESS
+}
+
+vec4 input_texture(uint index, vec2 uv, float bias) {
+#define ACCESS(input) INPUT_COLOR(texture(input, uv, bias))
+    SELECT_INPUT(index, ACCESS)
+#undef ACCESS
+}
+
+vec4 input_texture_lod(uint index, vec2 uv, float lod) {
+#define ACCESS(input) INPUT_COLOR(textureLod(input, uv, lod))
+    SELECT_INPUT(index, ACCESS)
+#undef ACCESS
+}
+
+// Maps a coordinate of the output to the input at the binding, fit by the input's aspect
+// ratio and shifted by its alignment offset.
+vec2 align_input(uint index, vec2 uv) {
+    vec2 size = vec2(input_size(index, 0));
+    // All inputs take the first one's extent, unless each is scaled to the output on its own.
+    vec2 fit_size = (pc.flags & (FLAG_EXTENT_SCALE_TO_FIRST | FLAG_EXTENT_CROP)) != 0
+        ? vec2(input_size(eye_base(), 0))
+        : size;
+    if ((pc.flags & (FLAG_FIT_CONTAIN | FLAG_FIT_COVER)) != 0) {
+        // The output's aspect ratio relative to the input's.
+        float ratio = (abs(pixel_size.y) / abs(pixel_size.x)) / (fit_size.x / fit_size.y);
+        vec2 scale = (pc.flags & FLAG_FIT_CONTAIN) != 0
+            ? max(vec2(ratio, 1.0 / ratio), vec2(1.0))
+            : min(vec2(ratio, 1.0 / ratio), vec2(1.0));
+        uv = (uv - 0.5) * scale + 0.5;
+    }
+    if ((pc.flags & FLAG_EXTENT_CROP) != 0) {
+        // In pixels of the first input, so that larger inputs are cropped and smaller ones
+        // padded.
+        uv *= fit_size / size;
+    }
+    return uv - unpackHalf2x16(pc.input_offsets[index]) / size;
+}
+
+vec4 fetch_input(uint index, vec2 uv) {
+    index = eye_input(index);
+    uv = align_input(index, uv);
+    float lod = unpackHalf2x16(pc.lod_checker_size).x;
+    bool fixed_lod = (pc.flags & FLAG_FIXED_LOD) != 0;
+    if ((pc.flags & FLAG_NEAREST) != 0) {
+        // The texel of the base level, or of the nearest fixed one, under the coordinate,
+        // unfiltered.
+        int level = fixed_lod
+            ? clamp(int(round(lod)), 0, input_levels(index) - 1)
+            : 0;
+        ivec2 size = input_size(index, level);
+        ivec2 texel = clamp(ivec2(floor(uv * vec2(size))), ivec2(0), size - 1);
+        return decode_input(index, input_texel(index, texel, level));
+    }
+    if (fixed_lod) {
+        return decode_input(index, input_texture_lod(index, uv, lod));
+    }
+    return decode_input(index, input_texture(index, uv, lod));
+}
+
+// Whether the texels of the first two inputs under uv differ in any bit. They are fetched
+// unfiltered and compared as integers, so that no difference is too small to count.
+bool texels_differ(vec2 uv) {
+    uint index_a = eye_input(0);
+    uint index_b = eye_input(1);
+    ivec2 size_a = input_size(index_a, 0);
+    ivec2 size_b = input_size(index_b, 0);
+    // Only cropped inputs of different extents have corresponding texels.
+    if (size_a != size_b && (pc.flags & FLAG_EXTENT_CROP) == 0) {
+        return true;
+    }
+
+    vec2 uv_a = align_input(index_a, uv);
+    vec2 uv_b = align_input(index_b, uv);
+    ivec2 texel_a = clamp(ivec2(floor(uv_a * vec2(size_a))), ivec2(0), size_a - 1);
+    ivec2 texel_b = clamp(ivec2(floor(uv_b * vec2(size_b))), ivec2(0), size_b - 1);
+    uvec4 a = floatBitsToUint(decode_input(index_a, input_texel(index_a, texel_a, 0)));
+    uvec4 b = floatBitsToUint(decode_input(index_b, input_texel(index_b, texel_b, 0)));
+    return any(notEqual(a, b));
+}
+
+#ifdef STATS
+// Accumulated over the whole pass. The maximum holds the bits of a non-negative float,
+// which order like the float itself.
+layout (set = 1, binding = 0) buffer Stats {
+    uint above_threshold;
+    uint max_difference;
+    uint non_finite;
+    uint mismatches;
+} stats;
+
+void accumulate_stats(vec2 uv) {
+    vec4 a = fetch_input(0, uv);
+    vec4 b = fetch_input(1, uv);
+    // Non-finite values are counted on their own, they'd poison the maximum.
+    if (is_non_finite(a) || is_non_finite(b)) {
+        atomicAdd(stats.non_finite, 1);
+        return;
+    }
+
+    vec4 difference = abs(a - b);
+    float m = max(max(difference.r, difference.g), max(difference.b, difference.a));
+    if (m > pc.stats_threshold) {
+        atomicAdd(stats.above_threshold, 1);
+    }
+    if (m > 0.0) {
+        atomicMax(stats.max_difference, floatBitsToUint(m));
+    }
+}
+#endif
+
 layout (location = 0) in vec2 texPosition;
 
 layout (location = 0) out vec4 outColor;
 
+// The position within the output the modes draw, which the magnifier moves towards its
+// center.
+vec2 position;
+
+uint background_pattern() {
+    return (pc.flags >> BACKGROUND_SHIFT) & 3u;
+}
+
+bool out_of_bounds(vec2 uv) {
+    return background_pattern() != BACKGROUND_CLAMP &&
+        (any(lessThan(uv, vec2(0.0))) || any(greaterThan(uv, vec2(1.0))));
+}
+
+vec4 background() {
+    // Diagonal stripes in screen space, 8 pixels wide, so they never look like content.
+    if (background_pattern() == BACKGROUND_HATCHED && mod(gl_FragCoord.x + gl_FragCoord.y, 16.0) < 8.0) {
+        vec4 color = unpackUnorm4x8(pc.background_color);
+        return vec4(color.rgb * 0.5, color.a);
+    }
+    return unpackUnorm4x8(pc.background_color);
+}
+
+// Brings the values of a shown input into the displayable range.
+vec4 tone_map(vec4 color) {
+    vec3 c = color.rgb * pc.exposure;
+    if ((pc.flags & FLAG_TONE_MAP_REINHARD) != 0) {
+        c = c / (1.0 + c);
+    } else if ((pc.flags & FLAG_TONE_MAP_ACES) != 0) {
+        // Narkowicz's fit of the ACES filmic curve.
+        c = clamp((c * (2.51 * c + 0.03)) / (c * (2.43 * c + 0.59) + 0.14), 0.0, 1.0);
+    }
+    return vec4(c, color.a);
+}
+
+// The grid cell or split strip being drawn.
+uint cell_index() {
+    return pc.cell & ((1u << CHANNEL_SHIFT) - 1);
+}
+
+// Shows only the isolated channel, as grayscale. Color channels keep the alpha.
+vec4 isolate_channel(vec4 color) {
+    uint channel = (pc.cell >> CHANNEL_SHIFT) & 0xfu;
+    if (channel == CHANNEL_ALL) {
+        return color;
+    } else if (channel == CHANNEL_ALPHA) {
+        return vec4(vec3(color.a), 1.0);
+    }
+    float value = channel == CHANNEL_LUMA ? dot(color.rgb, vec3(0.2126, 0.7152, 0.0722))
+        : color[channel - 1];
+    return vec4(vec3(value), color.a);
+}
+
+// Samples an input, painting non-finite texels in the debug color when asked to.
+vec4 sample_input(uint index, vec2 uv) {
+    if (out_of_bounds(align_input(eye_input(index), uv))) {
+        return background();
+    }
+
+    vec4 color = fetch_input(index, uv);
+    if ((pc.flags & FLAG_HIGHLIGHT_NON_FINITE) != 0 && is_non_finite(color)) {
+        return unpackUnorm4x8(pc.non_finite_color);
+    }
+    color = isolate_channel(tone_map(color));
+    // In screen space, so that the tiles line up across the divider.
+    if ((pc.flags & FLAG_ALPHA_CHECKERBOARD) != 0) {
+        float size = unpackHalf2x16(pc.lod_checker_size).y;
+        ivec2 tile = ivec2(floor(gl_FragCoord.xy / max(size, 1.0)));
+        vec4 checker = unpackUnorm4x8((tile.x + tile.y) % 2 == 0 ?
+            pc.alpha_checker_colors.x : pc.alpha_checker_colors.y);
+        return vec4(mix(checker.rgb, color.rgb, clamp(color.a, 0.0, 1.0)), 1.0);
+    }
+    return color;
+}
+
+// Zooms around the center of the output, shared by all inputs.
+vec2 sample_position() {
+    return (position - 0.5) / pc.zoom + 0.5 + pc.pan;
+}
+
+vec4 wipe() {
+    // The divider passes through the divider position on both axes, at any angle. The
+    // normal is scaled by the aspect ratio, so that distances along it are relative to the
+    // output width like the divider width.
+    vec2 normal = vec2(cos(pc.divider_angle),
+        sin(pc.divider_angle) * abs(pixel_size.x / pixel_size.y));
+    float distance = dot(position - vec2(pc.divider), normal);
+
+    vec2 uv = sample_position();
+    if (distance < -pc.divider_width / 2.0) {
+        return sample_input(0, uv);
+    } else if (distance > pc.divider_width / 2.0) {
+        return sample_input(1, uv);
+    } else {
+        return unpackUnorm4x8(pc.divider_color);
+    }
+}
+
+vec4 quad() {
+    // Both dividers are drawn on top, so their intersection can be dragged as a whole.
+    if (abs(position.x - pc.divider) <= pc.divider_width / 2.0 ||
+        abs(position.y - pc.divider_y) <= divider_height() / 2.0) {
+        return unpackUnorm4x8(pc.divider_color);
+    }
+
+    vec2 uv = sample_position();
+    bool left = position.x < pc.divider;
+    if (position.y < pc.divider_y) {
+        return left ? sample_input(0, uv) : sample_input(1, uv);
+    } else {
+        return left ? sample_input(2, uv) : sample_input(3, uv);
+    }
+}
+
+vec4 exact() {
+    vec2 uv = sample_position();
+    if (out_of_bounds(uv)) {
+        return background();
+    }
+    if (texels_differ(uv)) {
+        return unpackUnorm4x8(pc.highlight_color);
+    }
+    // Matching pixels are dimmed, so that they give context without drawing attention.
+    return vec4(sample_input(0, uv).rgb * 0.25, 1.0);
+}
+
+vec4 threshold() {
+    vec2 uv = sample_position();
+    if (out_of_bounds(uv)) {
+        return background();
+    }
+
+    // Non-finite values exceed any threshold, as their difference is meaningless.
+    vec4 a = fetch_input(0, uv);
+    vec4 b = fetch_input(1, uv);
+    vec4 limit = vec4(unpackHalf2x16(pc.difference_threshold.x),
+        unpackHalf2x16(pc.difference_threshold.y));
+    if (is_non_finite(a) || is_non_finite(b) || any(greaterThan(abs(a - b), limit))) {
+        return unpackUnorm4x8(pc.highlight_color);
+    }
+    vec4 color = sample_input(0, uv);
+    return vec4(color.rgb * pc.mode_parameter, color.a);
+}
+
+// CIE L*a*b* of a linear sRGB color, relative to the D65 white point.
+vec3 linear_srgb_to_lab(vec3 rgb) {
+    const mat3 to_xyz = mat3(
+        0.4124564, 0.2126729, 0.0193339,
+        0.3575761, 0.7151522, 0.1191920,
+        0.1804375, 0.0721750, 0.9503041);
+    vec3 xyz = to_xyz * max(rgb, 0.0) / vec3(0.95047, 1.0, 1.08883);
+    bvec3 cubic = greaterThan(xyz, vec3(216.0 / 24389.0));
+    vec3 f = mix((24389.0 / 27.0 * xyz + 16.0) / 116.0, pow(xyz, vec3(1.0 / 3.0)), cubic);
+    return vec3(116.0 * f.y - 16.0, 500.0 * (f.x - f.y), 200.0 * (f.y - f.z));
+}
+
+// The hue angle of a and b in degrees, in the range [0, 360).
+float hue_angle(float b, float a) {
+    if (a == 0.0 && b == 0.0) {
+        return 0.0;
+    }
+    float h = degrees(atan(b, a));
+    return h < 0.0 ? h + 360.0 : h;
+}
+
+// The CIEDE2000 color difference, following Sharma et al., with unit weighting factors.
+float ciede2000(vec3 lab1, vec3 lab2) {
+    float c1 = length(lab1.yz);
+    float c2 = length(lab2.yz);
+    float c_mean7 = pow((c1 + c2) / 2.0, 7.0);
+    float g = 0.5 * (1.0 - sqrt(c_mean7 / (c_mean7 + 6103515625.0)));
+    vec2 ab1 = vec2(lab1.y * (1.0 + g), lab1.z);
+    vec2 ab2 = vec2(lab2.y * (1.0 + g), lab2.z);
+    float cp1 = length(ab1);
+    float cp2 = length(ab2);
+    float hp1 = hue_angle(ab1.y, ab1.x);
+    float hp2 = hue_angle(ab2.y, ab2.x);
+
+    float dl = lab2.x - lab1.x;
+    float dc = cp2 - cp1;
+    float dhp = 0.0;
+    if (cp1 * cp2 != 0.0) {
+        dhp = hp2 - hp1;
+        dhp -= dhp > 180.0 ? 360.0 : (dhp < -180.0 ? -360.0 : 0.0);
+    }
+    float dh = 2.0 * sqrt(cp1 * cp2) * sin(radians(dhp) / 2.0);
+
+    float l_mean = (lab1.x + lab2.x) / 2.0;
+    float cp_mean = (cp1 + cp2) / 2.0;
+    float hp_mean = hp1 + hp2;
+    if (cp1 * cp2 != 0.0) {
+        hp_mean = abs(hp1 - hp2) <= 180.0 ? hp_mean / 2.0
+            : (hp_mean < 360.0 ? (hp_mean + 360.0) / 2.0 : (hp_mean - 360.0) / 2.0);
+    }
+
+    float t = 1.0 - 0.17 * cos(radians(hp_mean - 30.0)) + 0.24 * cos(radians(2.0 * hp_mean))
+        + 0.32 * cos(radians(3.0 * hp_mean + 6.0)) - 0.20 * cos(radians(4.0 * hp_mean - 63.0));
+    float l_offset = (l_mean - 50.0) * (l_mean - 50.0);
+    float sl = 1.0 + 0.015 * l_offset / sqrt(20.0 + l_offset);
+    float sc = 1.0 + 0.045 * cp_mean;
+    float sh = 1.0 + 0.015 * cp_mean * t;
+    float cp_mean7 = pow(cp_mean, 7.0);
+    // pow is undefined for negative bases, so the hue offset is squared by hand.
+    float hue_offset = (hp_mean - 275.0) / 25.0;
+    float rt = -2.0 * sqrt(cp_mean7 / (cp_mean7 + 6103515625.0))
+        * sin(radians(60.0 * exp(-hue_offset * hue_offset)));
+
+    vec3 terms = vec3(dl / sl, dc / sc, dh / sh);
+    return sqrt(dot(terms, terms) + rt * terms.y * terms.z);
+}
+
+vec4 delta_e() {
+    vec2 uv = sample_position();
+    if (out_of_bounds(uv)) {
+        return background();
+    }
+
+    vec4 a = fetch_input(0, uv);
+    vec4 b = fetch_input(1, uv);
+    if ((pc.flags & FLAG_HIGHLIGHT_NON_FINITE) != 0 && (is_non_finite(a) || is_non_finite(b))) {
+        return unpackUnorm4x8(pc.non_finite_color);
+    }
+    // Values above one are clipped like on a display, Lab is only defined up to white.
+    float difference = ciede2000(linear_srgb_to_lab(min(a.rgb, 1.0)),
+        linear_srgb_to_lab(min(b.rgb, 1.0)));
+    return vec4(vec3(difference / max(pc.mode_parameter, 1e-6)), 1.0);
+}
+
+vec4 mask() {
+    vec2 uv = sample_position();
+    vec4 a = sample_input(0, uv);
+    vec4 b = sample_input(1, uv);
+    // Outside of the mask, the first input shows.
+    if (out_of_bounds(align_input(eye_input(2), uv))) {
+        return a;
+    }
+    return mix(a, b, clamp(fetch_input(2, uv).r, 0.0, 1.0));
+}
+
+vec4 onion() {
+    vec2 uv = sample_position();
+    return mix(sample_input(0, uv), sample_input(1, uv), pc.mode_parameter);
+}
+
+vec4 difference() {
+    vec2 uv = sample_position();
+    if (out_of_bounds(uv)) {
+        return background();
+    }
+
+    vec4 a = fetch_input(0, uv);
+    vec4 b = fetch_input(1, uv);
+    if ((pc.flags & FLAG_HIGHLIGHT_NON_FINITE) != 0 && (is_non_finite(a) || is_non_finite(b))) {
+        return unpackUnorm4x8(pc.non_finite_color);
+    }
+    vec4 difference = isolate_channel(abs(a - b) * pc.mode_parameter);
+    return vec4(difference.rgb, 1.0);
+}
+
+vec4 checkerboard() {
+    // Tiles in screen space, so that they keep their size when zooming.
+    ivec2 tile = ivec2(floor(gl_FragCoord.xy / max(pc.mode_parameter, 1.0)));
+    // The input array may only be indexed uniformly, so both inputs are sampled.
+    vec2 uv = sample_position();
+    vec4 a = sample_input(0, uv);
+    vec4 b = sample_input(1, uv);
+    return (tile.x + tile.y) % 2 == 0 ? a : b;
+}
+
+vec4 flicker() {
+    // Toggled by a push constant only, so that flickering needs no pipeline changes.
+    uint index = pc.mode_parameter > 0.5 ? 1 : 0;
+    return sample_input(index, sample_position());
+}
+
+vec4 split() {
+    // Each strip is a draw of its own, scissored to the strip between the divider positions.
+    // Neighbouring strips each draw half of the divider between them.
+    float half_width = pc.divider_width / 2.0;
+    if ((pc.divider > 0.0 && texPosition.x < pc.divider + half_width) ||
+        (pc.divider_y < 1.0 && texPosition.x > pc.divider_y - half_width)) {
+        return unpackUnorm4x8(pc.divider_color);
+    }
+
+    // The strip index is uniform across the draw, so it may index the input array.
+    return sample_input(cell_index(), sample_position());
+}
+
+vec4 grid() {
+    // Each cell is drawn separately, with its own viewport. The border widths are relative
+    // to the cell and doubled for the highlighted one.
+    bool highlighted = (pc.flags & FLAG_HIGHLIGHTED) != 0;
+    vec2 border = vec2(pc.divider_width, divider_height()) * (highlighted ? 2.0 : 1.0);
+    if (any(lessThan(texPosition, border)) || any(greaterThan(texPosition, 1.0 - border))) {
+        return unpackUnorm4x8(highlighted ? pc.highlight_color : pc.divider_color);
+    }
+
+    // The cell index is uniform across the draw, so it may index the input array.
+    return sample_input(cell_index(), sample_position());
+}
+
 void main() {
+    // Taken while all invocations are still active.
+    pixel_size = vec2(dFdx(texPosition.x), dFdy(texPosition.y));
+    position = texPosition;
 
-    if (texPosition.x < pc.divider - pc.divider_width / 2.0) {
-        outColor = texture(leftImage, texPosition);
-    } else if (texPosition.x > pc.divider + pc.divider_width / 2.0) {
-        outColor = texture(rightImage, texPosition);
+#ifdef STATS
+    // Every output pixel counts once: grid cells other than the first show other inputs.
+    if ((pc.flags & FLAG_DIFFERENCE_OUTPUT) != 0) {
+        accumulate_stats(texPosition);
+    } else if (MODE != MODE_GRID || cell_index() == 0) {
+        accumulate_stats(sample_position());
+    }
+    if ((pc.flags & FLAG_DIFFERENCE_OUTPUT) == 0 && MODE == MODE_EXACT &&
+        texels_differ(sample_position())) {
+        atomicAdd(stats.mismatches, 1);
+    }
+#endif
+
+    // Pure data for further passes: no transform and no decoration.
+    if ((pc.flags & FLAG_DIFFERENCE_OUTPUT) != 0) {
+        vec4 difference = fetch_input(0, texPosition) - fetch_input(1, texPosition);
+        outColor = (pc.flags & FLAG_SIGNED_DIFFERENCE) != 0 ? difference : abs(difference);
+        return;
+    }
+
+    // Within the magnifier, the area around its center is drawn enlarged, divider included.
+    vec2 magnifier = unpackHalf2x16(pc.magnifier);
+    vec2 from_center = gl_FragCoord.xy - pc.magnifier_center;
+    float center_distance = length(from_center);
+    if (center_distance < magnifier.x) {
+        position -= from_center * (1.0 - 1.0 / magnifier.y) * pixel_size;
+    }
+
+    if (MODE == MODE_GRID) {
+        outColor = grid();
+    } else if (MODE == MODE_EXACT) {
+        outColor = exact();
+    } else if (MODE == MODE_THRESHOLD) {
+        outColor = threshold();
+    } else if (MODE == MODE_DELTA_E) {
+        outColor = delta_e();
+    } else if (MODE == MODE_MASK) {
+        outColor = mask();
+    } else if (MODE == MODE_ONION) {
+        outColor = onion();
+    } else if (MODE == MODE_DIFFERENCE) {
+        outColor = difference();
+    } else if (MODE == MODE_CHECKERBOARD) {
+        outColor = checkerboard();
+    } else if (MODE == MODE_FLICKER) {
+        outColor = flicker();
+    } else if (MODE == MODE_SPLIT) {
+        outColor = split();
+    } else if (MODE == MODE_QUAD) {
+        outColor = quad();
     } else {
-        outColor = pc.divider_color;
+        outColor = wipe();
+    }
+
+    // A ring in the divider color sets the magnifier apart from its surroundings.
+    if (magnifier.x > 0.0 && abs(center_distance - magnifier.x) < 1.0) {
+        outColor = unpackUnorm4x8(pc.divider_color);
+    }
+
+    if ((pc.flags & FLAG_ENCODE_SRGB) != 0) {
+        outColor = encode_output(outColor);
     }
-}
\ No newline at end of file
+}
@@ -1,6 +1,12 @@
 #version 460
 
+#ifdef MULTIVIEW
+#extension GL_EXT_multiview : require
+#endif
+
 layout (location = 0) out vec2 texPosition;
+// The eye being drawn with multiview, zero otherwise.
+layout (location = 1) flat out uint view;
 
 void main() {
 
@@ -20,4 +26,9 @@ void main() {
 
     gl_Position = vec4(positions[gl_VertexIndex], 0.0, 1.0);
     texPosition = uv[gl_VertexIndex];
+#ifdef MULTIVIEW
+    view = gl_ViewIndex;
+#else
+    view = 0;
+#endif
 }
\ No newline at end of file
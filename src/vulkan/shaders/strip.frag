@@ -0,0 +1,30 @@
+#version 460
+
+layout (binding = 0) uniform sampler2D inputs[2];
+
+layout (push_constant) uniform StripPC {
+    // Sort the types descending by size to avoid alignment issues
+    vec4 marker_color;
+    float gain;
+    // Playhead position and marker width, relative to the whole strip.
+    float playhead;
+    float marker_width;
+    // Horizontal range of the current thumbnail cell, relative to the whole strip.
+    float cell_start;
+    float cell_end;
+} pc;
+
+layout (location = 0) in vec2 texPosition;
+
+layout (location = 0) out vec4 outColor;
+
+void main() {
+    float strip_x = mix(pc.cell_start, pc.cell_end, texPosition.x);
+
+    if (abs(strip_x - pc.playhead) <= pc.marker_width / 2.0) {
+        outColor = pc.marker_color;
+    } else {
+        vec3 difference = abs(texture(inputs[0], texPosition).rgb - texture(inputs[1], texPosition).rgb);
+        outColor = vec4(clamp(difference * pc.gain, 0.0, 1.0), 1.0);
+    }
+}
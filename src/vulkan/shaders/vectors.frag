@@ -0,0 +1,11 @@
+#version 460
+
+layout (push_constant) uniform VectorPC {
+    vec4 color;
+} pc;
+
+layout (location = 0) out vec4 outColor;
+
+void main() {
+    outColor = pc.color;
+}
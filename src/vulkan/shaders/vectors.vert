@@ -0,0 +1,54 @@
+#version 460
+
+layout (binding = 0) uniform sampler2D inputs[8];
+
+layout (push_constant) uniform VectorPC {
+    vec4 color;
+    // Offset of the sampled area, in texture coordinates.
+    vec2 pan;
+    vec2 viewport_size;
+    float zoom;
+    // Pixels per unit of vector difference.
+    float scale;
+    // The grid of arrows, one instance per cell.
+    uint columns;
+    uint rows;
+} pc;
+
+// The arrow is a list of three lines: the shaft, then both sides of the head. Every odd
+// vertex is the tip.
+const float HEAD_ANGLE = 0.5;
+const float MAX_HEAD_LENGTH = 6.0;
+
+void main() {
+    uint cell = gl_InstanceIndex;
+    vec2 grid = vec2(pc.columns, pc.rows);
+    vec2 center = (vec2(cell % pc.columns, cell / pc.columns) + 0.5) / grid;
+
+    // Arrows sample where the comparison does, and vanish outside the inputs.
+    vec2 uv = (center - 0.5) / pc.zoom + 0.5 + pc.pan;
+    vec2 difference = vec2(0.0);
+    if (all(greaterThanEqual(uv, vec2(0.0))) && all(lessThanEqual(uv, vec2(1.0)))) {
+        difference = textureLod(inputs[1], uv, 0.0).xy - textureLod(inputs[0], uv, 0.0).xy;
+    }
+
+    // Laid out in pixels, so that the head keeps its shape on non-square outputs.
+    vec2 start = center * pc.viewport_size;
+    vec2 shaft = difference * pc.scale;
+    vec2 tip = start + shaft;
+
+    vec2 position = tip;
+    if (gl_VertexIndex == 0) {
+        position = start;
+    } else if (gl_VertexIndex % 2 == 0) {
+        float shaft_length = length(shaft);
+        vec2 back = shaft_length > 0.0
+            ? -shaft / shaft_length * min(shaft_length * 0.3, MAX_HEAD_LENGTH)
+            : vec2(0.0);
+        float angle = gl_VertexIndex == 2 ? HEAD_ANGLE : -HEAD_ANGLE;
+        mat2 rotation = mat2(cos(angle), sin(angle), -sin(angle), cos(angle));
+        position = tip + rotation * back;
+    }
+
+    gl_Position = vec4(position / pc.viewport_size * 2.0 - 1.0, 0.0, 1.0);
+}
@@ -0,0 +1,164 @@
+//! Comparing multi-planar YCbCr inputs, such as NV12 or P010 video frames.
+//!
+//! Multi-planar images can only be sampled through a sampler YCbCr conversion, which
+//! converts to RGB as part of the sampling. A [`YcbcrConversion`] holds the conversion and
+//! the sampler using it. The comparator is created with it in
+//! [`RenderTargetComparatorCreateInfo::ycbcr_conversion`], which makes that sampler an
+//! immutable sampler of its input descriptor set layout, and the input views must be
+//! created with [`YcbcrConversion::create_view`].
+//!
+//! Requires the `samplerYcbcrConversion` device feature, and a format supporting it, see
+//! `VK_FORMAT_FEATURE_MIDPOINT_CHROMA_SAMPLES_BIT` and its siblings. The converted inputs
+//! are filtered by the sampler, so features reading unfiltered texels are not available:
+//! [`CompareMode::Exact`], [`CompareInfo::nearest`], pixel reads and the quality metrics.
+//!
+//! [`RenderTargetComparatorCreateInfo::ycbcr_conversion`]:
+//!     crate::RenderTargetComparatorCreateInfo::ycbcr_conversion
+//! [`CompareMode::Exact`]: crate::CompareMode::Exact
+//! [`CompareInfo::nearest`]: crate::CompareInfo::nearest
+
+use anyhow::Result;
+use std::sync::Arc;
+use vulkanalia::prelude::v1_3::*;
+
+/// The amount of descriptors a combined image sampler with a YCbCr conversion may take
+/// in a descriptor pool, one per plane. Drivers report their actual amount per format in
+/// `combinedImageSamplerDescriptorCount`, which is at most this.
+pub const MAX_YCBCR_DESCRIPTOR_COUNT: u32 = 3;
+
+/// How the values of a multi-planar format are converted to RGB.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct YcbcrConversionInfo {
+    /// The multi-planar format of the inputs, e.g. `G8_B8R8_2PLANE_420_UNORM` for NV12 or
+    /// `G10X6_B10X6R10X6_2PLANE_420_UNORM_3PACK16` for P010.
+    pub format: vk::Format,
+    /// The color model, i.e. the matrix converting YCbCr to RGB.
+    pub model: vk::SamplerYcbcrModelConversion,
+    /// Whether the values use the full range of the encoding, or the narrow one of video.
+    pub range: vk::SamplerYcbcrRange,
+    /// Where the subsampled chroma samples lie relative to the luma ones, horizontally.
+    pub x_chroma_offset: vk::ChromaLocation,
+    /// Where the subsampled chroma samples lie relative to the luma ones, vertically.
+    pub y_chroma_offset: vk::ChromaLocation,
+    /// The filter reconstructing chroma between its samples, and also the filter of the
+    /// sampler. `LINEAR` requires the format to support linear filtering.
+    pub chroma_filter: vk::Filter,
+}
+
+impl YcbcrConversionInfo {
+    /// The conversion of video frames of a format: BT.709 in the narrow range, with the
+    /// chroma samples cosited horizontally and between the rows vertically.
+    pub fn new(format: vk::Format) -> Self {
+        Self {
+            format,
+            model: vk::SamplerYcbcrModelConversion::YCBCR_709,
+            range: vk::SamplerYcbcrRange::ITU_NARROW,
+            x_chroma_offset: vk::ChromaLocation::COSITED_EVEN,
+            y_chroma_offset: vk::ChromaLocation::MIDPOINT,
+            chroma_filter: vk::Filter::LINEAR,
+        }
+    }
+}
+
+/// A sampler YCbCr conversion and the sampler using it. Destroys both when dropped, so it
+/// must outlive the comparators and views using it.
+#[derive(Debug)]
+pub struct YcbcrConversion {
+    device: Arc<Device>,
+    conversion: vk::SamplerYcbcrConversion,
+    sampler: vk::Sampler,
+    format: vk::Format,
+}
+
+impl Drop for YcbcrConversion {
+    fn drop(&mut self) {
+        unsafe {
+            self.device.destroy_sampler(self.sampler, None);
+            self.device
+                .destroy_sampler_ycbcr_conversion(self.conversion, None);
+        }
+    }
+}
+
+impl YcbcrConversion {
+    /// Creates the conversion and its sampler.
+    pub fn new(device: &Arc<Device>, info: &YcbcrConversionInfo) -> Result<Self> {
+        let conversion_info = vk::SamplerYcbcrConversionCreateInfo::builder()
+            .format(info.format)
+            .ycbcr_model(info.model)
+            .ycbcr_range(info.range)
+            .components(vk::ComponentMapping::default())
+            .x_chroma_offset(info.x_chroma_offset)
+            .y_chroma_offset(info.y_chroma_offset)
+            .chroma_filter(info.chroma_filter)
+            .force_explicit_reconstruction(false)
+            .build();
+        let conversion = unsafe { device.create_sampler_ycbcr_conversion(&conversion_info, None) }?;
+
+        // Owned right away, so that everything created so far is released on error.
+        let mut ycbcr = Self {
+            device: Arc::clone(device),
+            conversion,
+            sampler: vk::Sampler::null(),
+            format: info.format,
+        };
+
+        // Samplers with a conversion must clamp to the edge, without anisotropy, and filter
+        // like the conversion unless the format supports separate filters.
+        let mut sampler_conversion_info = vk::SamplerYcbcrConversionInfo::builder()
+            .conversion(conversion)
+            .build();
+        let sampler_info = vk::SamplerCreateInfo::builder()
+            .address_mode_u(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+            .address_mode_v(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+            .address_mode_w(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+            .anisotropy_enable(false)
+            .min_filter(info.chroma_filter)
+            .mag_filter(info.chroma_filter)
+            .unnormalized_coordinates(false)
+            .compare_enable(false)
+            .mipmap_mode(vk::SamplerMipmapMode::NEAREST)
+            .min_lod(0.0)
+            .max_lod(vk::LOD_CLAMP_NONE)
+            .push_next(&mut sampler_conversion_info)
+            .build();
+        ycbcr.sampler = unsafe { device.create_sampler(&sampler_info, None) }?;
+
+        Ok(ycbcr)
+    }
+
+    /// Creates a view of a whole single-layer image of the conversion's format, to compare
+    /// as an input. The caller owns the view and must destroy it.
+    pub fn create_view(&self, image: vk::Image) -> Result<vk::ImageView> {
+        let mut conversion_info = vk::SamplerYcbcrConversionInfo::builder()
+            .conversion(self.conversion)
+            .build();
+        let subresource_range = vk::ImageSubresourceRange::builder()
+            .aspect_mask(vk::ImageAspectFlags::COLOR)
+            .base_mip_level(0)
+            .level_count(1)
+            .base_array_layer(0)
+            .layer_count(1)
+            .build();
+        let info = vk::ImageViewCreateInfo::builder()
+            .image(image)
+            .view_type(vk::ImageViewType::_2D)
+            .format(self.format)
+            .subresource_range(subresource_range)
+            .push_next(&mut conversion_info)
+            .build();
+
+        let image_view = unsafe { self.device.create_image_view(&info, None) }?;
+        Ok(image_view)
+    }
+
+    /// Returns the sampler using the conversion.
+    pub fn sampler(&self) -> vk::Sampler {
+        self.sampler
+    }
+
+    /// Returns the multi-planar format the conversion is for.
+    pub fn format(&self) -> vk::Format {
+        self.format
+    }
+}